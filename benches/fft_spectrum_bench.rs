@@ -52,6 +52,22 @@ fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("spectrum with multiple scaling steps", |b| {
         b.iter(|| spectrum_with_multiple_scaling(black_box(&hann_window)))
     });
+
+    // Applying a scaling recalculates the statistics (min, max, average,
+    // median) of the spectrum, which dominates for large spectra.
+    let samples = (0..16384)
+        .map(|_| rand::random::<i16>())
+        .map(|x| x as f32)
+        .collect::<Vec<_>>();
+    let mut spectrum = spectrum_without_scaling(&windows::hann_window(&samples));
+    let mut working_buffer = vec![(0.0.into(), 0.0.into()); spectrum.data().len()];
+    c.bench_function("statistics of a spectrum with 8193 bins", |b| {
+        b.iter(|| {
+            spectrum
+                .apply_scaling_fn(&|val, _stats| black_box(val), &mut working_buffer)
+                .unwrap()
+        })
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -23,21 +23,21 @@ SOFTWARE.
 */
 use audio_visualizer::dynamic::live_input::AudioDevAndCfg;
 use audio_visualizer::dynamic::window_top_btm::{TransformFn, open_window_connect_audio};
+use spectrum_analyzer::averaging::{AveragingMode, SpectrumAverager};
 use spectrum_analyzer::scaling::divide_by_N;
 use spectrum_analyzer::windows::hann_window;
-use spectrum_analyzer::{FrequencyLimit, FrequencyValue, samples_fft_to_spectrum};
+use spectrum_analyzer::{FrequencyLimit, samples_fft_to_spectrum};
 use std::cell::RefCell;
-use std::cmp::max;
 
 /// Example that creates a live visualization of the frequency spectrum of realtime audio data
 /// **Execute this with `--release`, otherwise it is very laggy!**.
 fn main() {
-    // Contains the data for the spectrum to be visualized. It contains ordered pairs of
-    // `(frequency, frequency_value)`. During each iteration, the frequency value gets
-    // combined with `max(old_value * smoothing_factor, new_value)`.
-    let visualize_spectrum: RefCell<Vec<(f64, f64)>> = RefCell::new(vec![(0.0, 0.0); 1024]);
+    // Smoothens the spectrum to be visualized. During each iteration, the frequency value
+    // gets combined with `max(old_value * decay, new_value)`.
+    let averager =
+        RefCell::new(SpectrumAverager::new(AveragingMode::PeakHold { decay: 0.84 }).unwrap());
 
-    // Closure that captures `visualize_spectrum`.
+    // Closure that captures `averager`.
     let to_spectrum_fn = move |audio: &[f32], sampling_rate| {
         let skip_elements = audio.len() - 2048;
         // spectrum analysis only of the latest 46ms
@@ -55,22 +55,16 @@ fn main() {
 
         // now smoothen the spectrum; old values are decreased a bit and replaced,
         // if the new value is higher
-        latest_spectrum
+        let mut averager = averager.borrow_mut();
+        averager.add(&latest_spectrum).unwrap();
+
+        averager
+            .spectrum()
+            .unwrap()
             .data()
             .iter()
-            .zip(visualize_spectrum.borrow_mut().iter_mut())
-            .for_each(|((fr_new, fr_val_new), (fr_old, fr_val_old))| {
-                // actually only required in very first iteration
-                *fr_old = fr_new.val() as f64;
-                let old_val = *fr_val_old * 0.84;
-                let max = max(
-                    *fr_val_new * 5000.0_f32.into(),
-                    FrequencyValue::from(old_val as f32),
-                );
-                *fr_val_old = max.val() as f64;
-            });
-
-        visualize_spectrum.borrow().clone()
+            .map(|(fr, fr_val)| (fr.val() as f64, fr_val.val() as f64 * 5000.0))
+            .collect::<Vec<_>>()
     };
 
     open_window_connect_audio(
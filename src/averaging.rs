@@ -0,0 +1,370 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the struct [`SpectrumAverager`], which combines successive
+//! spectra, e.g. of a live audio stream, into a smoothed spectrum.
+
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::spectrum::FrequencySpectrum;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+
+/// How a [`SpectrumAverager`] combines successive spectra.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AveragingMode {
+    /// Arithmetic mean of the last `frames` spectra.
+    Linear {
+        /// Number of spectra to average. Must be at least one.
+        frames: usize,
+    },
+    /// Exponential moving average. Each new spectrum is weighted with
+    /// `1 - exp(-frame_interval / time_constant)`.
+    Exponential {
+        /// Time constant in seconds. Must be positive.
+        time_constant: f32,
+        /// Time between two successive spectra in seconds, e.g. the hop size
+        /// divided by the sampling rate. Must be positive.
+        frame_interval: f32,
+    },
+    /// Keeps the maximum of each frequency value. Held values decay by being
+    /// multiplied with `decay` for each new spectrum, i.e. the held value is
+    /// `max(new, old * decay)`. This expects linear, not negative values.
+    PeakHold {
+        /// Decay factor in `[0.0; 1.0]`. `1.0` holds the peaks forever.
+        decay: f32,
+    },
+    /// Keeps the minimum of each frequency value.
+    MinHold,
+}
+
+impl AveragingMode {
+    /// Verifies that the mode has sane parameters.
+    fn verify(&self) -> Result<(), AveragingError> {
+        let valid = match self {
+            Self::Linear { frames } => *frames > 0,
+            Self::Exponential {
+                time_constant,
+                frame_interval,
+            } => {
+                time_constant.is_finite()
+                    && *time_constant > 0.0
+                    && frame_interval.is_finite()
+                    && *frame_interval > 0.0
+            }
+            Self::PeakHold { decay } => (0.0..=1.0).contains(decay),
+            Self::MinHold => true,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(AveragingError::InvalidMode(*self))
+        }
+    }
+}
+
+/// Stateful averaging of successive [`FrequencySpectrum`]s that all have the
+/// same frequencies, e.g. spectra of a live audio stream.
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::averaging::{AveragingMode, SpectrumAverager};
+/// use spectrum_analyzer::{samples_fft_to_spectrum, FrequencyLimit};
+///
+/// let mut averager = SpectrumAverager::new(AveragingMode::PeakHold { decay: 0.84 }).unwrap();
+/// for samples in [[0.0, 1.0, 0.0, -1.0], [0.0, 0.5, 0.0, -0.5]] {
+///     let spectrum = samples_fft_to_spectrum(&samples, 44100, FrequencyLimit::All, None).unwrap();
+///     averager.add(&spectrum).unwrap();
+/// }
+/// let smoothed = averager.spectrum().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SpectrumAverager {
+    /// How the spectra are combined.
+    mode: AveragingMode,
+    /// Frequencies of all added spectra. Empty before the first spectrum.
+    frequencies: Vec<Frequency>,
+    /// Frequency resolution of the first spectrum.
+    frequency_resolution: f32,
+    /// Number of samples of the latest spectrum.
+    samples_len: u32,
+    /// Current averaged value of each frequency.
+    values: Vec<f32>,
+    /// Ring buffer with the values of the last spectra for
+    /// [`AveragingMode::Linear`], one spectrum after another.
+    history: Vec<f32>,
+    /// Number of spectra added since the creation or the last reset.
+    frames: usize,
+}
+
+impl SpectrumAverager {
+    /// Creates a new averager without any spectra.
+    pub fn new(mode: AveragingMode) -> Result<Self, AveragingError> {
+        mode.verify()?;
+        Ok(Self {
+            mode,
+            frequencies: Vec::new(),
+            frequency_resolution: 0.0,
+            samples_len: 0,
+            values: Vec::new(),
+            history: Vec::new(),
+            frames: 0,
+        })
+    }
+
+    /// Returns how the spectra are combined.
+    #[inline]
+    #[must_use]
+    pub const fn mode(&self) -> AveragingMode {
+        self.mode
+    }
+
+    /// Returns the number of spectra added since the creation or the last
+    /// reset.
+    #[inline]
+    #[must_use]
+    pub const fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Forgets all added spectra. Afterward, spectra with different
+    /// frequencies can be added.
+    pub fn reset(&mut self) {
+        self.frequencies.clear();
+        self.values.clear();
+        self.history.clear();
+        self.frames = 0;
+    }
+
+    /// Adds a spectrum. All spectra must have the same frequencies as the
+    /// first one. Otherwise, [`AveragingError::LayoutMismatch`] is returned.
+    pub fn add(&mut self, spectrum: &FrequencySpectrum) -> Result<(), AveragingError> {
+        let data = spectrum.data();
+        if self.frames == 0 {
+            self.frequencies.clear();
+            self.frequencies.extend(data.iter().map(|(fr, _)| *fr));
+            self.frequency_resolution = spectrum.frequency_resolution();
+            self.values.clear();
+            self.values
+                .extend(data.iter().map(|(_, fr_val)| fr_val.val()));
+        } else if self.frequencies.len() != data.len()
+            || self
+                .frequencies
+                .iter()
+                .zip(data)
+                .any(|(fr, (new_fr, _))| fr != new_fr)
+        {
+            return Err(AveragingError::LayoutMismatch);
+        }
+        self.samples_len = spectrum.samples_len();
+        let new_values = data.iter().map(|(_, fr_val)| fr_val.val());
+
+        match self.mode {
+            AveragingMode::Linear { frames } => {
+                let bins = self.values.len();
+                if self.history.len() < frames * bins {
+                    // fill the ring buffer
+                    self.history.extend(new_values);
+                } else {
+                    // overwrite the oldest spectrum
+                    let oldest = self.frames % frames;
+                    for (old, new) in self.history[oldest * bins..(oldest + 1) * bins]
+                        .iter_mut()
+                        .zip(new_values)
+                    {
+                        *old = new;
+                    }
+                }
+
+                // Sum up from scratch so that no rounding errors accumulate.
+                let count = self.history.len() / bins;
+                for (i, value) in self.values.iter_mut().enumerate() {
+                    let sum = (0..count)
+                        .map(|frame| self.history[frame * bins + i])
+                        .sum::<f32>();
+                    *value = sum / count as f32;
+                }
+            }
+            AveragingMode::Exponential {
+                time_constant,
+                frame_interval,
+            } => {
+                if self.frames > 0 {
+                    let alpha = 1.0 - libm::expf(-frame_interval / time_constant);
+                    for (value, new) in self.values.iter_mut().zip(new_values) {
+                        *value += alpha * (new - *value);
+                    }
+                }
+            }
+            AveragingMode::PeakHold { decay } => {
+                for (value, new) in self.values.iter_mut().zip(new_values) {
+                    let decayed = *value * decay;
+                    *value = if new > decayed { new } else { decayed };
+                }
+            }
+            AveragingMode::MinHold => {
+                for (value, new) in self.values.iter_mut().zip(new_values) {
+                    if new < *value {
+                        *value = new;
+                    }
+                }
+            }
+        }
+
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Returns the averaged spectrum or `None`, if no spectrum was added yet.
+    #[must_use]
+    pub fn spectrum(&self) -> Option<FrequencySpectrum> {
+        if self.frames == 0 {
+            return None;
+        }
+        let data = self
+            .frequencies
+            .iter()
+            .zip(&self.values)
+            .map(|(fr, val)| (*fr, FrequencyValue::from(*val)))
+            .collect::<Vec<_>>();
+        let mut working_buffer = vec![(0.0.into(), 0.0.into()); data.len()];
+        Some(FrequencySpectrum::new(
+            data,
+            self.frequency_resolution,
+            self.samples_len,
+            &mut working_buffer,
+        ))
+    }
+}
+
+/// Possible errors of a [`SpectrumAverager`].
+#[derive(Debug)]
+pub enum AveragingError {
+    /// The [`AveragingMode`] has invalid parameters.
+    InvalidMode(AveragingMode),
+    /// The frequencies of the spectrum don't match the frequencies of the
+    /// previously added spectra.
+    LayoutMismatch,
+}
+
+impl Display for AveragingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidMode(mode) => write!(f, "Invalid averaging mode: {mode:?}"),
+            Self::LayoutMismatch => write!(f, "Frequencies of the spectra don't match!"),
+        }
+    }
+}
+
+impl Error for AveragingError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spectrum(values: &[f32]) -> FrequencySpectrum {
+        let mut data = values
+            .iter()
+            .enumerate()
+            .map(|(i, val)| ((i as f32 * 10.0).into(), (*val).into()))
+            .collect::<Vec<(Frequency, FrequencyValue)>>();
+        FrequencySpectrum::new(data.clone(), 10.0, 2 * data.len() as u32, &mut data)
+    }
+
+    fn values(averager: &SpectrumAverager) -> Vec<f32> {
+        averager
+            .spectrum()
+            .unwrap()
+            .data()
+            .iter()
+            .map(|(_, val)| val.val())
+            .collect()
+    }
+
+    #[test]
+    fn test_linear() {
+        let mut averager = SpectrumAverager::new(AveragingMode::Linear { frames: 2 }).unwrap();
+        assert!(averager.spectrum().is_none());
+
+        averager.add(&spectrum(&[1.0, 2.0])).unwrap();
+        assert_eq!(values(&averager), [1.0, 2.0]);
+        averager.add(&spectrum(&[3.0, 4.0])).unwrap();
+        assert_eq!(values(&averager), [2.0, 3.0]);
+        // the first spectrum drops out
+        averager.add(&spectrum(&[5.0, 6.0])).unwrap();
+        assert_eq!(values(&averager), [4.0, 5.0]);
+        assert_eq!(averager.frames(), 3);
+    }
+
+    #[test]
+    fn test_exponential() {
+        let mut averager = SpectrumAverager::new(AveragingMode::Exponential {
+            time_constant: 1.0,
+            frame_interval: core::f32::consts::LN_2,
+        })
+        .unwrap();
+
+        averager.add(&spectrum(&[0.0, 4.0])).unwrap();
+        assert_eq!(values(&averager), [0.0, 4.0]);
+        // weight of new spectrum is 1 - exp(-ln(2)) = 0.5
+        averager.add(&spectrum(&[4.0, 0.0])).unwrap();
+        let values = values(&averager);
+        float_cmp::assert_approx_eq!(f32, values[0], 2.0, epsilon = 0.0001);
+        float_cmp::assert_approx_eq!(f32, values[1], 2.0, epsilon = 0.0001);
+    }
+
+    #[test]
+    fn test_peak_and_min_hold() {
+        let mut peak = SpectrumAverager::new(AveragingMode::PeakHold { decay: 0.5 }).unwrap();
+        let mut min = SpectrumAverager::new(AveragingMode::MinHold).unwrap();
+        for frame in [[8.0, 1.0], [1.0, 2.0], [1.0, 0.5]] {
+            peak.add(&spectrum(&frame)).unwrap();
+            min.add(&spectrum(&frame)).unwrap();
+        }
+        assert_eq!(values(&peak), [2.0, 1.0]);
+        assert_eq!(values(&min), [1.0, 0.5]);
+    }
+
+    #[test]
+    fn test_errors() {
+        SpectrumAverager::new(AveragingMode::Linear { frames: 0 }).unwrap_err();
+        SpectrumAverager::new(AveragingMode::PeakHold { decay: 1.1 }).unwrap_err();
+        SpectrumAverager::new(AveragingMode::Exponential {
+            time_constant: 0.0,
+            frame_interval: 1.0,
+        })
+        .unwrap_err();
+
+        let mut averager = SpectrumAverager::new(AveragingMode::MinHold).unwrap();
+        averager.add(&spectrum(&[1.0, 2.0])).unwrap();
+        assert!(matches!(
+            averager.add(&spectrum(&[1.0, 2.0, 3.0])),
+            Err(AveragingError::LayoutMismatch)
+        ));
+
+        // different layout is fine after a reset
+        averager.reset();
+        averager.add(&spectrum(&[1.0, 2.0, 3.0])).unwrap();
+        assert_eq!(values(&averager), [1.0, 2.0, 3.0]);
+    }
+}
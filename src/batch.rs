@@ -0,0 +1,412 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+//! Module for the struct [`BatchAnalyzer`], which calculates the spectra of
+//! many frames in parallel with `rayon`, e.g. of hours of recordings.
+
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::{Complex32, DefaultFftBackend, Fft, FftBackend, FftImpl, MAX_FFT_LEN};
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::limit::FrequencyLimit;
+use crate::output::SpectrumOutput;
+use crate::scaling::ScalingPipeline;
+use crate::spectrum::FrequencySpectrum;
+use alloc::vec::Vec;
+use rayon::prelude::*;
+
+/// Analyzes many frames of the same length in parallel.
+///
+/// The frames are either given as a slice, see [`Self::analyze_frames`], or
+/// cut out of a long signal with a hop size, see [`Self::analyze_signal`].
+/// Each frame is analyzed on its own, so the spectra are deterministic and in
+/// the order of the frames, independent of the number of threads. The
+/// buffers of the FFT are allocated once per rayon job and reused for all
+/// frames of that job. Rayon splits the frames into a few jobs per thread.
+///
+/// The FFTs are calculated with the [`DefaultFftBackend`], unless another
+/// [`FftBackend`] is set with [`Self::with_backend`]. Each rayon job uses its
+/// own copy of the backend, which is prepared for [`Self::frame_len`].
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::batch::BatchAnalyzer;
+/// use spectrum_analyzer::windows::hann_window;
+/// use spectrum_analyzer::FrequencyLimit;
+///
+/// // 2048 samples per FFT
+/// let analyzer = BatchAnalyzer::new(2048, 48000.0, FrequencyLimit::All, hann_window).unwrap();
+///
+/// // ten seconds of samples, a spectrum every 512 samples
+/// let signal = vec![0.0; 10 * 48000];
+/// let spectra = analyzer.analyze_signal(&signal, 512).unwrap();
+/// assert_eq!(spectra.len(), (signal.len() - 2048) / 512 + 1);
+/// ```
+#[derive(Debug)]
+pub struct BatchAnalyzer<B: FftBackend = DefaultFftBackend> {
+    /// The FFT, prepared for `frame_len` samples. Cloned for each rayon job.
+    fft: Fft<B>,
+    /// Number of samples of each frame and FFT.
+    frame_len: usize,
+    /// The used sampling rate, e.g. `44100 [Hz]`.
+    sampling_rate: f32,
+    /// The frequency limit of each spectrum.
+    frequency_limit: FrequencyLimit,
+    /// What the values of the spectra represent.
+    output: SpectrumOutput,
+    /// Optional scaling of each spectrum.
+    scaling: Option<ScalingPipeline>,
+    /// Coefficients of the window function.
+    window: Vec<f32>,
+}
+
+/// Buffers that are reused for all frames of the same rayon job.
+struct Scratch<B: FftBackend> {
+    /// The FFT of this job.
+    fft: Fft<B>,
+    /// Buffer for the windowed samples and the in-place FFT.
+    fft_buffer: Vec<f32>,
+    /// The FFT result from the DC component to the Nyquist frequency.
+    fft_result: Vec<Complex32>,
+    /// Working buffer for the statistics of the spectrum.
+    working_buffer: Vec<(Frequency, FrequencyValue)>,
+}
+
+impl BatchAnalyzer {
+    /// Creates a new analyzer.
+    ///
+    /// ## Parameters
+    /// * `frame_len` Number of samples of each frame. Must be a power of two.
+    /// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+    /// * `frequency_limit` The [`FrequencyLimit`].
+    /// * `window_fn` Window function that is applied to each frame, e.g.
+    ///   [`crate::windows::hann_window`]. Its coefficients are calculated
+    ///   once.
+    pub fn new(
+        frame_len: usize,
+        sampling_rate: f32,
+        frequency_limit: FrequencyLimit,
+        window_fn: fn(&[f32]) -> Vec<f32>,
+    ) -> Result<Self, SpectrumAnalyzerError> {
+        if frame_len < 2 {
+            return Err(SpectrumAnalyzerError::TooFewSamples);
+        }
+        if !frame_len.is_power_of_two() {
+            return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+        }
+        if frame_len > MAX_FFT_LEN {
+            return Err(SpectrumAnalyzerError::TooManySamples);
+        }
+        crate::verify_sampling_rate(sampling_rate)?;
+        frequency_limit
+            .verify(sampling_rate / 2.0)
+            .map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+
+        let mut fft = FftImpl::default();
+        fft.prepare(frame_len);
+        Ok(Self {
+            fft,
+            frame_len,
+            sampling_rate,
+            frequency_limit,
+            output: SpectrumOutput::Magnitude,
+            scaling: None,
+            window: window_fn(&vec![1.0; frame_len]),
+        })
+    }
+}
+
+impl<B: FftBackend> BatchAnalyzer<B> {
+    /// Calculates the FFTs with the given backend instead of the
+    /// [`DefaultFftBackend`]. The backend is prepared for
+    /// [`Self::frame_len`].
+    ///
+    /// Returns [`SpectrumAnalyzerError::TooManySamples`] if the backend
+    /// doesn't support FFTs of [`Self::frame_len`] samples.
+    pub fn with_backend<C: FftBackend>(
+        self,
+        backend: C,
+    ) -> Result<BatchAnalyzer<C>, SpectrumAnalyzerError> {
+        if self.frame_len > C::MAX_LEN {
+            return Err(SpectrumAnalyzerError::TooManySamples);
+        }
+        let mut fft = Fft::new(backend);
+        fft.prepare(self.frame_len);
+        Ok(BatchAnalyzer {
+            fft,
+            frame_len: self.frame_len,
+            sampling_rate: self.sampling_rate,
+            frequency_limit: self.frequency_limit,
+            output: self.output,
+            scaling: self.scaling,
+            window: self.window,
+        })
+    }
+
+    /// Sets what the values of the spectra represent.
+    pub fn with_output(mut self, output: SpectrumOutput) -> Result<Self, SpectrumAnalyzerError> {
+        output.verify()?;
+        self.output = output;
+        Ok(self)
+    }
+
+    /// Sets the scaling that is applied to each spectrum.
+    #[must_use]
+    pub fn with_scaling(mut self, scaling: ScalingPipeline) -> Self {
+        self.scaling = Some(scaling);
+        self
+    }
+
+    /// Returns the number of samples of each frame.
+    #[must_use]
+    pub const fn frame_len(&self) -> usize {
+        self.frame_len
+    }
+
+    /// Returns the used sampling rate.
+    #[must_use]
+    pub const fn sampling_rate(&self) -> f32 {
+        self.sampling_rate
+    }
+}
+
+impl<B: FftBackend + Clone + Send + Sync> BatchAnalyzer<B> {
+    /// Calculates the spectra of all frames in parallel.
+    ///
+    /// ## Parameters
+    /// * `frames` The frames. Each frame must have exactly
+    ///   [`Self::frame_len`] samples.
+    ///
+    /// ## Return value
+    /// One spectrum per frame, in the order of the frames.
+    pub fn analyze_frames<F: AsRef<[f32]> + Sync>(
+        &self,
+        frames: &[F],
+    ) -> Result<Vec<FrequencySpectrum>, SpectrumAnalyzerError> {
+        if let Some(frame) = frames.iter().find(|f| f.as_ref().len() != self.frame_len) {
+            return Err(SpectrumAnalyzerError::InvalidFrameLength(
+                frame.as_ref().len(),
+            ));
+        }
+        frames
+            .par_iter()
+            .map_init(
+                || self.scratch(),
+                |scratch, frame| self.analyze(frame.as_ref(), scratch),
+            )
+            .collect()
+    }
+
+    /// Cuts a long signal into frames of [`Self::frame_len`] samples that
+    /// start every `hop_size` samples and calculates their spectra in
+    /// parallel. Samples after the last complete frame are ignored.
+    ///
+    /// ## Parameters
+    /// * `signal` The samples of the whole signal.
+    /// * `hop_size` Number of samples between the starts of two frames. Must
+    ///   not be zero.
+    ///
+    /// ## Return value
+    /// One spectrum per frame, in the order of the frames. There is none if
+    /// the signal is shorter than a single frame.
+    pub fn analyze_signal(
+        &self,
+        signal: &[f32],
+        hop_size: usize,
+    ) -> Result<Vec<FrequencySpectrum>, SpectrumAnalyzerError> {
+        if hop_size == 0 {
+            return Err(SpectrumAnalyzerError::TooFewSamples);
+        }
+        let frames = signal
+            .len()
+            .checked_sub(self.frame_len)
+            .map_or(0, |last_start| last_start / hop_size + 1);
+        (0..frames)
+            .into_par_iter()
+            .map_init(
+                || self.scratch(),
+                |scratch, i| {
+                    let start = i * hop_size;
+                    self.analyze(&signal[start..start + self.frame_len], scratch)
+                },
+            )
+            .collect()
+    }
+
+    /// Allocates the buffers of one rayon job.
+    fn scratch(&self) -> Scratch<B> {
+        Scratch {
+            fft: self.fft.clone(),
+            fft_buffer: vec![0.0; self.frame_len],
+            fft_result: Vec::with_capacity(self.frame_len / 2 + 1),
+            working_buffer: Vec::new(),
+        }
+    }
+
+    /// Calculates the spectrum of a single frame.
+    fn analyze(
+        &self,
+        frame: &[f32],
+        scratch: &mut Scratch<B>,
+    ) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+        if frame.iter().any(|x| x.is_nan()) {
+            return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+        }
+        if frame.iter().any(|x| x.is_infinite()) {
+            return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+        }
+
+        for ((windowed, sample), coefficient) in
+            scratch.fft_buffer.iter_mut().zip(frame).zip(&self.window)
+        {
+            *windowed = sample * coefficient;
+        }
+        let fft_res = scratch.fft.calc_in_place(&mut scratch.fft_buffer);
+        // `microfft::real` packs the real value of the Nyquist frequency
+        // inside the imaginary part of the DC component.
+        scratch.fft_result.clear();
+        scratch.fft_result.push(Complex32::new(fft_res[0].re, 0.0));
+        scratch.fft_result.extend_from_slice(&fft_res[1..]);
+        scratch.fft_result.push(Complex32::new(fft_res[0].im, 0.0));
+
+        let mut spectrum = crate::fft_result_to_spectrum(
+            self.frame_len,
+            &scratch.fft_result,
+            self.sampling_rate,
+            self.frequency_limit,
+            self.output,
+            None,
+        )?;
+        if let Some(scaling) = &self.scaling {
+            scratch
+                .working_buffer
+                .resize(spectrum.data().len(), (0.0.into(), 0.0.into()));
+            scaling.apply(&mut spectrum, &mut scratch.working_buffer)?;
+        }
+        Ok(spectrum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::Microfft;
+    use crate::scaling::{Decibel, Scaling};
+    use crate::streaming::StreamingAnalyzer;
+    use crate::windows::hann_window;
+
+    fn signal(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| libm::sinf(i as f32 * 0.3) + 0.25 * libm::sinf(i as f32 * 0.0021 * i as f32))
+            .collect()
+    }
+
+    /// The spectra must be in order and match those of a sequential
+    /// analysis.
+    #[test]
+    fn test_matches_streaming() {
+        let signal = signal(10_000);
+        let limit = FrequencyLimit::Range(100.0, 3000.0);
+        let scaling = || ScalingPipeline::new().with(Scaling::Decibel(Decibel::dbfs(1.0, 0.5)));
+        let analyzer = BatchAnalyzer::new(256, 8000.0, limit, hann_window)
+            .unwrap()
+            .with_scaling(scaling());
+        let spectra = analyzer.analyze_signal(&signal, 100).unwrap();
+        assert_eq!(spectra.len(), (10_000 - 256) / 100 + 1);
+
+        let mut streaming = StreamingAnalyzer::new(256, 100, 8000.0, limit, hann_window)
+            .unwrap()
+            .with_scaling(scaling());
+        let mut expected = Vec::new();
+        streaming
+            .push(&signal, |spectrum| expected.push(spectrum.data().to_vec()))
+            .unwrap();
+        assert_eq!(spectra.len(), expected.len());
+        for (spectrum, expected) in spectra.iter().zip(&expected) {
+            assert_eq!(spectrum.data(), expected.as_slice());
+        }
+    }
+
+    /// A slice of frames gives the same spectra as the frames of a signal,
+    /// independent of the number of threads.
+    #[test]
+    fn test_frames() {
+        let signal = signal(4096);
+        let analyzer = BatchAnalyzer::new(512, 8000.0, FrequencyLimit::All, hann_window)
+            .unwrap()
+            .with_output(SpectrumOutput::Power)
+            .unwrap();
+        let frames = signal.chunks_exact(512).collect::<Vec<_>>();
+        let spectra = analyzer.analyze_frames(&frames).unwrap();
+        let single_threaded = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap()
+            .install(|| analyzer.analyze_signal(&signal, 512))
+            .unwrap();
+        assert_eq!(spectra.len(), 8);
+        for (spectrum, expected) in spectra.iter().zip(&single_threaded) {
+            assert_eq!(spectrum.data(), expected.data());
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let analyzer = BatchAnalyzer::new(8, 8000.0, FrequencyLimit::All, hann_window).unwrap();
+        assert!(matches!(
+            analyzer.analyze_frames(&[vec![0.0; 8], vec![0.0; 4]]),
+            Err(SpectrumAnalyzerError::InvalidFrameLength(4))
+        ));
+        assert!(matches!(
+            analyzer.analyze_signal(&[0.0, f32::NAN, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0], 1),
+            Err(SpectrumAnalyzerError::NaNValuesNotSupported)
+        ));
+        assert!(matches!(
+            analyzer.analyze_signal(&[0.0; 16], 0),
+            Err(SpectrumAnalyzerError::TooFewSamples)
+        ));
+        assert!(analyzer.analyze_signal(&[0.0; 7], 1).unwrap().is_empty());
+        assert!(matches!(
+            BatchAnalyzer::new(12, 8000.0, FrequencyLimit::All, hann_window),
+            Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo)
+        ));
+    }
+
+    #[test]
+    fn test_backend() {
+        let signal = signal(2048);
+        let new = || BatchAnalyzer::new(256, 8000.0, FrequencyLimit::All, hann_window).unwrap();
+        let expected = new().analyze_signal(&signal, 128).unwrap();
+        let spectra = new()
+            .with_backend(Microfft::default())
+            .unwrap()
+            .analyze_signal(&signal, 128)
+            .unwrap();
+        assert_eq!(spectra.len(), expected.len());
+        for (spectrum, expected) in spectra.iter().zip(&expected) {
+            for ((_, val), (_, expected_val)) in spectrum.data().iter().zip(expected.data()) {
+                float_cmp::assert_approx_eq!(f32, val.val(), expected_val.val(), epsilon = 0.0001);
+            }
+        }
+    }
+}
@@ -0,0 +1,375 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the struct [`CalibrationCurve`], which corrects a spectrum by the
+//! frequency response of a measurement microphone or another sensor.
+//!
+//! Calibration files map frequencies to a deviation in dB. The common layout,
+//! used for example by REW and the miniDSP UMIK microphones, looks like this:
+//! ```text
+//! "Sens Factor =-1.378dB, SERNO: 7000000"
+//! 10.054   -2.4311
+//! 10.179   -2.3502
+//! 10.306   -2.2695
+//! ```
+
+use crate::error::SpectrumAnalyzerError;
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::spectrum::FrequencySpectrum;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Display, Formatter};
+
+/// How the correction is interpolated between two points of a
+/// [`CalibrationCurve`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum CalibrationInterpolation {
+    /// Linear interpolation over the frequency in Hertz.
+    Linear,
+    /// Linear interpolation over the logarithm of the frequency. This matches
+    /// the logarithmic spacing of the points in typical calibration files.
+    #[default]
+    LogFrequency,
+}
+
+/// Frequency response of a microphone or sensor, i.e., a list of
+/// `(frequency in Hertz, deviation in dB)`-pairs sorted by frequency.
+///
+/// Frequencies below the first or above the last point use the correction of
+/// that point.
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::calibration::CalibrationCurve;
+/// use spectrum_analyzer::scaling::scale_20_times_log10;
+/// use spectrum_analyzer::{samples_fft_to_spectrum, FrequencyLimit};
+///
+/// let curve = CalibrationCurve::parse("\"Sens Factor =-1.378dB\"\n20 -1.0\n20000 1.5")
+///     .unwrap()
+///     .with_sensitivity_offset(94.0 + 1.378);
+///
+/// let samples = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7];
+/// let mut spectrum = samples_fft_to_spectrum(
+///     &samples,
+///     44100,
+///     FrequencyLimit::All,
+///     Some(&scale_20_times_log10),
+/// )
+/// .unwrap();
+/// let mut working_buffer = vec![(0.0.into(), 0.0.into()); spectrum.data().len()];
+/// curve.apply(&mut spectrum, &mut working_buffer).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct CalibrationCurve {
+    /// All `(frequency, deviation in dB)`-pairs, sorted by frequency.
+    points: Vec<(f32, f32)>,
+    /// Interpolation between two points.
+    interpolation: CalibrationInterpolation,
+    /// Offset in dB that is added to every value after the correction.
+    sensitivity_offset: f32,
+    /// The "Sens Factor" from the header of the calibration file, if any.
+    sens_factor: Option<f32>,
+}
+
+impl CalibrationCurve {
+    /// Creates a new curve from `(frequency in Hertz, deviation in dB)`-pairs.
+    ///
+    /// ## Parameters
+    /// * `points` At least two points. The frequencies must be strictly
+    ///   increasing and not negative.
+    pub fn new(points: Vec<(f32, f32)>) -> Result<Self, CalibrationError> {
+        if points.len() < 2 {
+            return Err(CalibrationError::TooFewPoints);
+        }
+        for (i, (fr, db)) in points.iter().enumerate() {
+            if !fr.is_finite() || !db.is_finite() || *fr < 0.0 {
+                return Err(CalibrationError::InvalidPoint(*fr, *db));
+            }
+            if i > 0 && points[i - 1].0 >= *fr {
+                return Err(CalibrationError::FrequenciesNotIncreasing(*fr));
+            }
+        }
+
+        Ok(Self {
+            points,
+            interpolation: CalibrationInterpolation::default(),
+            sensitivity_offset: 0.0,
+            sens_factor: None,
+        })
+    }
+
+    /// Parses the content of a calibration file. Each data line holds the
+    /// frequency in Hertz and the deviation in dB, separated by whitespace,
+    /// a comma, or a semicolon. Further columns, such as the phase, are
+    /// ignored.
+    ///
+    /// Lines that don't start with a number, such as comments or CSV headers,
+    /// are skipped. A header line containing `Sens Factor =<x>dB` is available
+    /// via [`Self::sens_factor`] afterward.
+    pub fn parse(content: &str) -> Result<Self, CalibrationError> {
+        let mut points = Vec::new();
+        let mut sens_factor = None;
+
+        for (line_i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if let Some(factor) = parse_sens_factor(line) {
+                sens_factor = Some(factor);
+                continue;
+            }
+
+            let mut columns = line
+                .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+                .filter(|column| !column.is_empty());
+
+            // comments, headers, empty lines
+            let Some(Ok(fr)) = columns.next().map(str::parse::<f32>) else {
+                continue;
+            };
+            let Some(Ok(db)) = columns.next().map(str::parse::<f32>) else {
+                // line numbers start at one
+                return Err(CalibrationError::InvalidLine(line_i + 1));
+            };
+            points.push((fr, db));
+        }
+
+        let mut curve = Self::new(points)?;
+        curve.sens_factor = sens_factor;
+        Ok(curve)
+    }
+
+    /// Sets how the correction is interpolated between two points.
+    #[must_use]
+    pub const fn with_interpolation(mut self, interpolation: CalibrationInterpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Sets an offset in dB that is added to every value after the correction.
+    ///
+    /// If the microphone produces `x` dBFS at 94 dB SPL, an offset of `94 - x`
+    /// turns a dBFS spectrum into a dB SPL spectrum.
+    #[must_use]
+    pub const fn with_sensitivity_offset(mut self, offset: f32) -> Self {
+        self.sensitivity_offset = offset;
+        self
+    }
+
+    /// Returns all `(frequency, deviation in dB)`-pairs of the curve.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // false positive
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// Returns the interpolation between two points.
+    #[inline]
+    #[must_use]
+    pub const fn interpolation(&self) -> CalibrationInterpolation {
+        self.interpolation
+    }
+
+    /// Returns the offset in dB that is added to every value.
+    #[inline]
+    #[must_use]
+    pub const fn sensitivity_offset(&self) -> f32 {
+        self.sensitivity_offset
+    }
+
+    /// Returns the "Sens Factor" in dB from the header of the parsed file, if
+    /// there was one.
+    #[inline]
+    #[must_use]
+    pub const fn sens_factor(&self) -> Option<f32> {
+        self.sens_factor
+    }
+
+    /// Returns the deviation of the sensor in dB at the given frequency. This
+    /// value is subtracted from the spectrum to correct it.
+    #[must_use]
+    pub fn correction(&self, fr: f32) -> f32 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if fr <= first.0 {
+            return first.1;
+        }
+        if fr >= last.0 {
+            return last.1;
+        }
+
+        // index of the first point with a higher frequency; never 0 or len()
+        let i = self.points.partition_point(|(point_fr, _)| *point_fr <= fr);
+        let (x1, y1) = self.points[i - 1];
+        let (x2, y2) = self.points[i];
+
+        let ratio = match self.interpolation {
+            CalibrationInterpolation::LogFrequency if x1 > 0.0 => {
+                libm::logf(fr / x1) / libm::logf(x2 / x1)
+            }
+            // log(0) is not defined; fall back to linear interpolation
+            _ => (fr - x1) / (x2 - x1),
+        };
+        y1 + ratio * (y2 - y1)
+    }
+
+    /// Corrects a spectrum whose values are in dB, e.g. after
+    /// [`crate::scaling::scale_20_times_log10`]. Each value becomes
+    /// `value - correction(fr) + sensitivity_offset`.
+    ///
+    /// ## Parameters
+    /// * `spectrum` Spectrum with values in dB.
+    /// * `working_buffer` Mutable buffer with the same length as the data of
+    ///   the spectrum. See [`FrequencySpectrum::apply_scaling_fn`].
+    pub fn apply(
+        &self,
+        spectrum: &mut FrequencySpectrum,
+        working_buffer: &mut [(Frequency, FrequencyValue)],
+    ) -> Result<(), SpectrumAnalyzerError> {
+        spectrum.map_values(
+            |fr, fr_val| fr_val - self.correction(fr) + self.sensitivity_offset,
+            working_buffer,
+        )
+    }
+}
+
+/// Parses headers such as `"Sens Factor =-1.378dB, SERNO: 7000000"`.
+fn parse_sens_factor(line: &str) -> Option<f32> {
+    let (_, rest) = line.split_once("Sens Factor")?;
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+/// Possible errors when creating a [`CalibrationCurve`].
+#[derive(Debug)]
+pub enum CalibrationError {
+    /// The curve needs at least two points.
+    TooFewPoints,
+    /// A point contains NaN, infinity, or a negative frequency.
+    InvalidPoint(f32, f32),
+    /// The frequencies are not strictly increasing. Contains the first
+    /// frequency that violates the order.
+    FrequenciesNotIncreasing(f32),
+    /// A data line starts with a frequency but has no valid dB value. Contains
+    /// the line number, starting at one.
+    InvalidLine(usize),
+}
+
+impl Display for CalibrationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooFewPoints => write!(f, "Calibration curve needs at least two points!"),
+            Self::InvalidPoint(fr, db) => write!(f, "Invalid calibration point: {fr}Hz, {db}dB"),
+            Self::FrequenciesNotIncreasing(fr) => {
+                write!(f, "Frequencies must be strictly increasing: {fr}Hz")
+            }
+            Self::InvalidLine(line) => write!(f, "Invalid calibration data in line {line}"),
+        }
+    }
+}
+
+impl Error for CalibrationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UMIK_CAL: &str = "\"Sens Factor =-1.378dB, SERNO: 7000000\"\n\
+        10.054\t-2.4311\n\
+        100.0\t0.5\n\
+        1000.0\t0.0\t12.3\n\
+        10000.0\t2.0\n";
+
+    #[test]
+    fn test_parse_umik() {
+        let curve = CalibrationCurve::parse(UMIK_CAL).unwrap();
+        assert_eq!(curve.points().len(), 4);
+        assert_eq!(curve.points()[0], (10.054, -2.4311));
+        assert_eq!(curve.points()[2], (1000.0, 0.0));
+        assert_eq!(curve.sens_factor(), Some(-1.378));
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let curve = CalibrationCurve::parse("# comment\nfrequency,db\n20,1.0\n40, 2.0\n").unwrap();
+        assert_eq!(curve.points(), &[(20.0, 1.0), (40.0, 2.0)]);
+        assert_eq!(curve.sens_factor(), None);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(matches!(
+            CalibrationCurve::parse("20 1.0\n30\n40 1.0"),
+            Err(CalibrationError::InvalidLine(2))
+        ));
+        assert!(matches!(
+            CalibrationCurve::parse("20 1.0"),
+            Err(CalibrationError::TooFewPoints)
+        ));
+        assert!(matches!(
+            CalibrationCurve::parse("20 1.0\n10 1.0"),
+            Err(CalibrationError::FrequenciesNotIncreasing(_))
+        ));
+        assert!(matches!(
+            CalibrationCurve::parse("-20 1.0\n10 1.0"),
+            Err(CalibrationError::InvalidPoint(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_correction_interpolation() {
+        let curve = CalibrationCurve::new(vec![(100.0, 0.0), (1000.0, 10.0)]).unwrap();
+
+        // clamped outside the curve
+        assert_eq!(curve.correction(0.0), 0.0);
+        assert_eq!(curve.correction(5000.0), 10.0);
+
+        // geometric mean of both frequencies is halfway on a log axis
+        float_cmp::assert_approx_eq!(f32, curve.correction(316.227_77), 5.0, epsilon = 0.001);
+
+        let curve = curve.with_interpolation(CalibrationInterpolation::Linear);
+        float_cmp::assert_approx_eq!(f32, curve.correction(550.0), 5.0, epsilon = 0.001);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut data: Vec<(Frequency, FrequencyValue)> = vec![
+            (50.0_f32.into(), (-20.0_f32).into()),
+            (100.0.into(), (-20.0_f32).into()),
+            (1000.0.into(), (-20.0_f32).into()),
+        ];
+        let mut spectrum = FrequencySpectrum::new(data.clone(), 50.0, 6, &mut data);
+
+        let curve = CalibrationCurve::new(vec![(100.0, -1.0), (1000.0, 3.0)])
+            .unwrap()
+            .with_sensitivity_offset(100.0);
+        curve.apply(&mut spectrum, &mut data).unwrap();
+
+        assert_eq!(spectrum.data()[0].1.val(), 81.0);
+        assert_eq!(spectrum.data()[1].1.val(), 81.0);
+        assert_eq!(spectrum.data()[2].1.val(), 77.0);
+        assert_eq!(spectrum.max().1.val(), 81.0, "statistics must be updated");
+    }
+}
@@ -0,0 +1,368 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the cepstrum of signals, i.e. the inverse FFT of the
+//! logarithmic spectrum, e.g. for echo detection and pitch analysis.
+//!
+//! The x-axis of a cepstrum is the quefrency in seconds. A periodic
+//! structure in the spectrum, such as the harmonics of a tone with the
+//! fundamental frequency `f0` or the ripple of an echo with the delay `d`,
+//! results in a peak at the quefrency `1 / f0` or `d`, respectively.
+
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::{Complex32, DefaultFftBackend, Fft, FftBackend};
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+
+/// Calculates the real cepstrum `IFFT(ln(|FFT(x)|))` of the samples.
+///
+/// ## Parameters
+/// * `samples` Array with samples. Each value must be a regular floating
+///   point number (no NaN or infinite) and the length must be a power of
+///   two that doesn't exceed 32768, unless the `large-fft` or the `rustfft`
+///   feature is enabled. Magnitudes of zero are replaced by a small fraction of the
+///   maximum magnitude to keep the logarithm finite.
+/// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+///
+/// ## Return value
+/// The quefrency in seconds and the value, from quefrency `0` to
+/// `N / 2 / sampling_rate`. The real cepstrum is symmetric, so the negative
+/// quefrencies are omitted.
+///
+/// ## Examples
+/// ```rust
+/// use spectrum_analyzer::cepstrum::real_cepstrum;
+/// let cepstrum = real_cepstrum(&[0.0, 1.1, 5.5, -5.5], 44100.0).unwrap();
+/// assert_eq!(cepstrum.len(), 3);
+/// ```
+pub fn real_cepstrum(
+    samples: &[f32],
+    sampling_rate: f32,
+) -> Result<Vec<(f32, f32)>, SpectrumAnalyzerError> {
+    real_cepstrum_with_backend::<DefaultFftBackend>(samples, sampling_rate)
+}
+
+/// Like [`real_cepstrum`] but calculates the FFTs with the given
+/// [`FftBackend`] instead of the [`DefaultFftBackend`].
+pub fn real_cepstrum_with_backend<B: FftBackend>(
+    samples: &[f32],
+    sampling_rate: f32,
+) -> Result<Vec<(f32, f32)>, SpectrumAnalyzerError> {
+    crate::verify_sampling_rate(sampling_rate)?;
+    let mut fft = Fft::<B>::default();
+    let log_spectrum = log_magnitudes(&mut fft, samples)?
+        .into_iter()
+        .map(|val| Complex32::new(val, 0.0))
+        .collect::<Vec<_>>();
+    let cepstrum = fft.calc_inverse(&log_spectrum);
+    Ok(cepstrum
+        .iter()
+        .take(samples.len() / 2 + 1)
+        .enumerate()
+        .map(|(n, val)| (n as f32 / sampling_rate, val.re))
+        .collect())
+}
+
+/// Calculates the power cepstrum `|IFFT(ln(|FFT(x)|²))|²` of the samples,
+/// which equals four times the square of the [`real_cepstrum`]. See
+/// [`real_cepstrum`] for the parameters and the return value.
+pub fn power_cepstrum(
+    samples: &[f32],
+    sampling_rate: f32,
+) -> Result<Vec<(f32, f32)>, SpectrumAnalyzerError> {
+    power_cepstrum_with_backend::<DefaultFftBackend>(samples, sampling_rate)
+}
+
+/// Like [`power_cepstrum`] but calculates the FFTs with the given
+/// [`FftBackend`] instead of the [`DefaultFftBackend`].
+pub fn power_cepstrum_with_backend<B: FftBackend>(
+    samples: &[f32],
+    sampling_rate: f32,
+) -> Result<Vec<(f32, f32)>, SpectrumAnalyzerError> {
+    let mut cepstrum = real_cepstrum_with_backend::<B>(samples, sampling_rate)?;
+    // ln(|X|²) = 2 * ln(|X|)
+    cepstrum
+        .iter_mut()
+        .for_each(|(_, val)| *val = 4.0 * *val * *val);
+    Ok(cepstrum)
+}
+
+/// The complex cepstrum of a signal. See [`complex_cepstrum`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplexCepstrum {
+    /// Quefrencies in seconds and values, from lowest to highest.
+    data: Vec<(f32, f32)>,
+    /// The removed linear phase in samples.
+    delay: isize,
+}
+
+impl ComplexCepstrum {
+    /// Returns the quefrency in seconds and the value, from quefrency
+    /// `-N / 2 / sampling_rate` to `(N / 2 - 1) / sampling_rate`.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // false positive
+    pub fn data(&self) -> &[(f32, f32)] {
+        &self.data
+    }
+
+    /// Returns the linear phase in samples that was removed before the
+    /// inverse FFT, i.e. the integer delay of the signal. Without this,
+    /// the cepstrum would be dominated by the phase ramp.
+    #[inline]
+    #[must_use]
+    pub const fn delay(&self) -> isize {
+        self.delay
+    }
+}
+
+/// Calculates the complex cepstrum `IFFT(ln(|X|) + j * arg(X))` of the
+/// samples, where `X = FFT(x)` and `arg(X)` is the unwrapped phase without
+/// its linear part.
+///
+/// Unlike the [`real_cepstrum`], it keeps the phase. An echo with the
+/// delay `d` and a smaller amplitude than the direct sound results in a
+/// peak at the positive quefrency `d`. See [`real_cepstrum`] for the
+/// parameters.
+pub fn complex_cepstrum(
+    samples: &[f32],
+    sampling_rate: f32,
+) -> Result<ComplexCepstrum, SpectrumAnalyzerError> {
+    complex_cepstrum_with_backend::<DefaultFftBackend>(samples, sampling_rate)
+}
+
+/// Like [`complex_cepstrum`] but calculates the FFTs with the given
+/// [`FftBackend`] instead of the [`DefaultFftBackend`].
+pub fn complex_cepstrum_with_backend<B: FftBackend>(
+    samples: &[f32],
+    sampling_rate: f32,
+) -> Result<ComplexCepstrum, SpectrumAnalyzerError> {
+    crate::verify_sampling_rate(sampling_rate)?;
+    let mut fft = Fft::<B>::default();
+    let log_magnitudes = log_magnitudes(&mut fft, samples)?;
+    let fft_res = fft.calc(samples);
+    let len = samples.len();
+
+    // unwrap the phase of the positive frequencies
+    let mut phase = Vec::with_capacity(len / 2 + 1);
+    let mut previous = 0.0;
+    let mut offset = 0.0;
+    for val in &fft_res {
+        let wrapped = libm::atan2f(val.im, val.re);
+        let diff = wrapped - previous;
+        offset -= 2.0 * PI * libm::roundf(diff / (2.0 * PI));
+        previous = wrapped;
+        phase.push(wrapped + offset);
+    }
+
+    // The phase at the Nyquist frequency is a multiple of π, which
+    // corresponds to a linear phase of an integer delay.
+    let half = (len / 2) as f32;
+    // A delay of `d` samples has the phase `-2π * k * d / N`.
+    let delay = -libm::roundf(phase[len / 2] / PI);
+    for (k, phase) in phase.iter_mut().enumerate() {
+        *phase += PI * delay * k as f32 / half;
+    }
+
+    // the phase of the negative frequencies is mirrored
+    let log_spectrum = (0..len)
+        .map(|k| {
+            let phase = if k <= len / 2 {
+                phase[k]
+            } else {
+                -phase[len - k]
+            };
+            Complex32::new(log_magnitudes[k], phase)
+        })
+        .collect::<Vec<_>>();
+    let cepstrum = fft.calc_inverse(&log_spectrum);
+
+    // negative quefrencies are at the end of the result
+    let data = (-(len as isize / 2)..(len as isize / 2))
+        .map(|n| {
+            let index = n.rem_euclid(len as isize) as usize;
+            (n as f32 / sampling_rate, cepstrum[index].re)
+        })
+        .collect();
+    Ok(ComplexCepstrum {
+        data,
+        delay: delay as isize,
+    })
+}
+
+/// Verifies the samples and returns the natural logarithm of the magnitude
+/// of all `N` frequencies of the FFT.
+fn log_magnitudes<B: FftBackend>(
+    fft: &mut Fft<B>,
+    samples: &[f32],
+) -> Result<Vec<f32>, SpectrumAnalyzerError> {
+    if samples.len() < 2 {
+        return Err(SpectrumAnalyzerError::TooFewSamples);
+    }
+    if samples.iter().any(|x| x.is_nan()) {
+        return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+    }
+    if samples.iter().any(|x| x.is_infinite()) {
+        return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+    }
+    if !samples.len().is_power_of_two() {
+        return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+    }
+    if samples.len() > B::MAX_LEN {
+        return Err(SpectrumAnalyzerError::TooManySamples);
+    }
+
+    let magnitudes = fft
+        .calc_full(samples)
+        .iter()
+        .map(|val| libm::sqrtf(val.norm_sqr()))
+        .collect::<Vec<_>>();
+    let max = magnitudes.iter().copied().fold(0.0, f32::max);
+    let floor = (max * f32::EPSILON).max(f32::MIN_POSITIVE);
+    Ok(magnitudes
+        .into_iter()
+        .map(|val| libm::logf(val.max(floor)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sine::{random, sines};
+    use crate::windows::hann_window;
+
+    /// Sum of sines with the given frequencies at 8000 Hz.
+    /// Returns the quefrency of the highest value above the quefrency
+    /// `min`, to skip the spectral envelope.
+    fn peak(cepstrum: &[(f32, f32)], min: f32) -> f32 {
+        cepstrum
+            .iter()
+            .filter(|(q, _)| *q >= min)
+            .max_by(|(_, l), (_, r)| l.total_cmp(r))
+            .unwrap()
+            .0
+    }
+
+    /// The harmonics of 200 Hz result in a peak at 5 ms.
+    #[test]
+    fn test_pitch() {
+        let harmonics = (1..20).map(|h| 200.0 * h as f32).collect::<Vec<_>>();
+        let samples = hann_window(&sines(&harmonics, 8000.0, 2048));
+        let cepstrum = real_cepstrum(&samples, 8000.0).unwrap();
+        assert_eq!(cepstrum.len(), 1025);
+        assert_eq!(peak(&cepstrum, 0.001), 0.005);
+
+        let power = power_cepstrum(&samples, 8000.0).unwrap();
+        assert_eq!(peak(&power, 0.001), 0.005);
+        for ((_, real), (_, power)) in cepstrum.iter().zip(&power) {
+            assert!((4.0 * real * real - power).abs() < 0.0001);
+        }
+    }
+
+    /// A delayed copy of a signal results in a peak at its delay.
+    #[test]
+    fn test_echo() {
+        // many sines with pseudo-random frequencies, similar to noise
+        let frequencies = random(500, 42)
+            .iter()
+            .map(|x| (x * 4000.0) as f32)
+            .collect::<Vec<_>>();
+        let direct = sines(&frequencies, 8000.0, 2048 + 100);
+        // echo after 100 samples, i.e. 12.5 ms
+        let samples = direct[100..]
+            .iter()
+            .zip(&direct)
+            .map(|(direct, echo)| direct + 0.5 * echo)
+            .collect::<Vec<_>>();
+        let samples = hann_window(&samples);
+
+        let cepstrum = real_cepstrum(&samples, 8000.0).unwrap();
+        assert_eq!(peak(&cepstrum, 0.002), 0.0125);
+    }
+
+    /// The complex cepstrum of an impulse with an echo `a` after `d` samples
+    /// is `a^k / k * (-1)^(k + 1)` at the quefrencies `k * d`.
+    #[test]
+    fn test_complex_cepstrum_of_echo() {
+        let mut samples = [0.0; 256];
+        // delayed by 5 samples, echo after another 20 samples
+        samples[5] = 1.0;
+        samples[25] = 0.5;
+        let cepstrum = complex_cepstrum(&samples, 8000.0).unwrap();
+        assert_eq!(cepstrum.delay(), 5);
+        assert_eq!(cepstrum.data().len(), 256);
+        assert_eq!(cepstrum.data()[0].0, -0.016);
+
+        for (i, (quefrency, val)) in cepstrum.data().iter().enumerate() {
+            let n = i as isize - 128;
+            assert_eq!(*quefrency, n as f32 / 8000.0);
+            let expected = if n > 0 && n % 20 == 0 {
+                let k = n / 20;
+                let sign = if k % 2 == 1 { 1.0 } else { -1.0 };
+                sign * libm::powf(0.5, k as f32) / k as f32
+            } else {
+                0.0
+            };
+            assert!((val - expected).abs() < 0.002, "{n}: {val} != {expected}");
+        }
+
+        let microfft = complex_cepstrum_with_backend::<crate::fft::Microfft>(&samples, 8000.0);
+        let microfft = microfft.unwrap();
+        assert_eq!(microfft.delay(), 5);
+        for ((_, val), (_, expected)) in microfft.data().iter().zip(cepstrum.data()) {
+            assert!((val - expected).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(matches!(
+            real_cepstrum(&[1.0], 8000.0),
+            Err(SpectrumAnalyzerError::TooFewSamples)
+        ));
+        assert!(matches!(
+            complex_cepstrum(&[1.0; 6], 8000.0),
+            Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo)
+        ));
+        // zeros don't result in infinite values
+        let cepstrum = complex_cepstrum(&[0.0; 8], 8000.0).unwrap();
+        assert!(cepstrum.data().iter().all(|(_, val)| val.is_finite()));
+
+        #[cfg(not(any(feature = "large-fft", feature = "rustfft")))]
+        {
+            let samples = vec![0.1; 65536];
+            assert!(matches!(
+                real_cepstrum(&samples, 44100.0),
+                Err(SpectrumAnalyzerError::TooManySamples)
+            ));
+            assert!(matches!(
+                power_cepstrum(&samples, 44100.0),
+                Err(SpectrumAnalyzerError::TooManySamples)
+            ));
+            assert!(matches!(
+                complex_cepstrum(&samples, 44100.0),
+                Err(SpectrumAnalyzerError::TooManySamples)
+            ));
+        }
+    }
+}
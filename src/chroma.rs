@@ -0,0 +1,377 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for chroma vectors (pitch class profiles), which sum up the
+//! energy of all octaves of each of the twelve pitch classes, e.g. for key
+//! and chord detection.
+
+use crate::spectrum::FrequencySpectrum;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::f32::consts::PI;
+use core::fmt::{Display, Formatter};
+
+/// Names of the pitch classes, in the order of [`Chroma::values`].
+pub const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// How a [`Chroma`] vector is normalized.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ChromaNormalization {
+    /// The values are the summed energies.
+    None,
+    /// The strongest pitch class has the value `1`. This is the default.
+    #[default]
+    Max,
+    /// The values sum up to `1`.
+    Sum,
+    /// The vector has the euclidean length `1`.
+    Euclidean,
+}
+
+/// Configuration of the chroma extraction.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChromaConfig {
+    /// Frequency of A4 in Hz. The default is `440.0`. See
+    /// [`estimate_tuning_deviation`] to determine it from the audio.
+    pub tuning: f32,
+    /// Lower bound of the considered frequencies in Hz. The frequency
+    /// resolution of an FFT is usually too coarse for low notes. The default
+    /// is `50.0`.
+    pub min_frequency: f32,
+    /// Upper bound of the considered frequencies in Hz. The default is
+    /// `5000.0`.
+    pub max_frequency: f32,
+    /// See [`ChromaNormalization`].
+    pub normalization: ChromaNormalization,
+}
+
+impl Default for ChromaConfig {
+    fn default() -> Self {
+        Self {
+            tuning: 440.0,
+            min_frequency: 50.0,
+            max_frequency: 5000.0,
+            normalization: ChromaNormalization::default(),
+        }
+    }
+}
+
+impl ChromaConfig {
+    /// Checks that the tuning is positive and that the frequency bounds are
+    /// ordered. The upper bound may be infinite.
+    fn verify(&self) -> Result<(), ChromaError> {
+        if !self.tuning.is_finite()
+            || self.tuning <= 0.0
+            || !self.min_frequency.is_finite()
+            || self.max_frequency.is_nan()
+            || self.min_frequency >= self.max_frequency
+        {
+            return Err(ChromaError::InvalidConfig);
+        }
+        Ok(())
+    }
+}
+
+/// Energy of each of the twelve pitch classes.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Chroma {
+    /// Values of the pitch classes, starting with C.
+    values: [f32; 12],
+}
+
+impl Chroma {
+    /// Returns the values of the pitch classes, starting with C. See
+    /// [`PITCH_CLASS_NAMES`].
+    #[inline]
+    #[must_use]
+    pub const fn values(&self) -> &[f32; 12] {
+        &self.values
+    }
+
+    /// Returns the index of the strongest pitch class, e.g. `9` for A.
+    #[must_use]
+    pub fn max_pitch_class(&self) -> usize {
+        self.values
+            .iter()
+            .enumerate()
+            .max_by(|(_, l), (_, r)| l.total_cmp(r))
+            .map_or(0, |(i, _)| i)
+    }
+}
+
+/// Calculates the chroma vector of a spectrum.
+///
+/// Each frequency between [`ChromaConfig::min_frequency`] and [`ChromaConfig::max_frequency`] adds
+/// its energy, i.e. the squared value, to the closest pitch class.
+///
+/// This expects a spectrum with linear values, such as magnitudes, not
+/// decibels.
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::chroma::{spectrum_to_chroma, ChromaConfig, PITCH_CLASS_NAMES};
+/// use spectrum_analyzer::{samples_fft_to_spectrum, FrequencyLimit};
+///
+/// // A4
+/// let samples = (0..4096)
+///     .map(|i| (2.0 * core::f32::consts::PI * 440.0 * i as f32 / 44100.0).sin())
+///     .collect::<Vec<_>>();
+/// let spectrum = samples_fft_to_spectrum(&samples, 44100, FrequencyLimit::All, None).unwrap();
+/// let chroma = spectrum_to_chroma(&spectrum, &ChromaConfig::default()).unwrap();
+/// assert_eq!(PITCH_CLASS_NAMES[chroma.max_pitch_class()], "A");
+/// ```
+///
+/// ## Return value
+/// [`ChromaError::InvalidConfig`] if the tuning isn't positive or if
+/// [`ChromaConfig::min_frequency`] isn't below [`ChromaConfig::max_frequency`].
+pub fn spectrum_to_chroma(
+    spectrum: &FrequencySpectrum,
+    config: &ChromaConfig,
+) -> Result<Chroma, ChromaError> {
+    config.verify()?;
+    let mut values = [0.0; 12];
+    for (fr, val) in considered_frequencies(spectrum, config) {
+        let pitch = libm::roundf(midi_pitch(fr, config.tuning));
+        let pitch_class = (pitch as i32).rem_euclid(12) as usize;
+        values[pitch_class] += val * val;
+    }
+
+    let norm = match config.normalization {
+        ChromaNormalization::None => 1.0,
+        ChromaNormalization::Max => values.iter().copied().fold(0.0, f32::max),
+        ChromaNormalization::Sum => values.iter().sum(),
+        ChromaNormalization::Euclidean => libm::sqrtf(values.iter().map(|x| x * x).sum()),
+    };
+    if norm > 0.0 {
+        values.iter_mut().for_each(|x| *x /= norm);
+    }
+    Ok(Chroma { values })
+}
+
+/// Calculates the chroma vectors of successive spectra, e.g. of a
+/// [`crate::streaming::StreamingAnalyzer`]. See [`spectrum_to_chroma`].
+pub fn spectra_to_chromagram(
+    spectra: &[FrequencySpectrum],
+    config: &ChromaConfig,
+) -> Result<Vec<Chroma>, ChromaError> {
+    config.verify()?;
+    spectra
+        .iter()
+        .map(|spectrum| spectrum_to_chroma(spectrum, config))
+        .collect()
+}
+
+/// Estimates how much the pitches of the spectrum deviate from the tuning
+/// of the config.
+///
+/// This is the circular mean of the deviation of each frequency
+/// from its closest pitch, weighted by its energy.
+///
+/// ## Return value
+/// The deviation in cents in interval `[-50; 50]`. The tuning of the audio
+/// is `config.tuning * 2^(cents / 1200)`, or [`ChromaError::InvalidConfig`],
+/// see [`spectrum_to_chroma`].
+pub fn estimate_tuning_deviation(
+    spectrum: &FrequencySpectrum,
+    config: &ChromaConfig,
+) -> Result<f32, ChromaError> {
+    config.verify()?;
+    let (mut sin_sum, mut cos_sum) = (0.0, 0.0);
+    for (fr, val) in considered_frequencies(spectrum, config) {
+        let pitch = midi_pitch(fr, config.tuning);
+        // the deviation from the closest pitch as an angle
+        let angle = 2.0 * PI * (pitch - libm::roundf(pitch));
+        let energy = val * val;
+        sin_sum += energy * libm::sinf(angle);
+        cos_sum += energy * libm::cosf(angle);
+    }
+    if sin_sum == 0.0 && cos_sum == 0.0 {
+        return Ok(0.0);
+    }
+    Ok(libm::atan2f(sin_sum, cos_sum) / (2.0 * PI) * 100.0)
+}
+
+/// Returns the frequencies and values of the spectrum within the limits of
+/// the config.
+fn considered_frequencies<'a>(
+    spectrum: &'a FrequencySpectrum,
+    config: &ChromaConfig,
+) -> impl Iterator<Item = (f32, f32)> + 'a {
+    let (min, max) = (config.min_frequency.max(0.0), config.max_frequency);
+    spectrum
+        .data()
+        .iter()
+        .map(|(fr, val)| (fr.val(), val.val()))
+        .filter(move |(fr, _)| *fr > 0.0 && *fr >= min && *fr <= max)
+}
+
+/// Returns the MIDI pitch of the frequency, i.e. `69` for A4, as a
+/// floating point number.
+fn midi_pitch(frequency: f32, tuning: f32) -> f32 {
+    69.0 + 12.0 * libm::log2f(frequency / tuning)
+}
+
+/// Errors of the chroma extraction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ChromaError {
+    /// The tuning isn't positive and finite, or the minimum frequency isn't
+    /// below the maximum frequency.
+    InvalidConfig,
+}
+
+impl Display for ChromaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidConfig => write!(f, "Invalid chroma config!"),
+        }
+    }
+}
+
+impl Error for ChromaError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::windows::hann_window;
+    use crate::{FrequencyLimit, samples_fft_to_spectrum};
+
+    /// Spectrum of the sum of sines with harmonics.
+    fn spectrum_of(frequencies: &[f32]) -> FrequencySpectrum {
+        let samples = (0..8192)
+            .map(|i| {
+                let t = i as f32 / 44100.0;
+                frequencies
+                    .iter()
+                    .flat_map(|fr| {
+                        (1..=3).map(move |h| libm::sinf(2.0 * PI * fr * h as f32 * t) / h as f32)
+                    })
+                    .sum::<f32>()
+            })
+            .collect::<Vec<_>>();
+        samples_fft_to_spectrum(&hann_window(&samples), 44100, FrequencyLimit::All, None).unwrap()
+    }
+
+    #[test]
+    fn test_c_major_chord() {
+        // C4, E4, G4
+        let spectrum = spectrum_of(&[261.63, 329.63, 392.0]);
+        let chroma = spectrum_to_chroma(&spectrum, &ChromaConfig::default()).unwrap();
+        let values = chroma.values();
+        assert!([0, 4, 7].contains(&chroma.max_pitch_class()));
+        assert_eq!(values.iter().copied().fold(0.0, f32::max), 1.0);
+        for (i, val) in values.iter().enumerate() {
+            if [0, 4, 7].contains(&i) {
+                assert!(*val > 0.3, "{}: {val}", PITCH_CLASS_NAMES[i]);
+            } else if i != 11 {
+                // B is the third harmonic of E
+                assert!(*val < 0.1, "{}: {val}", PITCH_CLASS_NAMES[i]);
+            }
+        }
+
+        let config = ChromaConfig {
+            normalization: ChromaNormalization::Sum,
+            ..Default::default()
+        };
+        let sum = spectrum_to_chroma(&spectrum, &config)
+            .unwrap()
+            .values()
+            .iter()
+            .sum::<f32>();
+        assert!((sum - 1.0).abs() < 0.0001);
+        let config = ChromaConfig {
+            normalization: ChromaNormalization::Euclidean,
+            ..Default::default()
+        };
+        let chroma = spectrum_to_chroma(&spectrum, &config).unwrap();
+        let len = chroma.values().iter().map(|x| x * x).sum::<f32>();
+        assert!((len - 1.0).abs() < 0.0001);
+
+        let chromagram =
+            spectra_to_chromagram(&[spectrum, spectrum_of(&[261.63, 329.63, 392.0])], &config)
+                .unwrap();
+        assert_eq!(chromagram, [chroma, chroma]);
+    }
+
+    #[test]
+    fn test_tuning() {
+        // A4 tuned to 432 Hz, i.e. -31.8 cents
+        let spectrum = spectrum_of(&[432.0, 432.0 * libm::exp2f(7.0 / 12.0)]);
+        let deviation = estimate_tuning_deviation(&spectrum, &ChromaConfig::default()).unwrap();
+        assert!((deviation + 31.8).abs() < 3.0, "{deviation}");
+
+        // with the estimated tuning, the pitches are correct
+        let config = ChromaConfig {
+            tuning: 440.0 * libm::exp2f(deviation / 1200.0),
+            ..Default::default()
+        };
+        assert!(estimate_tuning_deviation(&spectrum, &config).unwrap().abs() < 3.0);
+        let chroma = spectrum_to_chroma(&spectrum, &config).unwrap();
+        // A and E
+        assert!(chroma.values()[9] > 0.5 && chroma.values()[4] > 0.5);
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let spectrum = spectrum_of(&[440.0]);
+        let configs = [
+            ChromaConfig {
+                tuning: 0.0,
+                ..Default::default()
+            },
+            ChromaConfig {
+                tuning: f32::NAN,
+                ..Default::default()
+            },
+            ChromaConfig {
+                min_frequency: 5000.0,
+                max_frequency: 50.0,
+                ..Default::default()
+            },
+            ChromaConfig {
+                max_frequency: f32::NAN,
+                ..Default::default()
+            },
+        ];
+        for config in configs {
+            assert_eq!(
+                spectrum_to_chroma(&spectrum, &config),
+                Err(ChromaError::InvalidConfig)
+            );
+            assert_eq!(
+                spectra_to_chromagram(&[], &config),
+                Err(ChromaError::InvalidConfig)
+            );
+            assert_eq!(
+                estimate_tuning_deviation(&spectrum, &config),
+                Err(ChromaError::InvalidConfig)
+            );
+        }
+        let config = ChromaConfig {
+            max_frequency: f32::INFINITY,
+            ..Default::default()
+        };
+        assert!(spectrum_to_chroma(&spectrum, &config).is_ok());
+    }
+}
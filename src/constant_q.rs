@@ -0,0 +1,417 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the struct [`ConstantQTransform`], which calculates a spectrum
+//! with logarithmically spaced frequencies, e.g. one bin per semitone.
+
+use crate::fft::{Complex32, DefaultFftBackend, Fft, FftBackend, MAX_FFT_LEN};
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::windows::hann_window;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::f32::consts::PI;
+use core::fmt::{Display, Formatter};
+
+/// Values of a spectral kernel below this fraction of its maximum are
+/// dropped. They hardly contribute but cost most of the time.
+const KERNEL_THRESHOLD: f32 = 0.005;
+
+/// Constant-Q transform (CQT) with musical bin spacing.
+///
+/// The centre frequencies are `min_frequency * 2^(k / bins_per_octave)`.
+/// Each bin has the same ratio `Q` of its frequency to its bandwidth, so low
+/// frequencies are analyzed with longer windows than high frequencies. This
+/// follows the efficient algorithm of Brown and Puckette (1992): the
+/// windowed complex exponential of each bin is transformed into a sparse
+/// spectral kernel once, and each transform is a single FFT of the samples
+/// followed by the products with all kernels.
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::constant_q::ConstantQTransform;
+///
+/// // A1 (55 Hz) to A5 with one bin per semitone
+/// let mut cqt = ConstantQTransform::new(44100.0, 55.0, 12, 4).unwrap();
+/// let samples = vec![0.0; cqt.fft_len()];
+/// let bins = cqt.transform(&samples).unwrap();
+/// assert_eq!(bins.len(), 48);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConstantQTransform<B: FftBackend = DefaultFftBackend> {
+    /// The FFT, prepared for `fft_len` samples.
+    fft: Fft<B>,
+    /// The used sampling rate, e.g. `44100 [Hz]`.
+    sampling_rate: f32,
+    /// Number of bins per octave, e.g. `12` for semitones.
+    bins_per_octave: usize,
+    /// Ratio of each centre frequency to its bandwidth.
+    q: f32,
+    /// Number of samples of each transform.
+    fft_len: usize,
+    /// Centre frequency of each bin.
+    frequencies: Vec<Frequency>,
+    /// Sparse spectral kernel of each bin: indices of the FFT result with
+    /// the corresponding factor.
+    kernels: Vec<Vec<(usize, Complex32)>>,
+}
+
+impl ConstantQTransform {
+    /// Calculates the kernels of the transform.
+    ///
+    /// ## Parameters
+    /// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+    /// * `min_frequency` Centre frequency of the lowest bin, e.g. `32.7` for
+    ///   C1.
+    /// * `bins_per_octave` Number of bins per octave, e.g. `12` or `36`.
+    /// * `octaves` Number of octaves. The highest bin must be below the
+    ///   Nyquist frequency.
+    ///
+    /// The longest window, i.e. the one of the lowest bin, must fit into an
+    /// FFT of at most 32768 samples (without the `large-fft` or the `rustfft`
+    /// feature), i.e.
+    /// about
+    /// `(bins_per_octave * 1.44 + 0.5) * sampling_rate / min_frequency`
+    /// samples.
+    pub fn new(
+        sampling_rate: f32,
+        min_frequency: f32,
+        bins_per_octave: usize,
+        octaves: usize,
+    ) -> Result<Self, ConstantQError> {
+        if !sampling_rate.is_finite()
+            || !min_frequency.is_finite()
+            || min_frequency <= 0.0
+            || bins_per_octave == 0
+        {
+            return Err(ConstantQError::InvalidConfig);
+        }
+        let bins = bins_per_octave * octaves;
+        let frequencies = (0..bins)
+            .map(|k| min_frequency * libm::exp2f(k as f32 / bins_per_octave as f32))
+            .collect::<Vec<_>>();
+        let max_frequency = frequencies.last().ok_or(ConstantQError::InvalidConfig)?;
+        if *max_frequency >= sampling_rate / 2.0 {
+            return Err(ConstantQError::InvalidConfig);
+        }
+
+        let q = 1.0 / (libm::exp2f(1.0 / bins_per_octave as f32) - 1.0);
+        let window_len = |fr: f32| libm::ceilf(q * sampling_rate / fr) as usize;
+        let fft_len = window_len(min_frequency).next_power_of_two();
+        if fft_len > MAX_FFT_LEN {
+            return Err(ConstantQError::FftLenTooBig(fft_len));
+        }
+
+        let mut fft = Fft::default();
+        fft.prepare(fft_len);
+        let mut re = vec![0.0; fft_len];
+        let mut im = vec![0.0; fft_len];
+        let kernels = frequencies
+            .iter()
+            .map(|fr| {
+                // temporal kernel: normalized window times complex exponential,
+                // centred in the FFT
+                let len = window_len(*fr);
+                let window = hann_window(&vec![1.0; len]);
+                let window_sum = window.iter().sum::<f32>();
+                let offset = (fft_len - len) / 2;
+                re.iter_mut().for_each(|x| *x = 0.0);
+                im.iter_mut().for_each(|x| *x = 0.0);
+                for (n, w) in window.iter().enumerate() {
+                    let angle = 2.0 * PI * fr * n as f32 / sampling_rate;
+                    re[offset + n] = w / window_sum * libm::cosf(angle);
+                    im[offset + n] = w / window_sum * libm::sinf(angle);
+                }
+
+                // FFT(re + j*im) = FFT(re) + j*FFT(im). Only the positive
+                // frequencies are relevant for real samples.
+                let re_fft = fft.calc(&re);
+                let im_fft = fft.calc(&im);
+                let spectral_kernel = re_fft
+                    .iter()
+                    .zip(&im_fft)
+                    .map(|(re, im)| re + Complex32::new(-im.im, im.re))
+                    .collect::<Vec<_>>();
+                let max = spectral_kernel
+                    .iter()
+                    .map(|x| x.norm_sqr())
+                    .fold(0.0, f32::max);
+                let threshold = KERNEL_THRESHOLD * KERNEL_THRESHOLD * max;
+
+                // By Parseval: sum(x * conj(t)) = 1/N * sum(X * conj(K)).
+                // Doubled to get the amplitude of a sine.
+                spectral_kernel
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, val)| val.norm_sqr() >= threshold)
+                    .map(|(i, val)| (i, val.conj() * (2.0 / fft_len as f32)))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Ok(Self {
+            fft,
+            sampling_rate,
+            bins_per_octave,
+            q,
+            fft_len,
+            frequencies: frequencies.into_iter().map(Frequency::from).collect(),
+            kernels,
+        })
+    }
+}
+
+impl<B: FftBackend> ConstantQTransform<B> {
+    /// Calculates the FFTs of [`Self::transform`] with the given backend
+    /// instead of the [`DefaultFftBackend`]. The backend is prepared for
+    /// [`Self::fft_len`] samples.
+    ///
+    /// Returns [`ConstantQError::FftLenTooBig`] if the backend doesn't
+    /// support FFTs of [`Self::fft_len`] samples.
+    pub fn with_backend<C: FftBackend>(
+        self,
+        backend: C,
+    ) -> Result<ConstantQTransform<C>, ConstantQError> {
+        if self.fft_len > C::MAX_LEN {
+            return Err(ConstantQError::FftLenTooBig(self.fft_len));
+        }
+        let mut fft = Fft::new(backend);
+        fft.prepare(self.fft_len);
+        Ok(ConstantQTransform {
+            fft,
+            sampling_rate: self.sampling_rate,
+            bins_per_octave: self.bins_per_octave,
+            q: self.q,
+            fft_len: self.fft_len,
+            frequencies: self.frequencies,
+            kernels: self.kernels,
+        })
+    }
+
+    /// Returns the used sampling rate.
+    #[inline]
+    #[must_use]
+    pub const fn sampling_rate(&self) -> f32 {
+        self.sampling_rate
+    }
+
+    /// Returns the number of bins per octave.
+    #[inline]
+    #[must_use]
+    pub const fn bins_per_octave(&self) -> usize {
+        self.bins_per_octave
+    }
+
+    /// Returns the ratio of each centre frequency to its bandwidth.
+    #[inline]
+    #[must_use]
+    pub const fn q(&self) -> f32 {
+        self.q
+    }
+
+    /// Returns the number of samples that [`Self::transform`] requires.
+    #[inline]
+    #[must_use]
+    pub const fn fft_len(&self) -> usize {
+        self.fft_len
+    }
+
+    /// Returns the centre frequency of each bin, from lowest to highest.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // false positive
+    pub fn frequencies(&self) -> &[Frequency] {
+        &self.frequencies
+    }
+
+    /// Transforms the samples. Don't apply a window function, each bin has
+    /// its own one.
+    ///
+    /// ## Parameters
+    /// * `samples` Exactly [`Self::fft_len`] samples. The bins are centred
+    ///   in the middle of the samples.
+    ///
+    /// ## Return value
+    /// The centre frequency and the magnitude of each bin. The magnitude
+    /// is the amplitude of a sine at the centre frequency.
+    pub fn transform(
+        &mut self,
+        samples: &[f32],
+    ) -> Result<Vec<(Frequency, FrequencyValue)>, ConstantQError> {
+        if samples.len() != self.fft_len {
+            return Err(ConstantQError::SamplesLength(self.fft_len));
+        }
+        if samples.iter().any(|x| !x.is_finite()) {
+            return Err(ConstantQError::InvalidSamples);
+        }
+
+        let fft_res = self.fft.calc(samples);
+        let bins = self
+            .frequencies
+            .iter()
+            .zip(&self.kernels)
+            .map(|(fr, kernel)| {
+                let val = kernel
+                    .iter()
+                    .map(|(i, factor)| fft_res[*i] * factor)
+                    .sum::<Complex32>();
+                (*fr, FrequencyValue::from(libm::sqrtf(val.norm_sqr())))
+            })
+            .collect();
+        Ok(bins)
+    }
+}
+
+/// Errors of the [`ConstantQTransform`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConstantQError {
+    /// The minimum frequency, the number of bins, or the number of octaves
+    /// are invalid, or the highest bin is above the Nyquist frequency.
+    InvalidConfig,
+    /// The required FFT length is bigger than the supported maximum. Use
+    /// a higher minimum frequency or fewer bins per octave.
+    FftLenTooBig(usize),
+    /// The number of samples doesn't match the expected FFT length.
+    SamplesLength(usize /* expected */),
+    /// NaN or infinite samples are not supported.
+    InvalidSamples,
+}
+
+impl Display for ConstantQError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidConfig => write!(f, "Invalid constant-Q config!"),
+            Self::FftLenTooBig(x) => write!(f, "FFT length too big: {x}"),
+            Self::SamplesLength(x) => write!(f, "Expected {x} samples!"),
+            Self::InvalidSamples => write!(f, "NaN or infinite samples are not supported!"),
+        }
+    }
+}
+
+impl Error for ConstantQError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sine::sines;
+
+    #[test]
+    fn test_musical_bins() {
+        let mut cqt = ConstantQTransform::new(22050.0, 55.0, 12, 5).unwrap();
+        assert_eq!(cqt.frequencies().len(), 60);
+        assert_eq!(cqt.frequencies()[12].val(), 110.0);
+        assert!((cqt.q() - 16.817).abs() < 0.001);
+
+        // A4 and A#1 (58.27 Hz): a semitone next to the lowest bin
+        let samples = sines(&[440.0], 22050.0, cqt.fft_len())
+            .iter()
+            .zip(sines(&[58.27], 22050.0, cqt.fft_len()))
+            .map(|(a, b)| a + 0.5 * b)
+            .collect::<Vec<_>>();
+        let bins = cqt.transform(&samples).unwrap();
+
+        let a4 = bins[36];
+        assert_eq!(a4.0.val(), 440.0);
+        assert!((a4.1.val() - 1.0).abs() < 0.01, "{a4:?}");
+        let a_sharp_1 = bins[1];
+        assert!((a_sharp_1.1.val() - 0.5).abs() < 0.01, "{a_sharp_1:?}");
+        // The Hann window halves the neighbouring semitones, and suppresses
+        // the ones further away.
+        for (peak, i) in [(1, 0), (1, 2), (36, 35), (36, 37)] {
+            assert!(bins[i].1.val() < bins[peak].1.val() * 0.55, "{:?}", bins[i]);
+        }
+        for (peak, i) in [(1, 3), (36, 34), (36, 38)] {
+            assert!(bins[i].1.val() < bins[peak].1.val() * 0.05, "{:?}", bins[i]);
+        }
+    }
+
+    /// The sparse kernels must match the direct calculation with the
+    /// temporal kernels.
+    #[test]
+    fn test_matches_temporal_kernel() {
+        let sampling_rate = 8000.0;
+        let mut cqt = ConstantQTransform::new(sampling_rate, 100.0, 24, 3).unwrap();
+        let samples = (0..cqt.fft_len())
+            .map(|i| libm::sinf(i as f32 * 0.37) + libm::cosf(i as f32 * 0.011 * i as f32))
+            .collect::<Vec<_>>();
+        let bins = cqt.transform(&samples).unwrap();
+        let mut microfft = cqt
+            .clone()
+            .with_backend(crate::fft::Microfft::default())
+            .unwrap();
+        for ((_, val), (_, expected)) in microfft.transform(&samples).unwrap().iter().zip(&bins) {
+            assert!((val.val() - expected.val()).abs() < 0.0001);
+        }
+
+        for (fr, val) in bins {
+            let len = libm::ceilf(cqt.q() * sampling_rate / fr.val()) as usize;
+            let window = hann_window(&vec![1.0; len]);
+            let window_sum = window.iter().sum::<f32>();
+            let offset = (cqt.fft_len() - len) / 2;
+            let expected = window
+                .iter()
+                .enumerate()
+                .map(|(n, w)| {
+                    let angle = -2.0 * PI * fr.val() * n as f32 / sampling_rate;
+                    Complex32::new(libm::cosf(angle), libm::sinf(angle))
+                        * (samples[offset + n] * w / window_sum)
+                })
+                .sum::<Complex32>()
+                * 2.0;
+            let expected = libm::sqrtf(expected.norm_sqr());
+            assert!(
+                (val.val() - expected).abs() < 0.01,
+                "{fr}: {val} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert_eq!(
+            ConstantQTransform::new(44100.0, 0.0, 12, 4).unwrap_err(),
+            ConstantQError::InvalidConfig
+        );
+        assert_eq!(
+            ConstantQTransform::new(44100.0, 55.0, 12, 0).unwrap_err(),
+            ConstantQError::InvalidConfig
+        );
+        assert_eq!(
+            ConstantQTransform::new(f32::INFINITY, 55.0, 12, 4).unwrap_err(),
+            ConstantQError::InvalidConfig
+        );
+        assert_eq!(
+            ConstantQTransform::new(8000.0, 55.0, 12, 7).unwrap_err(),
+            ConstantQError::InvalidConfig
+        );
+        #[cfg(not(any(feature = "large-fft", feature = "rustfft")))]
+        assert_eq!(
+            ConstantQTransform::new(44100.0, 32.7, 36, 8).unwrap_err(),
+            ConstantQError::FftLenTooBig(131072)
+        );
+        let mut cqt = ConstantQTransform::new(8000.0, 100.0, 12, 3).unwrap();
+        assert_eq!(
+            cqt.transform(&[0.0; 16]).unwrap_err(),
+            ConstantQError::SamplesLength(cqt.fft_len())
+        );
+    }
+}
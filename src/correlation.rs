@@ -0,0 +1,234 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the autocorrelation and the cross-correlation of signals,
+//! calculated with the FFT (Wiener–Khinchin theorem).
+
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::{DefaultFftBackend, Fft, FftBackend};
+use alloc::vec::Vec;
+
+/// Calculates the autocorrelation `r[l] = sum(x[n] * x[n + l])` of the
+/// samples for all non-negative lags.
+///
+/// This is the inverse FFT of the power spectrum. The samples are zero
+/// padded, so the correlation is linear, not circular.
+///
+/// The values are not normalized. Divide them by the first value, i.e. the
+/// energy of the signal, to get values in interval `[-1; 1]`.
+///
+/// ## Parameters
+/// * `samples` Samples of any length. Including the zero padding, the FFT
+///   length must not exceed 32768, i.e. at most 16384 samples, unless the
+///   `large-fft` or the `rustfft` feature is enabled.
+/// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+///
+/// ## Return value
+/// The lag in seconds and the correlation, from lag `0` to the length of
+/// the samples.
+///
+/// ## Examples
+/// ```rust
+/// use spectrum_analyzer::correlation::autocorrelation;
+/// let correlation = autocorrelation(&[1.0, 2.0, 3.0], 1000.0).unwrap();
+/// assert_eq!(correlation.len(), 3);
+/// // 1*1 + 2*2 + 3*3
+/// assert!((correlation[0].1 - 14.0).abs() < 0.0001);
+/// // 1*2 + 2*3
+/// assert!((correlation[1].0 - 0.001).abs() < 0.0001);
+/// assert!((correlation[1].1 - 8.0).abs() < 0.0001);
+/// ```
+pub fn autocorrelation(
+    samples: &[f32],
+    sampling_rate: f32,
+) -> Result<Vec<(f32, f32)>, SpectrumAnalyzerError> {
+    autocorrelation_with_backend::<DefaultFftBackend>(samples, sampling_rate)
+}
+
+/// Like [`autocorrelation`] but calculates the FFTs with the given
+/// [`FftBackend`] instead of the [`DefaultFftBackend`].
+pub fn autocorrelation_with_backend<B: FftBackend>(
+    samples: &[f32],
+    sampling_rate: f32,
+) -> Result<Vec<(f32, f32)>, SpectrumAnalyzerError> {
+    let mut correlation = cross_correlation_with_backend::<B>(samples, samples, sampling_rate)?;
+    // symmetric: only keep the non-negative lags
+    correlation.drain(..samples.len() - 1);
+    Ok(correlation)
+}
+
+/// Calculates the cross-correlation `r[l] = sum(x[n] * y[n + l])` of two
+/// signals.
+///
+/// This is the inverse FFT of `conj(X) * Y`. The samples are zero
+/// padded, so the correlation is linear, not circular.
+///
+/// If `y` is a copy of `x` that is delayed by `d` seconds, the
+/// cross-correlation has its maximum at the lag `d`.
+///
+/// ## Parameters
+/// * `x` The first signal, e.g. the reference. Any length.
+/// * `y` The second signal, e.g. the delayed signal. Any length. Including
+///   the zero padding, the FFT length, i.e. the next power of two of
+///   `x.len() + y.len() - 1`, must not exceed 32768, unless the `large-fft` or
+///   the `rustfft` feature is enabled.
+/// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+///
+/// ## Return value
+/// The lag in seconds and the correlation, from lag `-(x.len() - 1)` to
+/// lag `y.len() - 1`.
+pub fn cross_correlation(
+    x: &[f32],
+    y: &[f32],
+    sampling_rate: f32,
+) -> Result<Vec<(f32, f32)>, SpectrumAnalyzerError> {
+    cross_correlation_with_backend::<DefaultFftBackend>(x, y, sampling_rate)
+}
+
+/// Like [`cross_correlation`] but calculates the FFTs with the given
+/// [`FftBackend`] instead of the [`DefaultFftBackend`].
+pub fn cross_correlation_with_backend<B: FftBackend>(
+    x: &[f32],
+    y: &[f32],
+    sampling_rate: f32,
+) -> Result<Vec<(f32, f32)>, SpectrumAnalyzerError> {
+    crate::verify_sampling_rate(sampling_rate)?;
+    if x.is_empty() || y.is_empty() {
+        return Err(SpectrumAnalyzerError::TooFewSamples);
+    }
+    let samples = || x.iter().chain(y);
+    if samples().any(|x| x.is_nan()) {
+        return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+    }
+    if samples().any(|x| x.is_infinite()) {
+        return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+    }
+    let fft_len = (x.len() + y.len() - 1).next_power_of_two().max(2);
+    if fft_len > B::MAX_LEN {
+        return Err(SpectrumAnalyzerError::TooManySamples);
+    }
+
+    let mut fft = Fft::<B>::default();
+    let mut zero_padded = |samples: &[f32]| {
+        let mut padded = samples.to_vec();
+        padded.resize(fft_len, 0.0);
+        fft.calc_full(&padded)
+    };
+    let x_fft = zero_padded(x);
+    let y_fft = zero_padded(y);
+    let cross_spectrum = x_fft
+        .iter()
+        .zip(y_fft)
+        .map(|(x, y)| x.conj() * y)
+        .collect::<Vec<_>>();
+    let correlation = fft.calc_inverse(&cross_spectrum);
+
+    // negative lags are at the end of the result
+    let lags = -(x.len() as isize - 1)..=(y.len() as isize - 1);
+    let correlation = lags
+        .map(|lag| {
+            let index = lag.rem_euclid(fft_len as isize) as usize;
+            (lag as f32 / sampling_rate, correlation[index].re)
+        })
+        .collect();
+    Ok(correlation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sine::sines;
+    fn max_lag(correlation: &[(f32, f32)]) -> f32 {
+        correlation
+            .iter()
+            .max_by(|(_, l), (_, r)| l.total_cmp(r))
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_cross_correlation_of_delayed_sine() {
+        let x = sines(&[100.0], 8000.0, 1000);
+        // delayed by 12 samples, i.e. 1.5 ms
+        let y = [0.0; 12]
+            .into_iter()
+            .chain(x.iter().copied())
+            .take(1000)
+            .collect::<Vec<_>>();
+        let correlation = cross_correlation(&x, &y, 8000.0).unwrap();
+        assert_eq!(correlation.len(), 1999);
+        assert_eq!(correlation.first().unwrap().0, -999.0 / 8000.0);
+        assert_eq!(max_lag(&correlation), 0.0015);
+
+        // swapped: negative lag
+        let correlation = cross_correlation(&y, &x, 8000.0).unwrap();
+        assert_eq!(max_lag(&correlation), -0.0015);
+
+        // matches the definition
+        for (i, (_, val)) in correlation.iter().enumerate().step_by(97) {
+            let lag = i as isize - 999;
+            let expected = (0..1000)
+                .filter_map(|n| {
+                    let m = n as isize + lag;
+                    (0..1000).contains(&m).then(|| y[n] * x[m as usize])
+                })
+                .sum::<f32>();
+            assert!((val - expected).abs() < 0.01, "{lag}: {val} != {expected}");
+        }
+    }
+
+    #[test]
+    fn test_autocorrelation_of_sine() {
+        let x = sines(&[100.0], 8000.0, 800);
+        let correlation = autocorrelation(&x, 8000.0).unwrap();
+        assert_eq!(correlation.len(), 800);
+        // energy
+        assert!((correlation[0].1 - 400.0).abs() < 0.01);
+        // the period of the sine: 10 ms
+        assert_eq!(max_lag(&correlation[40..]), 0.01);
+
+        let microfft = autocorrelation_with_backend::<crate::fft::Microfft>(&x, 8000.0).unwrap();
+        for ((lag, val), (expected_lag, expected_val)) in microfft.iter().zip(&correlation) {
+            assert_eq!(lag, expected_lag);
+            assert!((val - expected_val).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(matches!(
+            autocorrelation(&[], 8000.0),
+            Err(SpectrumAnalyzerError::TooFewSamples)
+        ));
+        assert!(matches!(
+            cross_correlation(&[1.0], &[f32::NAN], 8000.0),
+            Err(SpectrumAnalyzerError::NaNValuesNotSupported)
+        ));
+        #[cfg(not(any(feature = "large-fft", feature = "rustfft")))]
+        assert!(matches!(
+            autocorrelation(&[0.0; 16385], 8000.0),
+            Err(SpectrumAnalyzerError::TooManySamples)
+        ));
+        assert_eq!(autocorrelation(&[2.0], 8000.0).unwrap(), [(0.0, 4.0)]);
+    }
+}
@@ -0,0 +1,540 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the struct [`CrossSpectrum`], which estimates the cross power
+//! spectral density, the coherence, and the transfer function between two
+//! signals, e.g. for loudspeaker and room measurements.
+
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::{Complex32, DefaultFftBackend, Fft, FftBackend};
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::limit::FrequencyLimit;
+use alloc::vec::Vec;
+
+/// Magnitude and phase of a complex quantity at a certain frequency, such as
+/// the value of a transfer function.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PolarValue {
+    /// The frequency.
+    frequency: Frequency,
+    /// The magnitude, i.e. the absolute value.
+    magnitude: FrequencyValue,
+    /// The phase in radians in interval `[-π; π]`.
+    phase: f32,
+}
+
+impl PolarValue {
+    /// Creates the value from a complex number.
+    pub(crate) fn from_complex(frequency: Frequency, val: Complex32) -> Self {
+        Self {
+            frequency,
+            magnitude: libm::sqrtf(val.re * val.re + val.im * val.im).into(),
+            phase: libm::atan2f(val.im, val.re),
+        }
+    }
+
+    /// Returns the frequency.
+    #[inline]
+    #[must_use]
+    pub const fn frequency(&self) -> Frequency {
+        self.frequency
+    }
+
+    /// Returns the magnitude, i.e. the absolute value.
+    #[inline]
+    #[must_use]
+    pub const fn magnitude(&self) -> FrequencyValue {
+        self.magnitude
+    }
+
+    /// Returns the phase in radians in interval `[-π; π]`.
+    #[inline]
+    #[must_use]
+    pub const fn phase(&self) -> f32 {
+        self.phase
+    }
+}
+
+/// Averaged auto and cross spectra of a reference signal `x` and a measured
+/// signal `y`, estimated with Welch's method.
+///
+/// Both signals are split into windowed, possibly overlapping segments. The
+/// one-sided power spectral densities `Pxx` and `Pyy` and the cross power
+/// spectral density `Pxy = conj(X) * Y` are averaged over all segments. All
+/// other quantities, such as the coherence and the transfer functions
+/// [`Self::h1`] and [`Self::h2`], are derived from them.
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::cross_spectrum::CrossSpectrum;
+/// use spectrum_analyzer::windows::hann_window;
+/// use spectrum_analyzer::FrequencyLimit;
+///
+/// // the test signal and the signal recorded by the microphone
+/// let reference = (0..4096).map(|i| ((i * 7919) % 1000) as f32 / 1000.0 - 0.5).collect::<Vec<_>>();
+/// let measured = reference.iter().map(|x| x * 0.5).collect::<Vec<_>>();
+///
+/// let cross = CrossSpectrum::new(
+///     &reference,
+///     &measured,
+///     48000.0,
+///     1024,
+///     512,
+///     hann_window,
+///     FrequencyLimit::All,
+/// )
+/// .unwrap();
+/// let transfer_function = cross.h1();
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrossSpectrum {
+    /// Frequencies of all values, from lowest to highest.
+    frequencies: Vec<Frequency>,
+    /// The frequency resolution of each segment.
+    frequency_resolution: f32,
+    /// Number of averaged segments.
+    segments: usize,
+    /// Power spectral density of the reference signal.
+    pxx: Vec<f32>,
+    /// Power spectral density of the measured signal.
+    pyy: Vec<f32>,
+    /// Cross power spectral density of both signals.
+    pxy: Vec<Complex32>,
+}
+
+impl CrossSpectrum {
+    /// Estimates the spectra of both signals.
+    ///
+    /// ## Parameters
+    /// * `reference` The reference signal `x`, e.g. the test signal that is
+    ///   sent to the loudspeaker.
+    /// * `measured` The measured signal `y`, e.g. the microphone signal. Must
+    ///   have the same length as `reference`.
+    /// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+    /// * `segment_len` Number of samples of each segment. Must be a power of
+    ///   two and not bigger than the signals.
+    /// * `hop_size` Number of samples between the beginnings of two segments,
+    ///   e.g. `segment_len / 2` for an overlap of 50%.
+    /// * `window_fn` Window function that is applied to each segment, e.g.
+    ///   [`crate::windows::hann_window`].
+    /// * `frequency_limit` The [`FrequencyLimit`].
+    pub fn new(
+        reference: &[f32],
+        measured: &[f32],
+        sampling_rate: f32,
+        segment_len: usize,
+        hop_size: usize,
+        window_fn: fn(&[f32]) -> Vec<f32>,
+        frequency_limit: FrequencyLimit,
+    ) -> Result<Self, SpectrumAnalyzerError> {
+        Self::new_with_backend::<DefaultFftBackend>(
+            reference,
+            measured,
+            sampling_rate,
+            segment_len,
+            hop_size,
+            window_fn,
+            frequency_limit,
+        )
+    }
+
+    /// Like [`Self::new`] but calculates the FFTs with the given
+    /// [`FftBackend`] instead of the [`DefaultFftBackend`].
+    pub fn new_with_backend<B: FftBackend>(
+        reference: &[f32],
+        measured: &[f32],
+        sampling_rate: f32,
+        segment_len: usize,
+        hop_size: usize,
+        window_fn: fn(&[f32]) -> Vec<f32>,
+        frequency_limit: FrequencyLimit,
+    ) -> Result<Self, SpectrumAnalyzerError> {
+        if reference.len() != measured.len() {
+            return Err(SpectrumAnalyzerError::InvalidChannelLayout);
+        }
+        if segment_len < 2 || hop_size == 0 || reference.len() < segment_len {
+            return Err(SpectrumAnalyzerError::TooFewSamples);
+        }
+        if !segment_len.is_power_of_two() {
+            return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+        }
+        if segment_len > B::MAX_LEN {
+            return Err(SpectrumAnalyzerError::TooManySamples);
+        }
+        let samples = || reference.iter().chain(measured);
+        if samples().any(|x| x.is_nan()) {
+            return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+        }
+        if samples().any(|x| x.is_infinite()) {
+            return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+        }
+        crate::verify_sampling_rate(sampling_rate)?;
+        frequency_limit
+            .verify(sampling_rate / 2.0)
+            .map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+
+        let frequency_resolution = sampling_rate / segment_len as f32;
+        let fft_indices = (0..=segment_len / 2)
+            .filter(|i| frequency_limit.contains(*i as f32 * frequency_resolution))
+            .collect::<Vec<_>>();
+        // Consistent with `FrequencySpectrum`.
+        if fft_indices.len() < 2 {
+            return Err(SpectrumAnalyzerError::FrequencyLimitTooNarrow);
+        }
+
+        let window = window_fn(&vec![1.0; segment_len]);
+        let mut fft = Fft::<B>::default();
+        fft.prepare(segment_len);
+        let mut x_buffer = vec![0.0; segment_len];
+        let mut y_buffer = vec![0.0; segment_len];
+        let mut pxx = vec![0.0; fft_indices.len()];
+        let mut pyy = vec![0.0; fft_indices.len()];
+        let mut pxy = vec![Complex32::new(0.0, 0.0); fft_indices.len()];
+
+        let segments = (reference.len() - segment_len) / hop_size + 1;
+        for segment in 0..segments {
+            let begin = segment * hop_size;
+            for (buffer, signal) in [(&mut x_buffer, reference), (&mut y_buffer, measured)] {
+                for ((windowed, sample), coefficient) in buffer
+                    .iter_mut()
+                    .zip(&signal[begin..begin + segment_len])
+                    .zip(&window)
+                {
+                    *windowed = sample * coefficient;
+                }
+            }
+            let x_res = fft.calc_in_place(&mut x_buffer);
+            let y_res = fft.calc_in_place(&mut y_buffer);
+
+            for (i, fft_index) in fft_indices.iter().enumerate() {
+                let x = unpack(x_res, *fft_index);
+                let y = unpack(y_res, *fft_index);
+                pxx[i] += x.norm_sqr();
+                pyy[i] += y.norm_sqr();
+                pxy[i] += x.conj() * y;
+            }
+        }
+
+        // scale the sums to the averaged one-sided densities
+        let window_energy = window.iter().map(|w| w * w).sum::<f32>();
+        for (i, fft_index) in fft_indices.iter().enumerate() {
+            let is_dc_or_nyquist = *fft_index == 0 || *fft_index == segment_len / 2;
+            let factor = if is_dc_or_nyquist { 1.0 } else { 2.0 };
+            let factor = factor / (sampling_rate * window_energy * segments as f32);
+            pxx[i] *= factor;
+            pyy[i] *= factor;
+            pxy[i] *= factor;
+        }
+
+        Ok(Self {
+            frequencies: fft_indices
+                .iter()
+                .map(|i| (*i as f32 * frequency_resolution).into())
+                .collect(),
+            frequency_resolution,
+            segments,
+            pxx,
+            pyy,
+            pxy,
+        })
+    }
+
+    /// Returns the frequency resolution of each segment.
+    #[inline]
+    #[must_use]
+    pub const fn frequency_resolution(&self) -> f32 {
+        self.frequency_resolution
+    }
+
+    /// Returns the number of averaged segments.
+    #[inline]
+    #[must_use]
+    pub const fn segments(&self) -> usize {
+        self.segments
+    }
+
+    /// Returns the one-sided power spectral density of the reference signal
+    /// in `unit²/Hz`.
+    #[must_use]
+    pub fn reference_psd(&self) -> Vec<(Frequency, FrequencyValue)> {
+        self.with_frequencies(self.pxx.iter().copied())
+    }
+
+    /// Returns the one-sided power spectral density of the measured signal
+    /// in `unit²/Hz`.
+    #[must_use]
+    pub fn measured_psd(&self) -> Vec<(Frequency, FrequencyValue)> {
+        self.with_frequencies(self.pyy.iter().copied())
+    }
+
+    /// Returns the one-sided cross power spectral density `Pxy`. Its phase is
+    /// the phase of the measured signal relative to the reference signal.
+    #[must_use]
+    pub fn cross_psd(&self) -> Vec<PolarValue> {
+        self.with_frequencies_polar(self.pxy.iter().copied())
+    }
+
+    /// Returns the magnitude-squared coherence `|Pxy|² / (Pxx * Pyy)` in
+    /// interval `[0; 1]`. A value of `1` means that the measured signal is
+    /// fully explained by a linear system driven by the reference signal.
+    ///
+    /// Frequencies without any power in one of the signals have a coherence
+    /// of `0`. With a single segment, the coherence is always `1`.
+    #[must_use]
+    pub fn coherence(&self) -> Vec<(Frequency, FrequencyValue)> {
+        let values =
+            self.pxy
+                .iter()
+                .zip(self.pxx.iter().zip(&self.pyy))
+                .map(|(pxy, (pxx, pyy))| {
+                    let auto = pxx * pyy;
+                    if auto > 0.0 {
+                        (pxy.norm_sqr() / auto).min(1.0)
+                    } else {
+                        0.0
+                    }
+                });
+        self.with_frequencies(values)
+    }
+
+    /// Returns the transfer function estimate `H1 = Pxy / Pxx`. It is
+    /// unbiased by noise in the measured signal, which is the common case.
+    ///
+    /// Frequencies without any power in the reference signal have a
+    /// magnitude of `0`.
+    #[must_use]
+    pub fn h1(&self) -> Vec<PolarValue> {
+        let values = self.pxy.iter().zip(&self.pxx).map(|(pxy, pxx)| {
+            if *pxx > 0.0 {
+                pxy / pxx
+            } else {
+                Complex32::new(0.0, 0.0)
+            }
+        });
+        self.with_frequencies_polar(values)
+    }
+
+    /// Returns the transfer function estimate `H2 = Pyy / Pyx`, where `Pyx`
+    /// is the complex conjugate of `Pxy`. It is unbiased by noise in the
+    /// reference signal.
+    ///
+    /// Frequencies without any cross power have a magnitude of `0`.
+    #[must_use]
+    pub fn h2(&self) -> Vec<PolarValue> {
+        let values = self.pxy.iter().zip(&self.pyy).map(|(pxy, pyy)| {
+            if pxy.norm_sqr() > 0.0 {
+                pyy / pxy.conj()
+            } else {
+                Complex32::new(0.0, 0.0)
+            }
+        });
+        self.with_frequencies_polar(values)
+    }
+
+    /// Combines real values with their frequencies.
+    fn with_frequencies(
+        &self,
+        values: impl Iterator<Item = f32>,
+    ) -> Vec<(Frequency, FrequencyValue)> {
+        self.frequencies
+            .iter()
+            .zip(values)
+            .map(|(fr, val)| (*fr, val.into()))
+            .collect()
+    }
+
+    /// Combines complex values with their frequencies.
+    fn with_frequencies_polar(&self, values: impl Iterator<Item = Complex32>) -> Vec<PolarValue> {
+        self.frequencies
+            .iter()
+            .zip(values)
+            .map(|(fr, val)| PolarValue::from_complex(*fr, val))
+            .collect()
+    }
+}
+
+/// Returns the FFT result at the given index of a buffer from
+/// [`Fft::calc_in_place`], where the real value of the Nyquist frequency
+/// is packed inside the imaginary part of the DC component.
+fn unpack(fft_res: &[Complex32], fft_index: usize) -> Complex32 {
+    match fft_index {
+        0 => Complex32::new(fft_res[0].re, 0.0),
+        i if i == fft_res.len() => Complex32::new(fft_res[0].im, 0.0),
+        i => fft_res[i],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sine::uniform_noise;
+    use crate::windows::hann_window;
+    use core::f32::consts::PI;
+
+    #[test]
+    fn test_delayed_and_attenuated() {
+        let x = uniform_noise(16384, 42);
+        // y[n] = 0.5 * x[n - 2]
+        let y = [0.0, 0.0]
+            .into_iter()
+            .chain(x.iter().map(|x| x * 0.5))
+            .take(x.len())
+            .collect::<Vec<_>>();
+        let cross =
+            CrossSpectrum::new(&x, &y, 1000.0, 512, 256, hann_window, FrequencyLimit::All).unwrap();
+        assert_eq!(cross.segments(), 63);
+
+        let coherence = cross.coherence();
+        for (h1, h2) in cross.h1().iter().zip(cross.h2()).skip(1) {
+            let fr = h1.frequency().val();
+            assert!((h1.magnitude().val() - 0.5).abs() < 0.01, "{h1:?}");
+            assert!((h2.magnitude().val() - 0.5).abs() < 0.01, "{h2:?}");
+            // a delay of two samples
+            let expected_phase = libm::remainderf(-2.0 * PI * fr * 2.0 / 1000.0, 2.0 * PI);
+            assert!((h1.phase() - expected_phase).abs() < 0.02, "{h1:?}");
+        }
+        assert!(coherence.iter().all(|(_, c)| c.val() > 0.99));
+
+        let microfft = CrossSpectrum::new_with_backend::<crate::fft::Microfft>(
+            &x,
+            &y,
+            1000.0,
+            512,
+            256,
+            hann_window,
+            FrequencyLimit::All,
+        )
+        .unwrap();
+        for (h1, expected) in microfft.h1().iter().zip(cross.h1()) {
+            assert!((h1.magnitude().val() - expected.magnitude().val()).abs() < 0.0001);
+        }
+    }
+
+    /// Uncorrelated noise in the measured signal lowers the coherence and
+    /// biases H2 but not H1.
+    #[test]
+    fn test_noisy_measurement() {
+        let x = uniform_noise(16384, 42);
+        let y = x
+            .iter()
+            .zip(uniform_noise(16384, 1337))
+            .map(|(x, n)| x + n)
+            .collect::<Vec<_>>();
+        let cross =
+            CrossSpectrum::new(&x, &y, 1000.0, 256, 128, hann_window, FrequencyLimit::All).unwrap();
+
+        let count = cross.h1().len() as f32;
+        let mean = |values: &[f32]| values.iter().sum::<f32>() / count;
+        let h1 = mean(
+            &cross
+                .h1()
+                .iter()
+                .map(|h| h.magnitude().val())
+                .collect::<Vec<_>>(),
+        );
+        let h2 = mean(
+            &cross
+                .h2()
+                .iter()
+                .map(|h| h.magnitude().val())
+                .collect::<Vec<_>>(),
+        );
+        let coherence = mean(
+            &cross
+                .coherence()
+                .iter()
+                .map(|(_, c)| c.val())
+                .collect::<Vec<_>>(),
+        );
+        assert!((h1 - 1.0).abs() < 0.05, "{h1}");
+        assert!((h2 - 2.0).abs() < 0.2, "{h2}");
+        assert!((coherence - 0.5).abs() < 0.05, "{coherence}");
+    }
+
+    #[test]
+    fn test_psd_and_limit() {
+        let x = (0..4096)
+            .map(|i| libm::sinf(2.0 * PI * 100.0 * i as f32 / 1000.0))
+            .collect::<Vec<_>>();
+        let cross = CrossSpectrum::new(
+            &x,
+            &x,
+            1000.0,
+            1024,
+            1024,
+            hann_window,
+            FrequencyLimit::Range(50.0, 150.0),
+        )
+        .unwrap();
+        assert_eq!(cross.segments(), 4);
+        let psd = cross.reference_psd();
+        assert_eq!(psd.first().unwrap().0.val(), 50.78125);
+        // mean power of the sine
+        let power =
+            psd.iter().map(|(_, val)| val.val()).sum::<f32>() * cross.frequency_resolution();
+        assert!((power - 0.5).abs() < 0.001, "{power}");
+        assert_eq!(psd, cross.measured_psd());
+        for (cross_psd, (_, psd)) in cross.cross_psd().iter().zip(&psd) {
+            float_cmp::assert_approx_eq!(f32, cross_psd.magnitude().val(), psd.val());
+            assert_eq!(cross_psd.phase(), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let x = [0.0; 64];
+        let new = |x: &[f32], y: &[f32], segment_len, hop_size| {
+            CrossSpectrum::new(
+                x,
+                y,
+                8.0,
+                segment_len,
+                hop_size,
+                hann_window,
+                FrequencyLimit::All,
+            )
+        };
+        assert!(matches!(
+            new(&x, &x[..32], 16, 8),
+            Err(SpectrumAnalyzerError::InvalidChannelLayout)
+        ));
+        assert!(matches!(
+            new(&x, &x, 128, 8),
+            Err(SpectrumAnalyzerError::TooFewSamples)
+        ));
+        assert!(matches!(
+            new(&x, &x, 16, 0),
+            Err(SpectrumAnalyzerError::TooFewSamples)
+        ));
+        assert!(matches!(
+            new(&x, &x, 12, 8),
+            Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo)
+        ));
+        let nan = [f32::NAN; 64];
+        assert!(matches!(
+            new(&x, &nan, 16, 8),
+            Err(SpectrumAnalyzerError::NaNValuesNotSupported)
+        ));
+        new(&x, &x, 16, 8).unwrap();
+    }
+}
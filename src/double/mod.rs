@@ -0,0 +1,297 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+//! Double precision (`f64`) analysis path, from the windows through the FFT to
+//! the spectrum, its statistics, and the scaling.
+//!
+//! The default API of this crate uses `f32` and the very fast FFT of
+//! [`microfft`]. For large FFTs of high-resolution samples, e.g. 32768 points
+//! of 24-bit audio, the rounding errors of `f32` raise the noise floor. This
+//! module mirrors the default API with `f64` values and a radix-2 FFT that
+//! calculates all twiddle factors directly. It is slower, but its noise floor
+//! is about nine orders of magnitude lower.
+//!
+//! ## Example
+//! ```rust
+//! use spectrum_analyzer::double::{samples_fft_to_spectrum, scaling, windows, FrequencyLimit};
+//! // get data from audio source
+//! let samples = windows::hann_window(&[0.0, 1.1, 5.5, -5.5]);
+//! let spectrum = samples_fft_to_spectrum(
+//!     &samples,
+//!     44100.0,
+//!     FrequencyLimit::All,
+//!     Some(&scaling::divide_by_N_sqrt),
+//! )
+//! .unwrap();
+//! assert_eq!(spectrum.data().len(), 3);
+//! ```
+
+pub mod scaling;
+mod spectrum;
+
+pub use crate::windows;
+pub use spectrum::FrequencySpectrum;
+
+/// Like [`crate::FrequencyLimit`] but with `f64` values.
+pub type FrequencyLimit = crate::FrequencyLimit<f64>;
+
+/// Like [`crate::SpectrumOutput`] but with an `f64` window energy.
+pub type SpectrumOutput = crate::SpectrumOutput<f64>;
+
+use crate::error::SpectrumAnalyzerError;
+use crate::radix2;
+use alloc::vec::Vec;
+use scaling::SpectrumScalingFunction;
+
+/// Like [`crate::samples_fft_to_spectrum`] but with `f64` samples and a
+/// fractional `f64` sampling rate.
+///
+/// The number of samples must be a power of two, but unlike with `f32`, it
+/// is not limited to 32768, even without the `large-fft` feature.
+pub fn samples_fft_to_spectrum(
+    samples: &[f64],
+    sampling_rate: f64,
+    frequency_limit: FrequencyLimit,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    samples_fft_to_spectrum_with_output(
+        samples,
+        sampling_rate,
+        frequency_limit,
+        SpectrumOutput::Magnitude,
+        scaling_fn,
+    )
+}
+
+/// Like [`crate::samples_fft_to_spectrum_with_output`] but with `f64` samples
+/// and a fractional `f64` sampling rate. See [`samples_fft_to_spectrum`].
+pub fn samples_fft_to_spectrum_with_output(
+    samples: &[f64],
+    sampling_rate: f64,
+    frequency_limit: FrequencyLimit,
+    output: SpectrumOutput,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    if samples.len() < 2 {
+        return Err(SpectrumAnalyzerError::TooFewSamples);
+    }
+    if samples.iter().any(|x| x.is_nan()) {
+        return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+    }
+    if samples.iter().any(|x| x.is_infinite()) {
+        return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+    }
+    if !samples.len().is_power_of_two() {
+        return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+    }
+    if !sampling_rate.is_finite() || sampling_rate <= 0.0 {
+        return Err(SpectrumAnalyzerError::InvalidSamplingRate(
+            sampling_rate as f32,
+        ));
+    }
+    frequency_limit
+        .verify(sampling_rate / 2.0)
+        .map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+    output.verify()?;
+
+    let samples_len = samples.len();
+    let frequency_resolution = sampling_rate / samples_len as f64;
+    let fft_res = radix2::real_fft(samples);
+    let data = fft_res
+        .iter()
+        .enumerate()
+        .map(|(fft_index, val)| (fft_index, fft_index as f64 * frequency_resolution, val))
+        .filter(|(_, fr, _)| frequency_limit.contains(*fr))
+        .map(|(fft_index, fr, val)| {
+            let val = output.convert(val, fft_index, samples_len, sampling_rate);
+            (fr, val)
+        })
+        .collect::<Vec<_>>();
+    // statistics and interpolation require at least two frequency points
+    if data.len() < 2 {
+        return Err(SpectrumAnalyzerError::FrequencyLimitTooNarrow);
+    }
+
+    let mut working_buffer = data.clone();
+    let mut spectrum = FrequencySpectrum::new(
+        data,
+        frequency_resolution,
+        samples_len as u32,
+        &mut working_buffer,
+    );
+    if let Some(scaling_fn) = scaling_fn {
+        spectrum.apply_scaling_fn(scaling_fn, &mut working_buffer)?;
+    }
+    Ok(spectrum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f64::consts::PI;
+
+    #[test]
+    fn test_matches_f32() {
+        let samples = (0..1024)
+            .map(|i| libm::sin(i as f64 * 0.3) + 0.5 * libm::cos(i as f64 * 1.7))
+            .collect::<Vec<_>>();
+        let samples = windows::hann_window(&samples);
+        let samples_f32 = samples.iter().map(|x| *x as f32).collect::<Vec<_>>();
+        let spectrum = samples_fft_to_spectrum(
+            &samples,
+            44100.0,
+            FrequencyLimit::Range(100.0, 15000.0),
+            None,
+        )
+        .unwrap();
+        let expected = crate::samples_fft_to_spectrum(
+            &samples_f32,
+            44100,
+            crate::FrequencyLimit::Range(100.0, 15000.0),
+            None,
+        )
+        .unwrap();
+        assert_eq!(spectrum.data().len(), expected.data().len());
+        for ((fr, val), (expected_fr, expected_val)) in spectrum.data().iter().zip(expected.data())
+        {
+            assert!((fr - f64::from(expected_fr.val())).abs() < 0.001);
+            assert!((val - f64::from(expected_val.val())).abs() < 0.001);
+        }
+        assert!((spectrum.max().1 - f64::from(expected.max().1.val())).abs() < 0.001);
+        assert!((spectrum.median() - f64::from(expected.median().val())).abs() < 0.001);
+    }
+
+    /// A sine in the centre of a bin only has a value in that bin. All other
+    /// bins show the noise floor of the rounding errors.
+    #[test]
+    #[cfg_attr(miri, ignore)] // runs forever + no real value add
+    fn test_noise_floor() {
+        let samples_len = 32768;
+        let samples = (0..samples_len)
+            .map(|i| libm::sin(2.0 * PI * 1000.0 * i as f64 / samples_len as f64))
+            .collect::<Vec<_>>();
+        let samples_f32 = samples.iter().map(|x| *x as f32).collect::<Vec<_>>();
+        let sampling_rate = samples_len as f64;
+
+        let noise_floor = |data: &[(f64, f64)]| {
+            let peak = data.iter().map(|(_, val)| *val).fold(0.0, f64::max);
+            let noise = data
+                .iter()
+                .filter(|(fr, _)| *fr != 1000.0)
+                .map(|(_, val)| *val)
+                .fold(0.0, f64::max);
+            noise / peak
+        };
+        let spectrum =
+            samples_fft_to_spectrum(&samples, sampling_rate, FrequencyLimit::All, None).unwrap();
+        assert_eq!(spectrum.max().0, 1000.0);
+        let spectrum_f32 = crate::samples_fft_to_spectrum(
+            &samples_f32,
+            samples_len,
+            crate::FrequencyLimit::All,
+            None,
+        )
+        .unwrap()
+        .data()
+        .iter()
+        .map(|(fr, val)| (f64::from(fr.val()), f64::from(val.val())))
+        .collect::<Vec<_>>();
+
+        assert!(noise_floor(spectrum.data()) < 1e-12);
+        assert!(noise_floor(&spectrum_f32) > 1e-9);
+    }
+
+    #[test]
+    fn test_output_and_scaling() {
+        let samples = windows::hann_window(&[1.0, -2.0, 3.0, 0.5, 0.0, 1.0, -1.0, 2.0]);
+        let window_energy = windows::hann_window(&[1.0; 8])
+            .iter()
+            .map(|w| w * w)
+            .sum::<f64>();
+        let psd = samples_fft_to_spectrum_with_output(
+            &samples,
+            8.0,
+            FrequencyLimit::All,
+            SpectrumOutput::PowerSpectralDensity { window_energy },
+            None,
+        )
+        .unwrap();
+        let magnitude = samples_fft_to_spectrum(&samples, 8.0, FrequencyLimit::All, None).unwrap();
+        for ((_, psd), (_, magnitude)) in psd.data().iter().zip(magnitude.data()).skip(1).take(3) {
+            assert!((psd - 2.0 * magnitude * magnitude / (8.0 * window_energy)).abs() < 1e-12);
+        }
+
+        let scaled = samples_fft_to_spectrum(
+            &samples,
+            8.0,
+            FrequencyLimit::All,
+            Some(&scaling::scale_to_zero_to_one),
+        )
+        .unwrap();
+        assert_eq!(scaled.max().1, 1.0);
+
+        assert!(matches!(
+            samples_fft_to_spectrum(&samples, 8.0, FrequencyLimit::All, Some(&|_, _| f64::NAN)),
+            Err(SpectrumAnalyzerError::ScalingError(..))
+        ));
+        assert!(matches!(
+            samples_fft_to_spectrum(&samples[..6], 8.0, FrequencyLimit::All, None),
+            Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo)
+        ));
+        assert!(matches!(
+            samples_fft_to_spectrum(&samples, 0.0, FrequencyLimit::All, None),
+            Err(SpectrumAnalyzerError::InvalidSamplingRate(_))
+        ));
+    }
+
+    /// The limits and the scaling keep the precision of `f64`.
+    #[test]
+    fn test_f64_limit_and_decibel() {
+        // the bins are at multiples of 16777217 Hz, which `f32` can't represent
+        let sampling_rate = 8.0 * 16_777_217.0;
+        let samples = (0..8)
+            .map(|i| libm::sin(2.0 * PI * i as f64 / 8.0))
+            .collect::<Vec<_>>();
+        let mut spectrum = samples_fft_to_spectrum(
+            &samples,
+            sampling_rate,
+            FrequencyLimit::Max(2.0 * 16_777_217.0),
+            None,
+        )
+        .unwrap();
+        assert_eq!(spectrum.data().len(), 3);
+        assert_eq!(spectrum.max_fr(), 33_554_434.0);
+
+        let pipeline = scaling::ScalingPipeline::new()
+            .with(scaling::Scaling::Decibel(
+                scaling::Decibel::dbfs(1.0, 1.0).with_floor(-400.0),
+            ))
+            .with(scaling::Scaling::custom("offset", |val, _stats| val + 3.0));
+        let mut working_buffer = spectrum.data().to_vec();
+        pipeline.apply(&mut spectrum, &mut working_buffer).unwrap();
+        assert!((spectrum.freq_val_exact(16_777_217.0) - 3.0).abs() < 1e-12);
+        // only the rounding errors remain in the other bins
+        assert!(spectrum.data()[0].1 < -250.0, "{}", spectrum.data()[0].1);
+    }
+}
@@ -0,0 +1,104 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+//! Double precision variants of the scaling functions of [`crate::scaling`]
+//! that you can use as parameters in [`crate::double::samples_fft_to_spectrum`].
+//!
+//! The scalings are the generic ones of [`crate::scaling`] with `f64`
+//! values, so that the whole path keeps the precision of `f64`.
+
+pub use crate::scaling::{
+    DecibelKind, divide_by_N, divide_by_N_sqrt, scale_20_times_log10, scale_to_zero_to_one,
+};
+
+/// Like [`crate::scaling::SpectrumDataStats`] but with `f64` values.
+pub type SpectrumDataStats = crate::scaling::SpectrumDataStats<f64>;
+
+/// Like [`crate::scaling::SpectrumScalingFunction`] but with `f64` values.
+pub type SpectrumScalingFunction = crate::scaling::SpectrumScalingFunction<f64>;
+
+/// Like [`crate::scaling::DecibelReference`] but with `f64` values.
+pub type DecibelReference = crate::scaling::DecibelReference<f64>;
+
+/// Like [`crate::scaling::Decibel`] but with `f64` values.
+pub type Decibel = crate::scaling::Decibel<f64>;
+
+/// Like [`crate::scaling::Scaling`] but with `f64` values.
+pub type Scaling = crate::scaling::Scaling<f64>;
+
+/// Like [`crate::scaling::CustomScaling`] but with `f64` values.
+pub type CustomScaling = crate::scaling::CustomScaling<f64>;
+
+/// Like [`crate::scaling::ScalingPipeline`] but with `f64` values.
+pub type ScalingPipeline = crate::scaling::ScalingPipeline<f64>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decibel() {
+        let stats = SpectrumDataStats {
+            min: 0.0,
+            max: 0.0,
+            average: 0.0,
+            median: 0.0,
+            n: 8.0,
+        };
+        let dbfs = Decibel::dbfs(1.0, 1.0).with_floor(-300.0);
+        assert_eq!(dbfs.scale(4.0, &stats), 0.0);
+        // far below the default floor
+        assert!((dbfs.scale(4e-13, &stats) + 260.0).abs() < 1e-9);
+        assert_eq!(dbfs.scale(0.0, &stats), -300.0);
+        assert_eq!(Decibel::dbfs(1.0, 1.0).scale(4e-13, &stats), -120.0);
+
+        let power = Decibel::power(DecibelReference::Sine {
+            amplitude: 1.0,
+            window_gain: 1.0,
+        });
+        assert_eq!(power.scale(16.0, &stats), 0.0);
+        assert!((power.scale(1.6, &stats) + 10.0).abs() < 1e-12);
+
+        let pipeline = ScalingPipeline::new()
+            .with(Scaling::Decibel(Decibel::amplitude(
+                DecibelReference::Value(1.0),
+            )))
+            .with(Scaling::custom("offset", |val, _stats| val + 3.0));
+        assert_eq!(pipeline.steps().len(), 2);
+        assert_eq!(pipeline.scale(100.0, &stats), 43.0);
+
+        let mut spectrum = crate::double::samples_fft_to_spectrum(
+            &[0.0, 1.0, 0.0, -1.0],
+            4.0,
+            crate::double::FrequencyLimit::All,
+            None,
+        )
+        .unwrap();
+        let mut working_buffer = vec![(0.0, 0.0); spectrum.data().len()];
+        pipeline.apply(&mut spectrum, &mut working_buffer).unwrap();
+        let (fr, val) = spectrum.data()[1];
+        assert_eq!(fr, 1.0);
+        assert!((val - (20.0 * libm::log10(2.0) + 3.0)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,297 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+//! Module for the struct [`FrequencySpectrum`] with `f64` values.
+
+use super::scaling::SpectrumDataStats;
+use crate::error::SpectrumAnalyzerError;
+use crate::spectrum::{FrequencyLookupError, calc_statistics, upper_index};
+use alloc::vec::Vec;
+
+/// Like [`crate::FrequencySpectrum`] but with `f64` frequencies and values.
+///
+/// The data is a list of plain `(frequency, frequency value)`-pairs, sorted
+/// from the lowest to the highest frequency.
+#[derive(Debug, Default, Clone)]
+pub struct FrequencySpectrum {
+    /// All (frequency, frequency value) data pairs sorted by lowest frequency
+    /// to the highest frequency.
+    data: Vec<(f64, f64)>,
+    /// Frequency resolution of the examined samples in Hertz.
+    frequency_resolution: f64,
+    /// Number of samples that were analyzed.
+    samples_len: u32,
+    /// Average frequency value.
+    average: f64,
+    /// Median frequency value.
+    median: f64,
+    /// (frequency, frequency value)-pair with the minimal frequency value.
+    min: (f64, f64),
+    /// (frequency, frequency value)-pair with the maximum frequency value.
+    max: (f64, f64),
+}
+
+impl FrequencySpectrum {
+    /// Creates a new object and calculates the statistics of the data.
+    ///
+    /// ## Parameters
+    /// * `data` Vector with all (frequency, frequency value)-tuples, sorted
+    ///   by frequency. Must contain at least two tuples.
+    /// * `frequency_resolution` Resolution in Hertz.
+    /// * `samples_len` Number of samples.
+    /// * `working_buffer` Mutable buffer with the same length as `data`
+    ///   required to calculate certain metrics.
+    #[must_use]
+    pub fn new(
+        data: Vec<(f64, f64)>,
+        frequency_resolution: f64,
+        samples_len: u32,
+        working_buffer: &mut [(f64, f64)],
+    ) -> Self {
+        debug_assert!(
+            data.len() >= 2,
+            "Input data of length={} for spectrum makes no sense!",
+            data.len()
+        );
+        let mut obj = Self {
+            data,
+            frequency_resolution,
+            samples_len,
+            ..Default::default()
+        };
+        obj.calc_statistics(working_buffer);
+        obj
+    }
+
+    /// Applies the function `scaling_fn`, e.g. a
+    /// [`crate::double::scaling::SpectrumScalingFunction`], to each element
+    /// and updates the statistics afterwards. If a value becomes NaN or
+    /// infinite, [`SpectrumAnalyzerError::ScalingError`] is returned.
+    pub fn apply_scaling_fn(
+        &mut self,
+        scaling_fn: &(impl Fn(f64, &SpectrumDataStats) -> f64 + ?Sized),
+        working_buffer: &mut [(f64, f64)],
+    ) -> Result<(), SpectrumAnalyzerError> {
+        let stats = SpectrumDataStats {
+            min: self.min.1,
+            max: self.max.1,
+            average: self.average,
+            median: self.median,
+            n: f64::from(self.samples_len),
+        };
+        for (_fr, fr_val) in &mut self.data {
+            let scaled_val = scaling_fn(*fr_val, &stats);
+            if !scaled_val.is_finite() {
+                return Err(SpectrumAnalyzerError::ScalingError(
+                    *fr_val as f32,
+                    scaled_val as f32,
+                ));
+            }
+            *fr_val = scaled_val;
+        }
+        self.calc_statistics(working_buffer);
+        Ok(())
+    }
+
+    /// Returns the average frequency value of the spectrum.
+    #[must_use]
+    pub const fn average(&self) -> f64 {
+        self.average
+    }
+
+    /// Returns the median frequency value of the spectrum.
+    #[must_use]
+    pub const fn median(&self) -> f64 {
+        self.median
+    }
+
+    /// Returns the maximum (frequency, frequency value)-pair of the spectrum
+    /// **regarding the frequency value**.
+    #[must_use]
+    pub const fn max(&self) -> (f64, f64) {
+        self.max
+    }
+
+    /// Returns the minimum (frequency, frequency value)-pair of the spectrum
+    /// **regarding the frequency value**.
+    #[must_use]
+    pub const fn min(&self) -> (f64, f64) {
+        self.min
+    }
+
+    /// Returns the range of the frequency values, i.e. the maximum minus the
+    /// minimum.
+    #[must_use]
+    pub fn range(&self) -> f64 {
+        self.max.1 - self.min.1
+    }
+
+    /// Returns the underlying data.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // false positive
+    pub fn data(&self) -> &[(f64, f64)] {
+        &self.data
+    }
+
+    /// Returns the frequency resolution of this spectrum.
+    #[must_use]
+    pub const fn frequency_resolution(&self) -> f64 {
+        self.frequency_resolution
+    }
+
+    /// Returns the number of samples used to obtain this spectrum.
+    #[must_use]
+    pub const fn samples_len(&self) -> u32 {
+        self.samples_len
+    }
+
+    /// Returns the highest frequency of the spectrum.
+    #[must_use]
+    pub fn max_fr(&self) -> f64 {
+        self.data[self.data.len() - 1].0
+    }
+
+    /// Returns the lowest frequency of the spectrum.
+    #[must_use]
+    pub fn min_fr(&self) -> f64 {
+        self.data[0].0
+    }
+
+    /// Returns the value of the DC component, if the spectrum includes `0 Hz`.
+    #[must_use]
+    pub fn dc_component(&self) -> Option<f64> {
+        let (fr, val) = self.data[0];
+        (fr == 0.0).then_some(val)
+    }
+
+    /// Like [`crate::FrequencySpectrum::freq_val_exact`]: returns the value of
+    /// the given frequency, linearly interpolated between the two closest
+    /// frequencies.
+    ///
+    /// ## Panics
+    /// If `search_fr` is outside [`Self::min_fr`] and [`Self::max_fr`]. See
+    /// [`Self::try_freq_val_exact`].
+    #[must_use]
+    pub fn freq_val_exact(&self, search_fr: f64) -> f64 {
+        self.try_freq_val_exact(search_fr)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::freq_val_exact`] but returns an error instead of
+    /// panicking if `search_fr` is out of bounds or NaN.
+    pub fn try_freq_val_exact(&self, search_fr: f64) -> Result<f64, FrequencyLookupError<f64>> {
+        let index = upper_index(&self.data, search_fr)?;
+        let ((x1, y1), (x2, y2)) = (self.data[index - 1], self.data[index]);
+        if x2 == search_fr {
+            return Ok(y2);
+        }
+        Ok(y1 + (y2 - y1) * (search_fr - x1) / (x2 - x1))
+    }
+
+    /// Like [`crate::FrequencySpectrum::freq_val_closest`]: returns the
+    /// (frequency, frequency value)-pair that is closest to `search_fr`.
+    ///
+    /// ## Panics
+    /// If `search_fr` is outside [`Self::min_fr`] and [`Self::max_fr`]. See
+    /// [`Self::try_freq_val_closest`].
+    #[must_use]
+    pub fn freq_val_closest(&self, search_fr: f64) -> (f64, f64) {
+        self.try_freq_val_closest(search_fr)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::freq_val_closest`] but returns an error instead of
+    /// panicking if `search_fr` is out of bounds or NaN.
+    pub fn try_freq_val_closest(
+        &self,
+        search_fr: f64,
+    ) -> Result<(f64, f64), FrequencyLookupError<f64>> {
+        let index = upper_index(&self.data, search_fr)?;
+        let (point_a, point_b) = (self.data[index - 1], self.data[index]);
+        Ok(if point_b.0 - search_fr < search_fr - point_a.0 {
+            point_b
+        } else {
+            point_a
+        })
+    }
+
+    /// Calculates the `min`, `max`, `median`, and `average` of the frequency
+    /// values, see [`calc_statistics`].
+    fn calc_statistics(&mut self, working_buffer: &mut [(f64, f64)]) {
+        let statistics = calc_statistics(&self.data, working_buffer);
+        self.min = statistics.min;
+        self.max = statistics.max;
+        self.average = statistics.average;
+        self.median = statistics.median;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statistics_and_lookups() {
+        let mut data = vec![(0.0, 3.0), (0.5, 5.0), (1.0, 1.0), (1.5, 2.0)];
+        let spectrum = FrequencySpectrum::new(data.clone(), 0.5, 6, &mut data);
+        assert_eq!(spectrum.min(), (1.0, 1.0));
+        assert_eq!(spectrum.max(), (0.5, 5.0));
+        assert_eq!(spectrum.average(), 2.75);
+        assert_eq!(spectrum.median(), 2.5);
+        assert_eq!(spectrum.range(), 4.0);
+        assert_eq!(spectrum.dc_component(), Some(3.0));
+
+        assert_eq!(spectrum.freq_val_exact(0.0), 3.0);
+        assert_eq!(spectrum.freq_val_exact(0.75), 3.0);
+        assert_eq!(spectrum.freq_val_exact(1.5), 2.0);
+        assert_eq!(spectrum.freq_val_closest(0.7), (0.5, 5.0));
+        assert_eq!(spectrum.freq_val_closest(0.8), (1.0, 1.0));
+        assert_eq!(spectrum.freq_val_closest(1.5), (1.5, 2.0));
+
+        assert_eq!(
+            spectrum.try_freq_val_exact(-0.5),
+            Err(FrequencyLookupError::OutOfBounds(-0.5, 0.0, 1.5))
+        );
+        assert_eq!(
+            spectrum.try_freq_val_closest(1.6),
+            Err(FrequencyLookupError::OutOfBounds(1.6, 0.0, 1.5))
+        );
+        assert_eq!(
+            spectrum.try_freq_val_exact(f64::NAN),
+            Err(FrequencyLookupError::NaN)
+        );
+        assert_eq!(
+            spectrum.try_freq_val_closest(f64::NAN),
+            Err(FrequencyLookupError::NaN)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_bounds() {
+        let mut data = vec![(1.0, 1.0), (2.0, 2.0)];
+        let spectrum = FrequencySpectrum::new(data.clone(), 1.0, 4, &mut data);
+        let _ = spectrum.freq_val_exact(2.5);
+    }
+}
@@ -0,0 +1,472 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the struct [`DtmfDecoder`], which decodes DTMF (dual-tone
+//! multi-frequency) digits, e.g. of recorded telephone calls.
+
+use crate::fft::Complex32;
+use crate::goertzel::Goertzel;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::f32::consts::PI;
+use core::fmt::{Display, Formatter};
+
+/// Frequencies of the rows of the DTMF keypad (low group).
+pub const DTMF_ROW_FREQUENCIES: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+
+/// Frequencies of the columns of the DTMF keypad (high group).
+pub const DTMF_COLUMN_FREQUENCIES: [f32; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
+
+/// The DTMF keypad. The row index corresponds to
+/// [`DTMF_ROW_FREQUENCIES`] and the column index to
+/// [`DTMF_COLUMN_FREQUENCIES`].
+const KEYPAD: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+
+/// Minimum ratio of the power of both tones to the power of the whole
+/// signal. Rejects speech, noise, and blocks that only partially contain a
+/// tone, but requires a signal-to-noise ratio of about 10 dB.
+const MIN_TONE_POWER_RATIO: f32 = 0.9;
+
+/// Minimum ratio of the magnitude of the strongest tone of a group to the
+/// magnitude of all other tones of that group.
+const MIN_GROUP_MAGNITUDE_RATIO: f32 = 2.0;
+
+/// Detection thresholds of a [`DtmfDecoder`]. The defaults follow the
+/// recommendation ITU-T Q.24.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DtmfConfig {
+    /// Length of the analyzed blocks in seconds. Longer blocks separate the
+    /// tones better but blur their start and end. The default is `0.0256`,
+    /// i.e. 205 samples at 8000 Hz.
+    pub block_duration: f32,
+    /// Minimum duration of a digit in seconds. The default is `0.04`.
+    pub min_duration: f32,
+    /// Maximum relative deviation of each tone from its nominal frequency.
+    /// Q.24 requires to accept `0.015` and to reject `0.035`. The default is
+    /// `0.025`.
+    pub max_frequency_deviation: f32,
+    /// Maximum level of the high group tone above the level of the low group
+    /// tone in dB (forward twist). The default is `8.0`.
+    pub max_forward_twist: f32,
+    /// Maximum level of the low group tone above the level of the high group
+    /// tone in dB (reverse twist). The default is `4.0`.
+    pub max_reverse_twist: f32,
+    /// Minimum amplitude of each tone, for samples in interval `[-1; 1]`.
+    /// The default is `0.01`, i.e. -40 dBFS.
+    pub min_amplitude: f32,
+}
+
+impl Default for DtmfConfig {
+    fn default() -> Self {
+        Self {
+            block_duration: 0.0256,
+            min_duration: 0.04,
+            max_frequency_deviation: 0.025,
+            max_forward_twist: 8.0,
+            max_reverse_twist: 4.0,
+            min_amplitude: 0.01,
+        }
+    }
+}
+
+impl DtmfConfig {
+    /// Verifies that the config has sane values.
+    fn verify(&self) -> Result<(), DtmfError> {
+        let is_positive = |x: f32| x.is_finite() && x > 0.0;
+        let valid = is_positive(self.block_duration)
+            && self.min_duration.is_finite()
+            && self.min_duration >= 0.0
+            && is_positive(self.max_frequency_deviation)
+            && self.max_frequency_deviation < 1.0
+            && is_positive(self.max_forward_twist)
+            && is_positive(self.max_reverse_twist)
+            && is_positive(self.min_amplitude);
+        if valid {
+            Ok(())
+        } else {
+            Err(DtmfError::InvalidConfig(*self))
+        }
+    }
+}
+
+/// A decoded DTMF digit.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DtmfDigit {
+    /// One of `0-9`, `A-D`, `*` and `#`.
+    digit: char,
+    /// Start of the tone in seconds.
+    start: f32,
+    /// End of the tone in seconds.
+    end: f32,
+}
+
+impl DtmfDigit {
+    /// Returns the digit, i.e. one of `0-9`, `A-D`, `*` and `#`.
+    #[inline]
+    #[must_use]
+    pub const fn digit(&self) -> char {
+        self.digit
+    }
+
+    /// Returns the start of the tone in seconds since the first sample.
+    #[inline]
+    #[must_use]
+    pub const fn start(&self) -> f32 {
+        self.start
+    }
+
+    /// Returns the end of the tone in seconds since the first sample.
+    #[inline]
+    #[must_use]
+    pub const fn end(&self) -> f32 {
+        self.end
+    }
+}
+
+/// Decodes DTMF digits of a stream of samples in interval `[-1; 1]`.
+///
+/// Overlapping blocks of samples are analyzed with the [`Goertzel`]
+/// algorithm. A block contains a digit if exactly one tone of each group
+/// dominates the signal and both tones satisfy the frequency deviation,
+/// twist, and level rules of [`DtmfConfig`]. Successive blocks with the same
+/// digit form a tone, which is emitted once it ends and if it lasted at least
+/// [`DtmfConfig::min_duration`].
+///
+/// The start and end of each tone are only known with the precision of the
+/// hop size between two blocks, i.e. an eighth of the block duration.
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::dtmf::{DtmfConfig, DtmfDecoder};
+///
+/// let mut decoder = DtmfDecoder::new(8000.0, DtmfConfig::default()).unwrap();
+/// let mut digits = String::new();
+/// // inside the audio callback
+/// let block = [0.0; 160];
+/// decoder.push(&block, |digit| digits.push(digit.digit())).unwrap();
+/// // at the end of the stream
+/// decoder.finish(|digit| digits.push(digit.digit()));
+/// ```
+#[derive(Debug)]
+pub struct DtmfDecoder {
+    /// The used sampling rate, e.g. `8000 [Hz]`.
+    sampling_rate: f32,
+    /// The detection thresholds.
+    config: DtmfConfig,
+    /// The latest `block_len` samples. The oldest sample is at `write_pos`.
+    ring_buffer: Vec<f32>,
+    /// Position of the next sample in `ring_buffer`.
+    write_pos: usize,
+    /// Number of samples until the next block is analyzed.
+    until_next: usize,
+    /// Number of new samples between two blocks.
+    hop_size: usize,
+    /// The samples of the analyzed block in chronological order.
+    block: Vec<f32>,
+    /// Number of pushed samples.
+    samples_len: usize,
+    /// The currently detected digit with the sample indices of its start and
+    /// end.
+    current: Option<(char, usize, usize)>,
+}
+
+impl DtmfDecoder {
+    /// Creates a new decoder.
+    ///
+    /// ## Parameters
+    /// * `sampling_rate` The used sampling rate, e.g. `8000 [Hz]`. Must be
+    ///   high enough for the highest DTMF frequency.
+    /// * `config` The detection thresholds. See [`DtmfConfig`].
+    pub fn new(sampling_rate: f32, config: DtmfConfig) -> Result<Self, DtmfError> {
+        config.verify()?;
+        let max_frequency = DTMF_COLUMN_FREQUENCIES[3] * (1.0 + config.max_frequency_deviation);
+        if !sampling_rate.is_finite() || sampling_rate < 2.0 * max_frequency {
+            return Err(DtmfError::InvalidSamplingRate(sampling_rate));
+        }
+        let block_len = libm::roundf(config.block_duration * sampling_rate) as usize;
+        // the frequency estimation needs a quarter of a block
+        if block_len < 8 {
+            return Err(DtmfError::InvalidConfig(config));
+        }
+        Ok(Self {
+            sampling_rate,
+            config,
+            ring_buffer: vec![0.0; block_len],
+            write_pos: 0,
+            until_next: block_len,
+            hop_size: block_len / 8,
+            block: vec![0.0; block_len],
+            samples_len: 0,
+            current: None,
+        })
+    }
+
+    /// Returns the detection thresholds.
+    #[inline]
+    #[must_use]
+    pub const fn config(&self) -> &DtmfConfig {
+        &self.config
+    }
+
+    /// Pushes a block of samples of any size. `on_digit` is called for each
+    /// digit whose tone ended.
+    ///
+    /// ## Return value
+    /// Number of emitted digits.
+    pub fn push(
+        &mut self,
+        samples: &[f32],
+        mut on_digit: impl FnMut(DtmfDigit),
+    ) -> Result<usize, DtmfError> {
+        if samples.iter().any(|x| !x.is_finite()) {
+            return Err(DtmfError::InvalidSamples);
+        }
+
+        let mut emitted = 0;
+        let mut samples = samples;
+        while !samples.is_empty() {
+            let count = samples.len().min(self.until_next);
+            for sample in &samples[..count] {
+                self.ring_buffer[self.write_pos] = *sample;
+                self.write_pos = (self.write_pos + 1) % self.ring_buffer.len();
+            }
+            samples = &samples[count..];
+            self.until_next -= count;
+            self.samples_len += count;
+
+            if self.until_next == 0 {
+                self.until_next = self.hop_size;
+                let (newer, older) = self.ring_buffer.split_at(self.write_pos);
+                let (block_older, block_newer) = self.block.split_at_mut(older.len());
+                block_older.copy_from_slice(older);
+                block_newer.copy_from_slice(newer);
+
+                let block_end = self.samples_len;
+                let block_start = block_end - self.block.len();
+                match (self.detect(), self.current) {
+                    (Some(digit), Some((current, start, _))) if digit == current => {
+                        self.current = Some((digit, start, block_end));
+                    }
+                    (detected, _) => {
+                        emitted += usize::from(self.finish(&mut on_digit));
+                        self.current = detected.map(|digit| (digit, block_start, block_end));
+                    }
+                }
+            }
+        }
+        Ok(emitted)
+    }
+
+    /// Ends the current tone, e.g. at the end of the stream. `on_digit` is
+    /// called if the tone lasted long enough.
+    ///
+    /// ## Return value
+    /// Whether a digit was emitted.
+    pub fn finish(&mut self, mut on_digit: impl FnMut(DtmfDigit)) -> bool {
+        let Some((digit, start, end)) = self.current.take() else {
+            return false;
+        };
+        let sampling_rate = self.sampling_rate;
+        let (start, end) = (start as f32 / sampling_rate, end as f32 / sampling_rate);
+        // the edges are only known with the precision of the hop size
+        let hop_duration = self.hop_size as f32 / sampling_rate;
+        if end - start + hop_duration >= self.config.min_duration {
+            on_digit(DtmfDigit { digit, start, end });
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the digit of the current block, if any.
+    fn detect(&self) -> Option<char> {
+        let block_len = self.block.len() as f32;
+        let power = self.block.iter().map(|x| x * x).sum::<f32>() / block_len;
+        if power <= 0.0 {
+            return None;
+        }
+
+        let (row, row_amplitude) = self.detect_group(&DTMF_ROW_FREQUENCIES)?;
+        let (column, column_amplitude) = self.detect_group(&DTMF_COLUMN_FREQUENCIES)?;
+
+        let tone_power =
+            (row_amplitude * row_amplitude + column_amplitude * column_amplitude) / 2.0;
+        if tone_power / power < MIN_TONE_POWER_RATIO {
+            return None;
+        }
+
+        let twist = 20.0 * libm::log10f(column_amplitude / row_amplitude);
+        if twist > self.config.max_forward_twist || -twist > self.config.max_reverse_twist {
+            return None;
+        }
+
+        Some(KEYPAD[row][column])
+    }
+
+    /// Returns the index and the amplitude of the dominating tone of a
+    /// group, if it satisfies the level and frequency deviation rules.
+    fn detect_group(&self, frequencies: &[f32; 4]) -> Option<(usize, f32)> {
+        let magnitudes = frequencies.map(|fr| magnitude(self.goertzel(fr, &self.block)));
+        let (index, max) = magnitudes
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|(_, l), (_, r)| l.total_cmp(r))?;
+        let dominates = magnitudes
+            .iter()
+            .enumerate()
+            .all(|(i, magnitude)| i == index || magnitude * MIN_GROUP_MAGNITUDE_RATIO <= max);
+        if !dominates {
+            return None;
+        }
+
+        // Estimates the frequency of the tone by the phase difference between
+        // two blocks that are shifted by `lag` samples. It is unambiguous
+        // for deviations up to `sampling_rate / (2 * lag)`.
+        let nominal = frequencies[index];
+        let lag = self.block.len() / 4;
+        let len = self.block.len() - lag;
+        let first = self.goertzel(nominal, &self.block[..len]);
+        let second = self.goertzel(nominal, &self.block[lag..]);
+        let phase_diff = arg(second * first.conj());
+        let expected = 2.0 * PI * nominal * lag as f32 / self.sampling_rate;
+        let deviation_rad = libm::remainderf(phase_diff - expected, 2.0 * PI);
+        let frequency = nominal + deviation_rad * self.sampling_rate / (2.0 * PI * lag as f32);
+        if (frequency - nominal).abs() > nominal * self.config.max_frequency_deviation {
+            return None;
+        }
+
+        // The magnitude at the nominal frequency is attenuated by the
+        // deviation, but the one at the estimated frequency is not.
+        let amplitude =
+            2.0 * magnitude(self.goertzel(frequency, &self.block)) / self.block.len() as f32;
+        (amplitude >= self.config.min_amplitude).then_some((index, amplitude))
+    }
+
+    /// Returns the complex value of the frequency in the samples.
+    fn goertzel(&self, frequency: f32, samples: &[f32]) -> Complex32 {
+        let mut goertzel =
+            Goertzel::new(frequency, self.sampling_rate).expect("frequency below Nyquist");
+        goertzel.push(samples);
+        goertzel.complex_result().expect("samples are finite")
+    }
+}
+
+/// Returns the absolute value of a complex number.
+fn magnitude(val: Complex32) -> f32 {
+    libm::sqrtf(val.norm_sqr())
+}
+
+/// Returns the phase of a complex number in radians.
+fn arg(val: Complex32) -> f32 {
+    libm::atan2f(val.im, val.re)
+}
+
+/// Decodes all DTMF digits of the samples. See [`DtmfDecoder`].
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::dtmf::{decode_dtmf, DtmfConfig};
+///
+/// let samples = [0.0; 8000];
+/// let digits = decode_dtmf(&samples, 8000.0, DtmfConfig::default()).unwrap();
+/// assert!(digits.is_empty());
+/// ```
+pub fn decode_dtmf(
+    samples: &[f32],
+    sampling_rate: f32,
+    config: DtmfConfig,
+) -> Result<Vec<DtmfDigit>, DtmfError> {
+    let mut decoder = DtmfDecoder::new(sampling_rate, config)?;
+    let mut digits = Vec::new();
+    decoder.push(samples, |digit| digits.push(digit))?;
+    decoder.finish(|digit| digits.push(digit));
+    Ok(digits)
+}
+
+/// Errors of the [`DtmfDecoder`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DtmfError {
+    /// The config has invalid values, e.g. a negative duration.
+    InvalidConfig(DtmfConfig),
+    /// The sampling rate is not finite or too low for the highest DTMF
+    /// frequency.
+    InvalidSamplingRate(f32),
+    /// NaN or infinite samples are not supported.
+    InvalidSamples,
+}
+
+impl Display for DtmfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidConfig(config) => write!(f, "Invalid DTMF config: {config:?}"),
+            Self::InvalidSamplingRate(x) => write!(f, "Invalid sampling rate for DTMF: {x}"),
+            Self::InvalidSamples => write!(f, "NaN or infinite samples are not supported!"),
+        }
+    }
+}
+
+impl Error for DtmfError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_config() {
+        DtmfDecoder::new(8000.0, DtmfConfig::default()).unwrap();
+        assert_eq!(
+            DtmfDecoder::new(3000.0, DtmfConfig::default()).unwrap_err(),
+            DtmfError::InvalidSamplingRate(3000.0)
+        );
+        let config = DtmfConfig {
+            min_duration: -1.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            DtmfDecoder::new(8000.0, config).unwrap_err(),
+            DtmfError::InvalidConfig(config)
+        );
+        let config = DtmfConfig {
+            block_duration: 0.0001,
+            ..Default::default()
+        };
+        assert_eq!(
+            DtmfDecoder::new(8000.0, config).unwrap_err(),
+            DtmfError::InvalidConfig(config)
+        );
+
+        let mut decoder = DtmfDecoder::new(8000.0, DtmfConfig::default()).unwrap();
+        assert_eq!(
+            decoder.push(&[f32::NAN], |_| ()).unwrap_err(),
+            DtmfError::InvalidSamples
+        );
+        assert_eq!(decoder.push(&[0.0; 8000], |_| ()).unwrap(), 0);
+        assert!(!decoder.finish(|_| ()));
+    }
+}
@@ -45,8 +45,25 @@ pub enum SpectrumAnalyzerError {
     InvalidFrequencyLimit(FrequencyLimitError),
     /// The frequency limit is valid in isolation but leaves too few frequency bins for a spectrum.
     FrequencyLimitTooNarrow,
+    /// The sampling rate must be a positive and finite number.
+    InvalidSamplingRate(f32),
     /// The number of samples must be a power of two in order for the FFT.
     SamplesLengthNotAPowerOfTwo,
+    /// The FFT supports at most 32768 samples, including any zero padding.
+    /// The `large-fft` and the `rustfft` features lift this limit.
+    TooManySamples,
+    /// The window energy of a [`crate::SpectrumOutput`] must be a positive
+    /// number.
+    InvalidWindowEnergy(f32),
+    /// The window coefficients must have the same length as the samples.
+    InvalidWindowLength(usize),
+    /// The multi-channel samples are inconsistent, e.g. because the channels
+    /// or signals have different lengths or mid/side was requested for
+    /// non-stereo data.
+    InvalidChannelLayout,
+    /// A frame of a batch has a different length than the configured frame
+    /// length.
+    InvalidFrameLength(usize),
     /// After applying the scaling function on a specific item, the returned value is either
     /// infinity or NaN, according to IEEE-754. This is invalid. Check
     /// your scaling function!
@@ -69,9 +86,15 @@ impl Display for SpectrumAnalyzerError {
             Self::FrequencyLimitTooNarrow => {
                 write!(f, "Frequency limit leaves too few frequency bins!")
             }
+            Self::InvalidSamplingRate(x) => write!(f, "Invalid sampling rate: {x}"),
             Self::SamplesLengthNotAPowerOfTwo => {
                 write!(f, "Samples length must be a power of two!")
             }
+            Self::TooManySamples => write!(f, "Too many samples!"),
+            Self::InvalidWindowEnergy(x) => write!(f, "Invalid window energy: {x}"),
+            Self::InvalidWindowLength(x) => write!(f, "Invalid window length: {x}"),
+            Self::InvalidChannelLayout => write!(f, "Invalid channel layout!"),
+            Self::InvalidFrameLength(x) => write!(f, "Invalid frame length: {x}"),
             Self::ScalingError(a, b) => write!(f, "Scaling error: {a} -> {b}"),
         }
     }
@@ -22,11 +22,25 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
-//! Real FFT using [`microfft::real`] that is very fast and also works in
-//! `no_std` environments. It is faster than regular fft (with the `rustfft`
-//! crate for example). The difference to a complex FFT, as with `rustfft` is,
-//! that the result vector contains fewer results as there are no mirrored
-//! frequencies.
+//! FFT backends of the spectrum pipeline.
+//!
+//! The default backend is [`Microfft`], a real FFT using [`microfft::real`]
+//! that is very fast and also works in `no_std` environments. The difference
+//! to a complex FFT, as with `rustfft` is, that the result vector contains
+//! fewer results as there are no mirrored frequencies.
+//!
+//! With the `large-fft` feature, [`Microfft`] uses the generic radix-2 FFT of
+//! this crate for more than 32768 samples. It is opt-in, so that the binary
+//! size for embedded targets does not grow.
+//!
+//! With the `rustfft` feature, the `RustFft` backend uses the
+//! SIMD-accelerated planner of the `rustfft` crate and becomes the
+//! [`DefaultFftBackend`]. Other backends can be used with
+//! [`crate::samples_fft_to_spectrum_with_backend`] and similar functions, or
+//! with [`crate::streaming::StreamingAnalyzer::with_backend`].
+//!
+//! Backends are values, so that they can keep state between transforms,
+//! such as the plans and the scratch buffer of `RustFft`.
 
 /// FFT base result type.
 pub use microfft::Complex32;
@@ -34,7 +48,81 @@ pub use microfft::Complex32;
 use alloc::vec::Vec;
 use core::convert::TryInto;
 use core::mem;
-use microfft::real;
+use microfft::{complex, real};
+#[cfg(feature = "rustfft")]
+use {
+    alloc::sync::Arc,
+    core::fmt::{Debug, Formatter},
+};
+
+/// Maximum number of samples of the FFT of the [`DefaultFftBackend`].
+pub(crate) const MAX_FFT_LEN: usize = <DefaultFftBackend as FftBackend>::MAX_LEN;
+
+/// Maximum number of samples of the FFT of [`microfft`].
+const MAX_MICROFFT_LEN: usize = 32768;
+
+/// Backend that calculates the FFTs of the spectrum pipeline.
+///
+/// Implementations only provide the in-place transforms. Everything else,
+/// such as the conversion of the results into a [`crate::FrequencySpectrum`],
+/// is shared by all backends. One-shot functions, such as
+/// [`crate::samples_fft_to_spectrum_with_backend`], use a new
+/// [`Default`] value for each call.
+pub trait FftBackend: Default {
+    /// Maximum number of samples of the FFT.
+    const MAX_LEN: usize;
+
+    /// Prepares the backend for FFTs of `len` real samples, e.g. by planning
+    /// them and allocating their scratch buffer. Afterward,
+    /// [`Self::real_fft_in_place`] doesn't allocate for this length. The
+    /// default implementation does nothing.
+    fn prepare(&mut self, _len: usize) {}
+
+    /// Calculates the FFT of real samples in place and returns a view into
+    /// the buffer with `buffer.len() / 2` values, from the DC component to
+    /// the last frequency below the Nyquist frequency.
+    ///
+    /// The real value of the Nyquist frequency is packed into the imaginary
+    /// part of the DC component, as [`microfft::real`] does.
+    ///
+    /// # Parameters
+    /// - `buffer`: Array with samples. The length is a power of two, at least
+    ///   two, and not bigger than [`Self::MAX_LEN`].
+    fn real_fft_in_place<'a>(&mut self, buffer: &'a mut [f32]) -> &'a mut [Complex32];
+
+    /// Calculates the FFT of complex samples in place. All `N` values are
+    /// relevant: the indices `N / 2..N` correspond to the negative
+    /// frequencies.
+    ///
+    /// # Parameters
+    /// - `buffer`: Array with samples. See [`Self::real_fft_in_place`].
+    fn complex_fft_in_place(&mut self, buffer: &mut [Complex32]);
+}
+
+/// Backend that is used by all functions of this crate, except for
+/// [`crate::samples_fft_to_spectrum_with_backend`].
+#[cfg(not(feature = "rustfft"))]
+pub type DefaultFftBackend = Microfft;
+
+/// Backend that is used by all functions of this crate, except for
+/// [`crate::samples_fft_to_spectrum_with_backend`].
+#[cfg(feature = "rustfft")]
+pub type DefaultFftBackend = RustFft;
+
+// `Complex32` is layout-compatible with `[f32; 2]`.
+const _: [(); size_of::<Complex32>()] = [(); 2 * size_of::<f32>()];
+const _: [(); align_of::<Complex32>()] = [(); align_of::<f32>()];
+
+/// Reinterprets a buffer of real values as pairs of real and imaginary parts.
+#[cfg(any(feature = "large-fft", feature = "rustfft"))]
+fn as_complex(buffer: &mut [f32]) -> &mut [Complex32] {
+    debug_assert_eq!(buffer.len() % 2, 0);
+
+    // SAFETY: `Complex32` is layout-compatible with `[f32; 2]`, as asserted
+    // above, and the view covers at most the buffer. The lifetime of the view
+    // is bound to the buffer.
+    unsafe { core::slice::from_raw_parts_mut(buffer.as_mut_ptr().cast(), buffer.len() / 2) }
+}
 
 /// Calculates the FFT by invoking the function of [`microfft::real`] that
 /// corresponds to the input size.
@@ -43,7 +131,7 @@ macro_rules! real_fft_n {
         match $buffer.len() {
             $(
                 $i => {
-                    let fixed_size_view = $buffer.as_mut_slice().try_into().unwrap();
+                    let fixed_size_view: &mut [f32; $i] = $buffer.try_into().unwrap();
                     paste::paste! (
                         real::[<rfft_$i>]
                     )(fixed_size_view)
@@ -54,10 +142,207 @@ macro_rules! real_fft_n {
     };
 }
 
-/// FFT using [`microfft::real`].
-pub struct FftImpl;
+/// Calculates the complex FFT by invoking the function of
+/// [`microfft::complex`] that corresponds to the input size.
+macro_rules! complex_fft_n {
+    ($buffer:expr, $( $i:literal ),*) => {
+        match $buffer.len() {
+            $(
+                $i => {
+                    let fixed_size_view: &mut [Complex32; $i] = $buffer.try_into().unwrap();
+                    // the result is a view into the buffer
+                    let _ = paste::paste! (
+                        complex::[<cfft_$i>]
+                    )(fixed_size_view);
+                }
+            )*
+            _ => { unimplemented!("should be one of the supported buffer lengths, but was {}", $buffer.len()) }
+        }
+    };
+}
+
+/// FFT using [`microfft::real`] and [`microfft::complex`] with small,
+/// precomputed tables.
+///
+/// With the `large-fft` feature, FFTs of more than 32768 samples use the
+/// radix-2 FFT of this crate. Its twiddle factors are calculated on the
+/// first of these FFTs, or by [`FftBackend::prepare`], and reused for all
+/// further FFTs of at most the same length.
+#[derive(Debug, Clone, Default)]
+pub struct Microfft {
+    /// Twiddle factors of the largest FFT so far, if it was bigger than
+    /// [`microfft`] supports.
+    #[cfg(feature = "large-fft")]
+    twiddles: Vec<Complex32>,
+}
+
+impl FftBackend for Microfft {
+    #[cfg(not(feature = "large-fft"))]
+    const MAX_LEN: usize = MAX_MICROFFT_LEN;
+
+    // The number of samples of a spectrum is an `u32`.
+    #[cfg(feature = "large-fft")]
+    const MAX_LEN: usize = 1 << 31;
+
+    #[cfg(feature = "large-fft")]
+    fn prepare(&mut self, len: usize) {
+        if len > MAX_MICROFFT_LEN {
+            self.twiddles(len);
+        }
+    }
+
+    #[inline]
+    fn real_fft_in_place<'a>(&mut self, buffer: &'a mut [f32]) -> &'a mut [Complex32] {
+        #[cfg(feature = "large-fft")]
+        if buffer.len() > MAX_MICROFFT_LEN {
+            let twiddles = self.twiddles(buffer.len());
+            let view = as_complex(buffer);
+            crate::radix2::real_fft_in_place(view, twiddles);
+            return view;
+        }
+        real_fft_n!(
+            buffer, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768
+        )
+    }
+
+    #[inline]
+    fn complex_fft_in_place(&mut self, buffer: &mut [Complex32]) {
+        #[cfg(feature = "large-fft")]
+        if buffer.len() > MAX_MICROFFT_LEN {
+            let twiddles = self.twiddles(buffer.len());
+            crate::radix2::fft_in_place(buffer, twiddles);
+            return;
+        }
+        complex_fft_n!(
+            buffer, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768
+        )
+    }
+}
+
+impl Microfft {
+    /// Returns the cached twiddle factors for FFTs of `len` samples and
+    /// only calculates them if the cached ones are for a smaller FFT.
+    #[cfg(feature = "large-fft")]
+    fn twiddles(&mut self, len: usize) -> &[Complex32] {
+        if 2 * self.twiddles.len() < len {
+            self.twiddles = crate::radix2::twiddles(len);
+        }
+        &self.twiddles
+    }
+}
+
+/// FFT using the SIMD-accelerated planner of the `rustfft` crate. It
+/// supports any power of two as length but requires `std`.
+///
+/// The FFT of real samples is calculated with a complex FFT of half the
+/// length, so it is as fast as a dedicated real FFT.
+///
+/// The plan of each length and a shared scratch buffer are created on the
+/// first FFT of that length, or by [`FftBackend::prepare`], and reused for
+/// all further FFTs of the same value. Clones share the plans.
+#[cfg(feature = "rustfft")]
+#[derive(Clone, Default)]
+pub struct RustFft {
+    /// Plans of the complex FFTs, one per length.
+    plans: Vec<Arc<dyn rustfft::Fft<f32>>>,
+    /// Scratch buffer that is big enough for all plans.
+    scratch: Vec<Complex32>,
+}
+
+#[cfg(feature = "rustfft")]
+impl RustFft {
+    /// Returns the index of the plan of the complex FFT of `len` samples.
+    /// If there is none yet, it is planned and the scratch buffer grows.
+    fn plan_index(&mut self, len: usize) -> usize {
+        if let Some(index) = self.plans.iter().position(|plan| plan.len() == len) {
+            return index;
+        }
+        let plan = rustfft::FftPlanner::new().plan_fft_forward(len);
+        let scratch_len = plan.get_inplace_scratch_len();
+        if self.scratch.len() < scratch_len {
+            self.scratch.resize(scratch_len, Complex32::new(0.0, 0.0));
+        }
+        self.plans.push(plan);
+        self.plans.len() - 1
+    }
+}
+
+#[cfg(feature = "rustfft")]
+impl Debug for RustFft {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RustFft")
+            .field("plans", &self.plans.len())
+            .field("scratch", &self.scratch.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "rustfft")]
+impl FftBackend for RustFft {
+    // The number of samples of a spectrum is an `u32`.
+    const MAX_LEN: usize = 1 << 31;
+
+    fn prepare(&mut self, len: usize) {
+        self.plan_index(len / 2);
+    }
+
+    fn real_fft_in_place<'a>(&mut self, buffer: &'a mut [f32]) -> &'a mut [Complex32] {
+        let len = buffer.len();
+        let half = len / 2;
+        // The even samples are the real parts and the odd samples the
+        // imaginary parts of a complex FFT of half the length.
+        let packed = as_complex(buffer);
+        self.complex_fft_in_place(packed);
+
+        // The spectra of the even and the odd samples are `E[k]` and `O[k]`.
+        // `X[k] = E[k] + W^k * O[k]` and `X[N/2 - k] = conj(E[k] - W^k * O[k])`
+        // with `W = e^(-j * 2 * PI / N)`, so both are calculated together.
+        let dc = packed[0];
+        packed[0] = Complex32::new(dc.re + dc.im, dc.re - dc.im);
+        for k in 1..=half / 2 {
+            let z = packed[k];
+            let z_mirrored = packed[half - k].conj();
+            let even = (z + z_mirrored) * 0.5;
+            let odd = (z - z_mirrored) * Complex32::new(0.0, -0.5);
+            let angle = -2.0 * core::f64::consts::PI * k as f64 / len as f64;
+            let twiddle = Complex32::new(libm::cos(angle) as f32, libm::sin(angle) as f32);
+            packed[k] = even + twiddle * odd;
+            packed[half - k] = (even - twiddle * odd).conj();
+        }
+        packed
+    }
+
+    fn complex_fft_in_place(&mut self, buffer: &mut [Complex32]) {
+        let index = self.plan_index(buffer.len());
+        let plan = &self.plans[index];
+        let scratch_len = plan.get_inplace_scratch_len();
+        plan.process_with_scratch(buffer, &mut self.scratch[..scratch_len]);
+    }
+}
+
+/// The spectrum pipeline on top of a [`FftBackend`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Fft<B: FftBackend> {
+    /// The backend that calculates the transforms.
+    backend: B,
+}
+
+/// The spectrum pipeline on top of the [`DefaultFftBackend`].
+pub(crate) type FftImpl = Fft<DefaultFftBackend>;
+
+impl<B: FftBackend> Fft<B> {
+    /// Creates the pipeline on top of the given backend.
+    #[inline]
+    pub(crate) const fn new(backend: B) -> Self {
+        Self { backend }
+    }
+
+    /// See [`FftBackend::prepare`].
+    #[inline]
+    pub(crate) fn prepare(&mut self, len: usize) {
+        self.backend.prepare(len);
+    }
 
-impl FftImpl {
     /// Calculates the FFT For the given input samples and returns a [`Vec`] of
     /// [`Complex32`] with length `samples.len() / 2 + 1`.
     ///
@@ -69,7 +354,18 @@ impl FftImpl {
     ///   point number (no NaN or infinite) and the length must be
     ///   a power of two. Otherwise, the function panics.
     #[inline]
-    pub(crate) fn calc(samples: &[f32]) -> Vec<Complex32> {
+    pub(crate) fn calc(&mut self, samples: &[f32]) -> Vec<Complex32> {
+        self.calc_iter(samples.iter().copied())
+    }
+
+    /// Like [`Self::calc`] but takes the samples from an iterator. This way,
+    /// samples of other formats, such as integers, are converted straight
+    /// into the buffer of the FFT.
+    #[inline]
+    pub(crate) fn calc_iter(
+        &mut self,
+        samples: impl ExactSizeIterator<Item = f32>,
+    ) -> Vec<Complex32> {
         assert_eq!(
             samples.len() % 2,
             0,
@@ -82,31 +378,11 @@ impl FftImpl {
             "vector capacity must be a multiple of two for safe casting!"
         );
 
-        vec_buffer.extend_from_slice(samples);
+        vec_buffer.extend(samples);
 
         // The result is a view into the buffer.
         // We discard the view and directly operate on the buffer.
-        let _fft_res: &mut [Complex32] = real_fft_n!(
-            &mut vec_buffer,
-            2,
-            4,
-            8,
-            16,
-            32,
-            64,
-            128,
-            256,
-            512,
-            1024,
-            2048,
-            4096,
-            8192,
-            16384,
-            32768
-        );
-
-        const _: [(); size_of::<Complex32>()] = [(); 2 * size_of::<f32>()];
-        const _: [(); align_of::<Complex32>()] = [(); align_of::<f32>()];
+        let _fft_res: &mut [Complex32] = self.calc_in_place(&mut vec_buffer);
 
         // We transform the original vector while preserving its memory, to
         // prevent any reallocation or unnecessary copying.
@@ -115,9 +391,9 @@ impl FftImpl {
             let len = vec_buffer.len() / 2;
             let capacity = vec_buffer.capacity() / 2;
             // SAFETY: `Complex32` is layout-compatible with `[f32; 2]`, as
-            // asserted above. The allocation size is unchanged because both
-            // length and capacity are halved while the element size is doubled,
-            // and the alignments match.
+            // asserted at the top of this module. The allocation size is unchanged
+            // because both length and capacity are halved while the element
+            // size is doubled, and the alignments match.
             let new_buffer_view = unsafe { Vec::from_raw_parts(ptr, len, capacity) };
             mem::forget(vec_buffer);
             new_buffer_view
@@ -131,18 +407,197 @@ impl FftImpl {
         buffer.push(Complex32::new(nyquist_fr_pos_val, 0.0));
         buffer
     }
+
+    /// Calculates the FFT in place without any allocation and returns a view
+    /// into the buffer with `buffer.len() / 2` values of [`Complex32`].
+    ///
+    /// The first index corresponds to the DC component. Unlike [`Self::calc`],
+    /// the real value of the Nyquist frequency is packed into the imaginary
+    /// part of the DC component, as [`microfft::real`] does.
+    ///
+    /// # Parameters
+    /// - `buffer`: Array with samples. See [`Self::calc`].
+    #[inline]
+    pub(crate) fn calc_in_place<'a>(&mut self, buffer: &'a mut [f32]) -> &'a mut [Complex32] {
+        self.backend.real_fft_in_place(buffer)
+    }
+
+    /// Calculates the FFT of complex samples, such as I/Q samples, in place.
+    /// Unlike the FFT of real samples, all `N` values are relevant: the
+    /// indices `N / 2..N` correspond to the negative frequencies.
+    ///
+    /// # Parameters
+    /// - `buffer`: Array with samples. The length must be a power of two.
+    #[inline]
+    pub(crate) fn calc_complex_in_place(&mut self, buffer: &mut [Complex32]) {
+        self.backend.complex_fft_in_place(buffer);
+    }
+
+    /// Calculates the inverse FFT of a full spectrum, i.e. with `N` values
+    /// including the mirrored negative frequencies, and returns the `N`
+    /// complex values of the time domain. For a Hermitian spectrum, such as
+    /// the FFT of real samples, the imaginary parts are zero.
+    ///
+    /// The inverse is calculated with the real forward FFT via
+    /// `IFFT(Z) = conj(FFT(conj(Z))) / N`, where
+    /// `FFT(conj(Z)) = FFT(re(Z)) - j * FFT(im(Z))`.
+    ///
+    /// # Parameters
+    /// - `spectrum`: The spectrum. The length must be a power of two.
+    pub(crate) fn calc_inverse(&mut self, spectrum: &[Complex32]) -> Vec<Complex32> {
+        let len = spectrum.len();
+        let re = self.calc_full(&spectrum.iter().map(|x| x.re).collect::<Vec<_>>());
+        let im = self.calc_full(&spectrum.iter().map(|x| x.im).collect::<Vec<_>>());
+        re.iter()
+            .zip(im)
+            .map(|(re, im)| {
+                let fft_of_conj = re - Complex32::new(-im.im, im.re);
+                fft_of_conj.conj() / len as f32
+            })
+            .collect()
+    }
+
+    /// Like [`Self::calc`] but returns all `N` values, including the
+    /// mirrored negative frequencies.
+    pub(crate) fn calc_full(&mut self, samples: &[f32]) -> Vec<Complex32> {
+        let mut res = self.calc(samples);
+        let len = samples.len();
+        // X[N - k] = conj(X[k]) for real samples
+        for k in (1..len.div_ceil(2)).rev() {
+            res.push(res[k].conj());
+        }
+        res
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::fft::FftImpl;
+    use crate::fft::{Complex32, FftImpl};
 
     /// This test is primarily for miri.
     #[test]
     fn test_memory_safety() {
         let samples = [1.0, 2.0, 3.0, 4.0];
-        let fft = FftImpl::calc(&samples);
+        let fft = FftImpl::default().calc(&samples);
 
         assert_eq!(fft.len(), 2 + 1);
     }
+
+    #[test]
+    fn test_complex() {
+        let samples = [1.0, -2.0, 3.0, 4.5, 0.0, 0.25, -1.0, 7.0];
+        let mut buffer = samples.map(|x| Complex32::new(x, 0.0));
+        let mut fft = FftImpl::default();
+        fft.calc_complex_in_place(&mut buffer);
+        for (val, expected) in buffer.iter().zip(fft.calc_full(&samples)) {
+            assert!((val - expected).norm() < 0.00001);
+        }
+    }
+
+    /// Sizes beyond [`microfft`] use the radix-2 FFT.
+    #[cfg(feature = "large-fft")]
+    #[test]
+    #[cfg_attr(miri, ignore)] // runs forever + no real value add
+    fn test_large() {
+        type FftImpl = crate::fft::Fft<crate::fft::Microfft>;
+        let samples = (0..65536)
+            .map(|i| libm::sinf(i as f32 * 0.01) + 0.5 * libm::cosf(i as f32 * 0.7))
+            .collect::<alloc::vec::Vec<_>>();
+        let mut fft_impl = FftImpl::default();
+        let fft = fft_impl.calc(&samples);
+        assert_eq!(fft.len(), 32769);
+        let expected = crate::radix2::real_fft(
+            &samples
+                .iter()
+                .map(|x| f64::from(*x))
+                .collect::<alloc::vec::Vec<_>>(),
+        );
+        for (val, expected) in fft.iter().zip(&expected) {
+            assert!((f64::from(val.re) - expected.re).abs() < 0.05);
+            assert!((f64::from(val.im) - expected.im).abs() < 0.05);
+        }
+
+        let mut buffer = samples.clone();
+        let packed = fft_impl.calc_in_place(&mut buffer);
+        assert_eq!(packed[0].im, fft[32768].re);
+        assert_eq!(packed[1..], fft[1..32768]);
+
+        let mut complex = samples
+            .iter()
+            .map(|x| Complex32::new(*x, 0.0))
+            .collect::<alloc::vec::Vec<_>>();
+        fft_impl.calc_complex_in_place(&mut complex);
+        for (val, expected) in complex.iter().zip(&fft) {
+            assert!((val - expected).norm() < 0.05);
+        }
+
+        // the twiddle factors of a larger FFT are reused
+        let mut prepared = FftImpl::default();
+        prepared.prepare(131072);
+        for (val, expected) in prepared.calc(&samples).iter().zip(&fft) {
+            assert!((val - expected).norm() < 0.05);
+        }
+    }
+
+    /// All backends must calculate the same FFT, including the packed
+    /// Nyquist frequency.
+    #[cfg(feature = "rustfft")]
+    #[test]
+    #[cfg_attr(miri, ignore)] // runs forever + no real value add
+    fn test_rustfft_matches_microfft() {
+        use crate::fft::{Fft, Microfft, RustFft};
+
+        // one value for all lengths, so that the plans are reused
+        let mut microfft = Fft::<Microfft>::default();
+        let mut rustfft = Fft::<RustFft>::default();
+        for len in [2, 4, 8, 16, 256, 4096, 32768, 256] {
+            let samples = (0..len)
+                .map(|i| libm::sinf(i as f32 * 0.01) + 0.5 * libm::cosf(i as f32 * 0.7))
+                .collect::<alloc::vec::Vec<_>>();
+            let tolerance = 1e-6 * len as f32;
+
+            let mut expected = samples.clone();
+            let expected = microfft.calc_in_place(&mut expected);
+            let mut actual = samples.clone();
+            let actual = rustfft.calc_in_place(&mut actual);
+            for (val, expected) in actual.iter().zip(expected.iter()) {
+                assert!(
+                    (val - expected).norm() < tolerance,
+                    "{len}: {val} != {expected}"
+                );
+            }
+
+            let mut expected = samples
+                .iter()
+                .map(|x| Complex32::new(*x, 0.0))
+                .collect::<alloc::vec::Vec<_>>();
+            let mut actual = expected.clone();
+            microfft.calc_complex_in_place(&mut expected);
+            rustfft.calc_complex_in_place(&mut actual);
+            for (val, expected) in actual.iter().zip(&expected) {
+                assert!(
+                    (val - expected).norm() < tolerance,
+                    "{len}: {val} != {expected}"
+                );
+            }
+        }
+        // half of the lengths for the real FFTs, and the lengths for the
+        // complex FFTs, each planned once
+        assert_eq!(rustfft.backend.plans.len(), 11);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let samples = [1.0, -2.0, 3.0, 4.5, 0.0, 0.25, -1.0, 7.0];
+        let mut fft = FftImpl::default();
+        let full = fft.calc_full(&samples);
+        assert_eq!(full.len(), 8);
+        assert_eq!(full[7], full[1].conj());
+
+        let inverse = fft.calc_inverse(&full);
+        for (val, sample) in inverse.iter().zip(samples) {
+            assert!((val.re - sample).abs() < 0.00001);
+            assert!(val.im.abs() < 0.00001);
+        }
+    }
 }
@@ -0,0 +1,317 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the Goertzel algorithm.
+//!
+//! It calculates single frequencies of the spectrum more efficiently than a
+//! full FFT, e.g. for tone detection on microcontrollers. Everything in this
+//! module works without allocations.
+
+use crate::cross_spectrum::PolarValue;
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::Complex32;
+use crate::limit::FrequencyLimitError;
+use core::f64::consts::PI;
+
+/// Calculates the value of a single frequency, like a single bin of the FFT.
+///
+/// This is the generalized Goertzel algorithm, which also supports
+/// frequencies between the bin centres of an FFT of the same length. At the
+/// bin centres, i.e. multiples of `sampling_rate / samples_len`, the
+/// magnitude equals the one of [`crate::samples_fft_to_spectrum`] and the
+/// phase equals the one of the FFT result.
+///
+/// Samples can be pushed in blocks of any size. The result always refers to
+/// all samples pushed since the creation or the last [`Self::reset`].
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::goertzel::Goertzel;
+///
+/// let samples = [0.0, 1.0, 0.0, -1.0, 0.0, 1.0, 0.0, -1.0];
+/// let mut goertzel = Goertzel::new(2000.0, 8000.0).unwrap();
+/// goertzel.push(&samples);
+/// assert!((goertzel.result().unwrap().magnitude().val() - 4.0).abs() < 0.0001);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Goertzel {
+    /// The frequency to detect.
+    frequency: f32,
+    /// Normalized angular frequency in radians per sample. It is `f64`, so
+    /// that the phase of long streams stays exact.
+    omega: f64,
+    /// `2 * cos(omega)`
+    coefficient: f32,
+    /// The previous state `s[n - 1]`.
+    s1: f32,
+    /// The state before the previous state `s[n - 2]`.
+    s2: f32,
+    /// Number of pushed samples.
+    samples_len: usize,
+    /// The first NaN or infinite sample that was pushed, if any.
+    invalid_sample: Option<f32>,
+}
+
+impl Goertzel {
+    /// Creates a new detector.
+    ///
+    /// ## Parameters
+    /// * `frequency` The frequency to detect. Must be in interval
+    ///   `[0; sampling_rate / 2]`.
+    /// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+    pub fn new(frequency: f32, sampling_rate: f32) -> Result<Self, SpectrumAnalyzerError> {
+        crate::verify_sampling_rate(sampling_rate)?;
+        if frequency.is_nan() || frequency < 0.0 {
+            return Err(SpectrumAnalyzerError::InvalidFrequencyLimit(
+                FrequencyLimitError::ValueBelowMinimum(frequency),
+            ));
+        }
+        if frequency > sampling_rate / 2.0 {
+            return Err(SpectrumAnalyzerError::InvalidFrequencyLimit(
+                FrequencyLimitError::ValueAboveNyquist(frequency),
+            ));
+        }
+        let omega = 2.0 * PI * f64::from(frequency) / f64::from(sampling_rate);
+        Ok(Self {
+            frequency,
+            omega,
+            coefficient: 2.0 * libm::cos(omega) as f32,
+            s1: 0.0,
+            s2: 0.0,
+            samples_len: 0,
+            invalid_sample: None,
+        })
+    }
+
+    /// Returns the frequency to detect.
+    #[inline]
+    #[must_use]
+    pub const fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    /// Returns the number of samples pushed since the creation or the last
+    /// [`Self::reset`].
+    #[inline]
+    #[must_use]
+    pub const fn samples_len(&self) -> usize {
+        self.samples_len
+    }
+
+    /// Forgets all pushed samples, including invalid ones.
+    #[inline]
+    pub const fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+        self.samples_len = 0;
+        self.invalid_sample = None;
+    }
+
+    /// Pushes a block of samples of any size.
+    ///
+    /// A block with NaN or infinite samples is dropped, and [`Self::result`]
+    /// returns an error until the next [`Self::reset`].
+    #[inline]
+    pub fn push(&mut self, samples: &[f32]) {
+        if let Some(invalid_sample) = samples.iter().find(|x| !x.is_finite()) {
+            self.invalid_sample.get_or_insert(*invalid_sample);
+            return;
+        }
+        for sample in samples {
+            let s0 = sample + self.coefficient * self.s1 - self.s2;
+            self.s2 = self.s1;
+            self.s1 = s0;
+        }
+        self.samples_len += samples.len();
+    }
+
+    /// Returns the complex value of the frequency for all pushed samples.
+    /// See [`Self::result`].
+    pub fn complex_result(&self) -> Result<Complex32, SpectrumAnalyzerError> {
+        match self.invalid_sample {
+            Some(x) if x.is_nan() => return Err(SpectrumAnalyzerError::NaNValuesNotSupported),
+            Some(_) => return Err(SpectrumAnalyzerError::InfinityValuesNotSupported),
+            None => {}
+        }
+        // one more iteration without input: s[N] = coefficient * s[N-1] - s[N-2]
+        let s0 = self.coefficient * self.s1 - self.s2;
+        // y[N] = s[N] - e^(-jω) * s[N-1]
+        let y = Complex32::new(
+            s0 - libm::cos(self.omega) as f32 * self.s1,
+            libm::sin(self.omega) as f32 * self.s1,
+        );
+        // The DFT is X(ω) = e^(-jωN) * y[N]. For frequencies at a bin centre,
+        // the factor is `1`. `N` is exact in `f64` for any realistic stream.
+        let angle = -libm::remainder(self.omega * self.samples_len as f64, 2.0 * PI) as f32;
+        Ok(y * Complex32::new(libm::cosf(angle), libm::sinf(angle)))
+    }
+
+    /// Returns the magnitude and the phase of the frequency for all pushed
+    /// samples. The magnitude is not normalized, like the magnitude of
+    /// [`crate::samples_fft_to_spectrum`] without a scaling function.
+    ///
+    /// Returns [`SpectrumAnalyzerError::NaNValuesNotSupported`] or
+    /// [`SpectrumAnalyzerError::InfinityValuesNotSupported`] if such samples
+    /// were pushed since the creation or the last [`Self::reset`].
+    pub fn result(&self) -> Result<PolarValue, SpectrumAnalyzerError> {
+        self.complex_result()
+            .map(|val| PolarValue::from_complex(self.frequency.into(), val))
+    }
+}
+
+/// Calculates the magnitude and phase of several frequencies of the samples
+/// with the Goertzel algorithm. See [`Goertzel`] for details.
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::goertzel::goertzel;
+///
+/// // row and column frequencies of the DTMF keypad
+/// let frequencies = [697.0, 770.0, 852.0, 941.0, 1209.0, 1336.0, 1477.0, 1633.0];
+/// let samples = [0.0; 205];
+/// let results = goertzel(&samples, 8000.0, frequencies).unwrap();
+/// assert_eq!(results[0].magnitude().val(), 0.0);
+/// ```
+pub fn goertzel<const N: usize>(
+    samples: &[f32],
+    sampling_rate: f32,
+    frequencies: [f32; N],
+) -> Result<[PolarValue; N], SpectrumAnalyzerError> {
+    if samples.iter().any(|x| x.is_nan()) {
+        return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+    }
+    if samples.iter().any(|x| x.is_infinite()) {
+        return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+    }
+    let mut detectors = [None; N];
+    for (detector, frequency) in detectors.iter_mut().zip(frequencies) {
+        let mut goertzel = Goertzel::new(frequency, sampling_rate)?;
+        goertzel.push(samples);
+        *detector = Some(goertzel);
+    }
+    Ok(detectors.map(|detector| {
+        detector
+            .expect("all are initialized")
+            .result()
+            .expect("samples are finite")
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FrequencyLimit, samples_fft_to_spectrum};
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_matches_fft_at_bin_centres() {
+        let samples = (0..256)
+            .map(|i| libm::sinf(i as f32 * 0.3) + 0.5 * libm::cosf(i as f32 * 1.1) + 0.2)
+            .collect::<Vec<_>>();
+        let spectrum = samples_fft_to_spectrum(&samples, 1024, FrequencyLimit::All, None).unwrap();
+        let fft = crate::fft::FftImpl::default().calc(&samples);
+        // rounding errors of f32 accumulate
+        let tolerance = spectrum.max().1.val() * 0.0001;
+
+        for (fft_index, (fr, _)) in spectrum.data().iter().enumerate() {
+            let mut goertzel = Goertzel::new(fr.val(), 1024.0).unwrap();
+            // in blocks of different sizes
+            for block in samples.chunks(100) {
+                goertzel.push(block);
+            }
+            let result = goertzel.result().unwrap();
+            let expected = spectrum.freq_val_exact(fr.val());
+            assert!(
+                (result.magnitude().val() - expected.val()).abs() < tolerance,
+                "{result:?} != {expected:?}"
+            );
+            let complex = goertzel.complex_result().unwrap();
+            assert!((complex - fft[fft_index]).norm_sqr() < tolerance * tolerance);
+        }
+    }
+
+    /// Between the bin centres, the generalized algorithm still detects the
+    /// exact magnitude and phase of a tone.
+    #[test]
+    fn test_non_integer_bin() {
+        let frequency = 1234.5;
+        let samples = (0..1000)
+            .map(|i| libm::cosf(2.0 * core::f32::consts::PI * frequency * i as f32 / 8000.0 + 0.5))
+            .collect::<Vec<_>>();
+        let [tone, other] = goertzel(&samples, 8000.0, [frequency, 2000.0]).unwrap();
+        // N/2 for a cosine with amplitude 1
+        assert!((tone.magnitude().val() - 500.0).abs() < 1.0, "{tone:?}");
+        assert!((tone.phase() - 0.5).abs() < 0.01, "{tone:?}");
+        assert!(other.magnitude().val() < 5.0, "{other:?}");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(Goertzel::new(-1.0, 8000.0).is_err());
+        assert!(Goertzel::new(f32::NAN, 8000.0).is_err());
+        assert!(Goertzel::new(4000.1, 8000.0).is_err());
+        Goertzel::new(4000.0, 8000.0).unwrap();
+        assert!(Goertzel::new(0.0, 0.0).is_err());
+        assert!(matches!(
+            goertzel(&[f32::NAN], 8000.0, [1000.0]),
+            Err(SpectrumAnalyzerError::NaNValuesNotSupported)
+        ));
+
+        let mut goertzel = Goertzel::new(1000.0, 8000.0).unwrap();
+        goertzel.push(&[1.0; 10]);
+        assert_eq!(goertzel.samples_len(), 10);
+        goertzel.reset();
+        assert_eq!(goertzel.samples_len(), 0);
+        assert_eq!(goertzel.result().unwrap().magnitude().val(), 0.0);
+
+        // invalid samples are dropped and flagged until the next reset
+        goertzel.push(&[1.0, f32::NAN]);
+        goertzel.push(&[f32::INFINITY]);
+        assert_eq!(goertzel.samples_len(), 0);
+        assert!(matches!(
+            goertzel.result(),
+            Err(SpectrumAnalyzerError::NaNValuesNotSupported)
+        ));
+        goertzel.reset();
+        goertzel.push(&[f32::NEG_INFINITY]);
+        assert!(matches!(
+            goertzel.complex_result(),
+            Err(SpectrumAnalyzerError::InfinityValuesNotSupported)
+        ));
+        goertzel.reset();
+        assert!(goertzel.result().is_ok());
+    }
+
+    /// The phase correction stays exact for streams longer than the mantissa
+    /// of `f32`.
+    #[test]
+    fn test_long_stream_phase() {
+        let mut goertzel = Goertzel::new(1234.5, 8000.0).unwrap();
+        goertzel.push(&[1.0, 0.5]);
+        let expected = goertzel.complex_result().unwrap();
+        // 1234.5 Hz has a period of 16000 samples
+        goertzel.samples_len += 16000 << 12;
+        let result = goertzel.complex_result().unwrap();
+        assert!((result - expected).norm() < 1e-5, "{result} != {expected}");
+    }
+}
@@ -0,0 +1,247 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+//! Module for the enum [`Interpolation`].
+
+use crate::frequency::{Frequency, FrequencyValue};
+use core::f64::consts::PI;
+
+/// Number of neighbouring bins on each side that contribute to
+/// [`Interpolation::Sinc`].
+const SINC_HALF_WIDTH: usize = 8;
+
+/// Describes how the value of a frequency between two bins of a
+/// [`crate::FrequencySpectrum`] is calculated, e.g. by
+/// [`crate::FrequencySpectrum::try_freq_val_interpolated`].
+///
+/// All modes return the exact value of a bin for the frequency of the bin.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Value of the closest bin, see
+    /// [`crate::FrequencySpectrum::freq_val_closest`].
+    Nearest,
+    /// Linear interpolation between the two adjacent bins, see
+    /// [`crate::FrequencySpectrum::freq_val_exact`]. This is the default.
+    #[default]
+    Linear,
+    /// Linear interpolation of the decibel values of the two adjacent bins,
+    /// i.e. a geometric interpolation of the linear values. This follows the
+    /// slopes of peaks better than [`Self::Linear`]. If one of the values
+    /// isn't positive, e.g. because the spectrum is already scaled to
+    /// decibels, it falls back to [`Self::Linear`].
+    LinearDb,
+    /// Cubic Catmull-Rom spline through the four surrounding bins. At the
+    /// edges of the spectrum, the missing bin is extrapolated linearly. The
+    /// result may overshoot the values of the bins.
+    Cubic,
+    /// Band-limited interpolation with the Dirichlet kernel, i.e. the
+    /// periodic sinc function of the FFT length, of the 16 surrounding bins.
+    ///
+    /// The weights are normalized to sum up to one, so a flat spectrum stays
+    /// flat, also at the edges of the spectrum where fewer bins contribute.
+    /// As the values of the bins lack their phase, this is a smooth curve
+    /// through the bins rather than the exact spectrum between them: the peak
+    /// of a tone between two bins is underestimated by about 2 dB, while
+    /// [`Self::Linear`] underestimates it by up to 4 dB. Like
+    /// [`Self::Cubic`], the result may overshoot the values of the bins.
+    Sinc,
+}
+
+impl Interpolation {
+    /// Calculates the value at `search_fr` between the bins `upper_index - 1`
+    /// and `upper_index` of `data`.
+    ///
+    /// ## Parameters
+    /// * `data` The bins of the spectrum, sorted by frequency.
+    /// * `upper_index` Index of the bin above `search_fr`. At least one.
+    /// * `search_fr` The frequency between the two bins.
+    /// * `samples_len` Number of samples of the FFT.
+    pub(crate) fn interpolate(
+        self,
+        data: &[(Frequency, FrequencyValue)],
+        upper_index: usize,
+        search_fr: f32,
+        samples_len: u32,
+    ) -> f32 {
+        let (fr_a, val_a) = data[upper_index - 1];
+        let (fr_b, val_b) = data[upper_index];
+        let (val_a, val_b) = (val_a.val(), val_b.val());
+        // position between the two bins, from 0 to 1
+        let t = (search_fr - fr_a.val()) / (fr_b.val() - fr_a.val());
+        let linear = val_a + t * (val_b - val_a);
+
+        match self {
+            Self::Nearest => {
+                if t < 0.5 {
+                    val_a
+                } else {
+                    val_b
+                }
+            }
+            Self::Linear => linear,
+            Self::LinearDb => {
+                if val_a > 0.0 && val_b > 0.0 {
+                    libm::powf(val_a, 1.0 - t) * libm::powf(val_b, t)
+                } else {
+                    linear
+                }
+            }
+            Self::Cubic => {
+                let before = upper_index
+                    .checked_sub(2)
+                    .map_or(2.0 * val_a - val_b, |i| data[i].1.val());
+                let after = data
+                    .get(upper_index + 1)
+                    .map_or(2.0 * val_b - val_a, |(_, val)| val.val());
+                catmull_rom(before, val_a, val_b, after, t)
+            }
+            Self::Sinc => {
+                // fractional index of `search_fr`
+                let position = (upper_index - 1) as f64 + f64::from(t);
+                let first = upper_index.saturating_sub(SINC_HALF_WIDTH);
+                let last = (upper_index + SINC_HALF_WIDTH).min(data.len());
+                // The bins that enter or leave the window when `search_fr`
+                // crosses a bin have a weight of zero, so the result is
+                // continuous.
+                let (sum, weights) = (first..last)
+                    .map(|i| {
+                        let weight = dirichlet_kernel(position - i as f64, samples_len);
+                        (f64::from(data[i].1.val()) * weight, weight)
+                    })
+                    .fold((0.0, 0.0), |(sum, weights), (val, weight)| {
+                        (sum + val, weights + weight)
+                    });
+                (sum / weights) as f32
+            }
+        }
+    }
+}
+
+/// Catmull-Rom spline between `y1` and `y2` at `t` in `0..=1`, with the
+/// neighbours `y0` and `y3`.
+fn catmull_rom(y0: f32, y1: f32, y2: f32, y3: f32, t: f32) -> f32 {
+    let a = -0.5 * y0 + 1.5 * y1 - 1.5 * y2 + 0.5 * y3;
+    let b = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+    let c = -0.5 * y0 + 0.5 * y2;
+    ((a * t + b) * t + c) * t + y1
+}
+
+/// Dirichlet kernel `sin(PI * x) / (N * sin(PI * x / N))` of an FFT of `N`
+/// samples, where `x` is the distance in bins. It is one for `x = 0` and zero
+/// for all other whole numbers below `N`.
+fn dirichlet_kernel(x: f64, samples_len: u32) -> f64 {
+    if x.abs() < 1e-9 {
+        return 1.0;
+    }
+    let n = f64::from(samples_len);
+    libm::sin(PI * x) / (n * libm::sin(PI * x / n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn data(values: &[f32]) -> Vec<(Frequency, FrequencyValue)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, val)| ((i as f32 * 10.0).into(), (*val).into()))
+            .collect()
+    }
+
+    #[test]
+    fn test_linear_db() {
+        let data = data(&[1.0, 100.0]);
+        let val = Interpolation::LinearDb.interpolate(&data, 1, 5.0, 4);
+        assert!((val - 10.0).abs() < 1e-4, "{val}");
+        // falls back to linear
+        let data = self::data(&[-20.0, 0.0]);
+        assert_eq!(Interpolation::LinearDb.interpolate(&data, 1, 5.0, 4), -10.0);
+    }
+
+    #[test]
+    fn test_cubic() {
+        // the spline reproduces a straight line, also at the edges
+        let data = data(&[1.0, 2.0, 3.0, 4.0]);
+        for (index, fr, expected) in [(1, 5.0, 1.5), (2, 12.5, 2.25), (3, 27.5, 3.75)] {
+            let val = Interpolation::Cubic.interpolate(&data, index, fr, 8);
+            assert!((val - expected).abs() < 1e-5, "{val} != {expected}");
+        }
+        // and is smooth around a peak
+        let data = self::data(&[0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(Interpolation::Cubic.interpolate(&data, 2, 15.0, 8), 1.125);
+    }
+
+    const ALL: [Interpolation; 5] = [
+        Interpolation::Nearest,
+        Interpolation::Linear,
+        Interpolation::LinearDb,
+        Interpolation::Cubic,
+        Interpolation::Sinc,
+    ];
+
+    #[test]
+    fn test_flat() {
+        let data = data(&[1.0; 32]);
+        for interpolation in ALL {
+            for fr in [0.5, 1.0, 2.5, 5.0, 97.5, 155.0, 309.0] {
+                let index = data.partition_point(|(x, _)| x.val() < fr);
+                let val = interpolation.interpolate(&data, index, fr, 64);
+                assert!((val - 1.0).abs() < 1e-5, "{interpolation:?} at {fr}: {val}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_continuous_at_bins() {
+        let data = data(&[
+            3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0, 5.0, 3.0, 5.0, 8.0, 9.0, 7.0, 9.0, 3.0, 2.0,
+            3.0, 8.0, 4.0,
+        ]);
+        for interpolation in ALL {
+            for index in [1, 4, 10, 18] {
+                let bin = data[index].1.val();
+                let fr = data[index].0.val();
+                let below = interpolation.interpolate(&data, index, fr - 0.001, 32);
+                let above = interpolation.interpolate(&data, index + 1, fr + 0.001, 32);
+                for val in [below, above] {
+                    assert!(
+                        (val - bin).abs() < 0.01,
+                        "{interpolation:?} at bin {index}: {val} != {bin}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dirichlet_kernel() {
+        assert_eq!(dirichlet_kernel(0.0, 64), 1.0);
+        assert!(dirichlet_kernel(1.0, 64).abs() < 1e-9);
+        assert!(dirichlet_kernel(-3.0, 64).abs() < 1e-9);
+        // half a bin off, the peak is about 4 dB lower
+        assert!((dirichlet_kernel(0.5, 64) - 0.6367).abs() < 1e-3);
+    }
+}
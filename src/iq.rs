@@ -0,0 +1,317 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+//! Module for complex samples, such as the baseband I/Q samples of a software
+//! defined radio (SDR).
+//!
+//! Unlike real samples, complex samples distinguish positive and negative
+//! frequencies. Their spectrum is two-sided and ranges from
+//! `-sampling_rate / 2` to `sampling_rate / 2`. As with `fftshift` of other
+//! tools, the spectrum is ordered from the lowest negative frequency to the
+//! highest positive frequency.
+
+pub use crate::fft::Complex32;
+
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::{DefaultFftBackend, Fft, FftBackend};
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::scaling::SpectrumScalingFunction;
+use crate::{FrequencyLimit, FrequencySpectrum, SpectrumOutput};
+use alloc::vec::Vec;
+
+/// Calculates the two-sided spectrum of complex samples. The frequencies
+/// range from `-sampling_rate / 2` to `sampling_rate / 2 - frequency_resolution`.
+///
+/// ## Parameters
+/// * `samples` The complex samples, e.g. `I + jQ`. The length must be a
+///   power of two and not bigger than 32768, unless the `large-fft` or
+///   the `rustfft` feature is enabled.
+/// * `window` Optional coefficients of a window function with the same
+///   length as the samples, e.g. `hann_window(&[1.0; 4096])`.
+/// * `sampling_rate` The used sampling rate, e.g. `2_048_000.0 [Hz]`.
+/// * `frequency_limit` The [`FrequencyLimit`]. Unlike for real samples, the
+///   limits may be negative, e.g. `FrequencyLimit::Range(-100e3, 50e3)`.
+/// * `output` See [`SpectrumOutput`]. The densities are two-sided, i.e. they
+///   are not doubled.
+/// * `scaling_fn` See [`SpectrumScalingFunction`].
+///
+/// ## Examples
+/// ```rust
+/// use spectrum_analyzer::iq::{samples_fft_to_spectrum, Complex32};
+/// use spectrum_analyzer::{FrequencyLimit, SpectrumOutput};
+/// // a tone at -250 kHz relative to the centre frequency
+/// let samples = (0..1024)
+///     .map(|i| {
+///         let phase = -2.0 * core::f32::consts::PI * 0.25 * i as f32;
+///         Complex32::new(phase.cos(), phase.sin())
+///     })
+///     .collect::<Vec<_>>();
+/// let spectrum = samples_fft_to_spectrum(
+///     &samples,
+///     None,
+///     1_000_000.0,
+///     FrequencyLimit::All,
+///     SpectrumOutput::Magnitude,
+///     None,
+/// )
+/// .unwrap();
+/// assert_eq!(spectrum.min_fr().val(), -500_000.0);
+/// assert_eq!(spectrum.max().0.val(), -250_000.0);
+/// ```
+pub fn samples_fft_to_spectrum(
+    samples: &[Complex32],
+    window: Option<&[f32]>,
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    output: SpectrumOutput,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    samples_fft_to_spectrum_with_backend::<DefaultFftBackend>(
+        samples,
+        window,
+        sampling_rate,
+        frequency_limit,
+        output,
+        scaling_fn,
+    )
+}
+
+/// Like [`samples_fft_to_spectrum`] but calculates the FFT with the given
+/// [`FftBackend`] instead of the [`DefaultFftBackend`].
+pub fn samples_fft_to_spectrum_with_backend<B: FftBackend>(
+    samples: &[Complex32],
+    window: Option<&[f32]>,
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    output: SpectrumOutput,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    if samples.len() < 2 {
+        return Err(SpectrumAnalyzerError::TooFewSamples);
+    }
+    let values = || {
+        samples
+            .iter()
+            .flat_map(|x| [x.re, x.im])
+            .chain(window.into_iter().flatten().copied())
+    };
+    if values().any(|x| x.is_nan()) {
+        return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+    }
+    if values().any(|x| x.is_infinite()) {
+        return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+    }
+    if !samples.len().is_power_of_two() {
+        return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+    }
+    if samples.len() > B::MAX_LEN {
+        return Err(SpectrumAnalyzerError::TooManySamples);
+    }
+    if let Some(window) = window.filter(|window| window.len() != samples.len()) {
+        return Err(SpectrumAnalyzerError::InvalidWindowLength(window.len()));
+    }
+    crate::verify_sampling_rate(sampling_rate)?;
+    frequency_limit
+        .verify_two_sided(sampling_rate / 2.0)
+        .map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+    output.verify()?;
+
+    let mut buffer = window.map_or_else(
+        || samples.to_vec(),
+        |window| samples.iter().zip(window).map(|(x, w)| x * w).collect(),
+    );
+    Fft::<B>::default().calc_complex_in_place(&mut buffer);
+
+    let samples_len = samples.len();
+    let frequency_resolution = sampling_rate / samples_len as f32;
+    // negative frequencies first, as with `fftshift`
+    let data = (samples_len / 2..samples_len)
+        .chain(0..samples_len / 2)
+        .map(|fft_index| {
+            let bin = fft_index as isize
+                - if fft_index >= samples_len / 2 {
+                    samples_len as isize
+                } else {
+                    0
+                };
+            (bin as f32 * frequency_resolution, fft_index)
+        })
+        .filter(|(fr, _)| frequency_limit.contains(*fr))
+        .map(|(fr, fft_index)| {
+            let val = output.convert_two_sided(&buffer[fft_index], sampling_rate);
+            (Frequency::from(fr), FrequencyValue::from(val))
+        })
+        .collect::<Vec<_>>();
+    // statistics and interpolation require at least two frequency points
+    if data.len() < 2 {
+        return Err(SpectrumAnalyzerError::FrequencyLimitTooNarrow);
+    }
+
+    let mut working_buffer = vec![(0.0.into(), 0.0.into()); data.len()];
+    let mut spectrum = FrequencySpectrum::new(
+        data,
+        frequency_resolution,
+        samples_len as u32,
+        &mut working_buffer,
+    );
+    if let Some(scaling_fn) = scaling_fn {
+        spectrum.apply_scaling_fn(scaling_fn, &mut working_buffer)?;
+    }
+    Ok(spectrum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sine::complex_tone;
+    use crate::windows::hann_window;
+    fn spectrum(
+        samples: &[Complex32],
+        limit: FrequencyLimit,
+    ) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+        samples_fft_to_spectrum(
+            samples,
+            None,
+            1024.0,
+            limit,
+            SpectrumOutput::Magnitude,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_negative_and_positive_frequencies() {
+        for frequency in [-100.0, 100.0, -512.0, 0.0, 508.0] {
+            let samples = complex_tone(frequency, 1.0, 1024.0, 256);
+            let spectrum = spectrum(&samples, FrequencyLimit::All).unwrap();
+            assert_eq!(spectrum.data().len(), 256);
+            assert_eq!(spectrum.min_fr().val(), -512.0);
+            assert_eq!(spectrum.max_fr().val(), 508.0);
+            assert_eq!(spectrum.max().0.val(), frequency);
+            assert!((spectrum.max().1.val() - 256.0).abs() < 0.01);
+            assert!(
+                spectrum
+                    .data()
+                    .windows(2)
+                    .all(|pair| pair[1].0.val() - pair[0].0.val() == 4.0),
+                "must be ordered like fftshift"
+            );
+        }
+    }
+
+    #[test]
+    fn test_negative_limits() {
+        let samples = complex_tone(-100.0, 1.0, 1024.0, 256);
+        let limited = spectrum(&samples, FrequencyLimit::Range(-200.0, -40.0)).unwrap();
+        assert_eq!(limited.min_fr().val(), -200.0);
+        assert_eq!(limited.max_fr().val(), -40.0);
+        assert_eq!(limited.max().0.val(), -100.0);
+
+        assert!(matches!(
+            spectrum(&samples, FrequencyLimit::Min(-513.0)),
+            Err(SpectrumAnalyzerError::InvalidFrequencyLimit(_))
+        ));
+        assert!(matches!(
+            spectrum(&samples, FrequencyLimit::Range(-1.0, 1.0)),
+            Err(SpectrumAnalyzerError::FrequencyLimitTooNarrow)
+        ));
+    }
+
+    /// Real samples are a special case: the spectrum is symmetric and the
+    /// positive half equals the spectrum of the real FFT.
+    #[test]
+    fn test_matches_real_samples() {
+        let samples = (0..128)
+            .map(|i| libm::sinf(i as f32 * 0.4) + 0.3 * libm::cosf(i as f32 * 1.9))
+            .collect::<Vec<_>>();
+        let complex = samples
+            .iter()
+            .map(|x| Complex32::new(*x, 0.0))
+            .collect::<Vec<_>>();
+        let two_sided = spectrum(&complex, FrequencyLimit::Min(0.0)).unwrap();
+        let one_sided =
+            crate::samples_fft_to_spectrum(&samples, 1024, FrequencyLimit::All, None).unwrap();
+        for ((fr, val), (expected_fr, expected_val)) in
+            two_sided.data().iter().zip(one_sided.data())
+        {
+            assert_eq!(fr, expected_fr);
+            assert!((val.val() - expected_val.val()).abs() < 0.001);
+        }
+    }
+
+    /// The conversions of the spectrum handle the negative frequencies.
+    #[test]
+    fn test_negative_frequencies_of_spectrum() {
+        let samples = complex_tone(-96.0, 1.0, 1024.0, 64)
+            .into_iter()
+            .map(|x| x + 0.5)
+            .collect::<Vec<_>>();
+        let spectrum = spectrum(&samples, FrequencyLimit::All).unwrap();
+        assert!((spectrum.dc_component().unwrap().val() - 32.0).abs() < 0.01);
+
+        let map = spectrum.to_signed_map();
+        assert_eq!(map.len(), 64);
+        assert_eq!(map.first_key_value().unwrap().0, &-512);
+        assert!((map[&-96] - 64.0).abs() < 0.01);
+        assert!((map[&0] - 32.0).abs() < 0.01);
+
+        // only the non-negative frequencies
+        let map = spectrum.to_map();
+        assert_eq!(map.len(), 32);
+        assert!((map[&0] - 32.0).abs() < 0.01);
+        assert_eq!(spectrum.to_mel_map().len(), 32);
+    }
+
+    /// The integral of the two-sided PSD is the mean power.
+    #[test]
+    fn test_power_spectral_density() {
+        let samples = complex_tone(-100.0, 0.5, 1024.0, 256);
+        let window = hann_window(&[1.0; 256]);
+        let window_energy = window.iter().map(|w| w * w).sum();
+        let psd = samples_fft_to_spectrum(
+            &samples,
+            Some(&window),
+            1024.0,
+            FrequencyLimit::All,
+            SpectrumOutput::PowerSpectralDensity { window_energy },
+            None,
+        )
+        .unwrap();
+        let mean_power =
+            psd.data().iter().map(|(_, val)| val.val()).sum::<f32>() * psd.frequency_resolution();
+        assert!((mean_power - 0.25).abs() < 0.0001, "{mean_power}");
+
+        assert!(matches!(
+            samples_fft_to_spectrum(
+                &samples,
+                Some(&window[..128]),
+                1024.0,
+                FrequencyLimit::All,
+                SpectrumOutput::Magnitude,
+                None,
+            ),
+            Err(SpectrumAnalyzerError::InvalidWindowLength(128))
+        ));
+    }
+}
@@ -82,22 +82,45 @@ extern crate std;
 extern crate alloc;
 
 pub use crate::frequency::{Frequency, FrequencyValue};
+pub use crate::interpolation::Interpolation;
 pub use crate::limit::FrequencyLimit;
 pub use crate::limit::FrequencyLimitError;
-pub use crate::spectrum::FrequencySpectrum;
+pub use crate::output::SpectrumOutput;
+pub use crate::spectrum::{FrequencyLookupError, FrequencySpectrum};
 
 use crate::error::SpectrumAnalyzerError;
-use crate::fft::{Complex32, FftImpl};
+use crate::fft::{Complex32, DefaultFftBackend, Fft, FftBackend};
 use crate::scaling::SpectrumScalingFunction;
 use alloc::vec::Vec;
 
+pub mod averaging;
+#[cfg(feature = "rayon")]
+pub mod batch;
+pub mod calibration;
+pub mod cepstrum;
+pub mod chroma;
+pub mod constant_q;
+pub mod correlation;
+pub mod cross_spectrum;
+pub mod double;
+pub mod dtmf;
 pub mod error;
-mod fft;
+pub mod fft;
 mod frequency;
+pub mod goertzel;
+mod interpolation;
+pub mod iq;
 mod limit;
+pub mod lomb_scargle;
+pub mod multichannel;
+mod output;
+pub mod pcm;
+mod radix2;
 pub mod scaling;
 mod spectrum;
+pub mod streaming;
 pub mod windows;
+pub mod zoom;
 
 // test module for large "integration"-like tests
 #[cfg(test)]
@@ -118,7 +141,8 @@ mod tests;
 ///   e.g. `44100/(16384/2) == 5.383Hz`, i.e. more samples =>
 ///   better accuracy/frequency resolution. The amount of samples must
 ///   be a power of 2. If you don't have enough data, provide zeroes.
-/// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+/// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`. Use
+///   [`samples_fft_to_spectrum_with_output`] for fractional sampling rates.
 /// * `frequency_limit` The [`FrequencyLimit`].
 /// * `scaling_fn` See [`SpectrumScalingFunction`] for details.
 ///
@@ -152,13 +176,93 @@ mod tests;
 ///  );
 /// ```
 ///
-/// ## Panics
-/// * When `samples.len()` isn't a power of two less than or equal to `16384` and `microfft` is used
+/// ## Errors
+/// * [`SpectrumAnalyzerError::TooManySamples`] when `samples.len()` is bigger
+///   than `32768` and neither the `large-fft` nor the `rustfft` feature is
+///   enabled
 pub fn samples_fft_to_spectrum(
     samples: &[f32],
     sampling_rate: u32,
     frequency_limit: FrequencyLimit,
     scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    samples_fft_to_spectrum_with_output(
+        samples,
+        sampling_rate as f32,
+        frequency_limit,
+        SpectrumOutput::Magnitude,
+        scaling_fn,
+    )
+}
+
+/// Like [`samples_fft_to_spectrum`] but the frequency values represent the
+/// given [`SpectrumOutput`], e.g. the power spectral density, instead of the
+/// magnitude.
+///
+/// * `sampling_rate` The used sampling rate in Hertz. Unlike with
+///   [`samples_fft_to_spectrum`], it may be fractional, e.g. `25599.87` for a
+///   calibrated clock or `0.5` for a slow sensor. A `f64` sampling rate can be
+///   converted with `as f32`, as all frequencies are `f32` anyway.
+/// * `output` See [`SpectrumOutput`] for details.
+///
+/// ## Examples
+/// ```rust
+/// use spectrum_analyzer::{samples_fft_to_spectrum_with_output, FrequencyLimit, SpectrumOutput};
+/// use spectrum_analyzer::windows::hann_window;
+/// // get data from audio source
+/// let samples = hann_window(&[0.0, 1.1, 5.5, -5.5]);
+/// // sum of the squared coefficients of the Hann window
+/// let window_energy = hann_window(&[1.0; 4]).iter().map(|w| w * w).sum();
+/// let psd = samples_fft_to_spectrum_with_output(
+///         &samples,
+///         44100.0,
+///         FrequencyLimit::All,
+///         SpectrumOutput::PowerSpectralDensity { window_energy },
+///         None,
+///  );
+/// ```
+pub fn samples_fft_to_spectrum_with_output(
+    samples: &[f32],
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    output: SpectrumOutput,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    samples_fft_to_spectrum_with_backend::<DefaultFftBackend>(
+        samples,
+        sampling_rate,
+        frequency_limit,
+        output,
+        scaling_fn,
+    )
+}
+
+/// Like [`samples_fft_to_spectrum_with_output`] but calculates the FFT with
+/// the given [`FftBackend`] instead of the [`DefaultFftBackend`].
+///
+/// This way, e.g. an embedded target keeps [`fft::Microfft`], even if
+/// another dependency enabled the `rustfft` feature.
+///
+/// ## Examples
+/// ```rust
+/// use spectrum_analyzer::fft::Microfft;
+/// use spectrum_analyzer::{samples_fft_to_spectrum_with_backend, FrequencyLimit, SpectrumOutput};
+/// // get data from audio source
+/// let samples = vec![0.0, 1.1, 5.5, -5.5];
+/// let res = samples_fft_to_spectrum_with_backend::<Microfft>(
+///         &samples,
+///         44100.0,
+///         FrequencyLimit::All,
+///         SpectrumOutput::Magnitude,
+///         None,
+///  );
+/// ```
+pub fn samples_fft_to_spectrum_with_backend<B: FftBackend>(
+    samples: &[f32],
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    output: SpectrumOutput,
+    scaling_fn: Option<&SpectrumScalingFunction>,
 ) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
     // everything below two samples is unreasonable
     if samples.len() < 2 {
@@ -174,11 +278,16 @@ pub fn samples_fft_to_spectrum(
     if !samples.len().is_power_of_two() {
         return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
     }
-    let max_detectable_frequency = sampling_rate as f32 / 2.0;
+    if samples.len() > B::MAX_LEN {
+        return Err(SpectrumAnalyzerError::TooManySamples);
+    }
+    verify_sampling_rate(sampling_rate)?;
+    let max_detectable_frequency = sampling_rate / 2.0;
     // verify frequency limit: unwrap error or else ok
     frequency_limit
         .verify(max_detectable_frequency)
         .map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+    output.verify()?;
 
     // With FFT we transform an array of time-domain waveform samples
     // into an array of frequency-domain spectrum samples
@@ -188,15 +297,14 @@ pub fn samples_fft_to_spectrum(
     // (but when we interpret the result, we don't need all indices)
 
     // applies the f32 samples onto the FFT algorithm implementation
-    // chosen at compile time (via Cargo feature).
-    // If a complex FFT implementation was chosen, this will internally
-    // transform all data to Complex numbers.
-    let fft_res = FftImpl::calc(samples);
+    // chosen at compile time (via generic parameter or Cargo feature).
+    let fft_res = Fft::<B>::default().calc(samples);
 
     // This function:
     // 1) calculates the corresponding frequency of each index in the FFT result
     // 2) filters out unwanted frequencies
-    // 3) calculates the magnitude (absolute value) at each frequency index for each complex value
+    // 3) calculates the magnitude (absolute value), or the desired output,
+    //    at each frequency index for each complex value
     // 4) optionally scales the magnitudes
     // 5) collects everything into the struct "FrequencySpectrum"
     fft_result_to_spectrum(
@@ -204,6 +312,7 @@ pub fn samples_fft_to_spectrum(
         &fft_res,
         sampling_rate,
         frequency_limit,
+        output,
         scaling_fn,
     )
 }
@@ -219,16 +328,18 @@ pub fn samples_fft_to_spectrum(
 /// * `fft_result` Result buffer from FFT. Has the same length as the samples array.
 /// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
 /// * `frequency_limit` The [`FrequencyLimit`].
+/// * `output` The [`SpectrumOutput`].
 /// * `scaling_fn` See [`SpectrumScalingFunction`] for details.
 ///
 /// ## Return value
 /// New object of type [`FrequencySpectrum`].
 #[inline]
-fn fft_result_to_spectrum(
+pub(crate) fn fft_result_to_spectrum(
     samples_len: usize,
     fft_result: &[Complex32],
-    sampling_rate: u32,
+    sampling_rate: f32,
     frequency_limit: FrequencyLimit,
+    output: SpectrumOutput,
     scaling_fn: Option<&SpectrumScalingFunction>,
 ) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
     let maybe_min = frequency_limit.maybe_min();
@@ -274,8 +385,10 @@ fn fft_result_to_spectrum(
                 // frequency step/resolution is for example: 1/2048 * 44100 = 21.53 Hz
                 //                                             2048 samples, 44100 sample rate
                 //
-                // equal to: 1.0 / samples_len as f32 * sampling_rate as f32
+                // equal to: 1.0 / samples_len as f32 * sampling_rate
                 fft_index as f32 * frequency_resolution,
+                // required for the output conversion, e.g. of the DC component
+                fft_index,
                 // in this .map() step we do nothing with this yet
                 fft_result,
             )
@@ -283,7 +396,7 @@ fn fft_result_to_spectrum(
         // #######################
         // ### BEGIN filtering: results in lower calculation and memory overhead!
         // check lower bound frequency (inclusive)
-        .filter(|(fr, _fft_result)| {
+        .filter(|(fr, _fft_index, _fft_result)| {
             maybe_min.is_none_or(|min_fr| {
                 // inclusive!
                 // attention: due to the frequency resolution, we do not necessarily hit
@@ -293,7 +406,7 @@ fn fft_result_to_spectrum(
             })
         })
         // check upper bound frequency (inclusive)
-        .filter(|(fr, _fft_result)| {
+        .filter(|(fr, _fft_index, _fft_result)| {
             maybe_max.is_none_or(|max_fr| {
                 // inclusive!
                 // attention: due to the frequency resolution, we do not necessarily hit
@@ -306,7 +419,13 @@ fn fft_result_to_spectrum(
         // #######################
         // FFT result is always complex: calc magnitude
         //   sqrt(re*re + im*im) (re: real part, im: imaginary part)
-        .map(|(fr, complex_res)| (fr, complex_to_magnitude(complex_res)))
+        // or the desired output, such as the power
+        .map(|(fr, fft_index, complex_res)| {
+            (
+                fr,
+                output.convert(complex_res, fft_index, samples_len, sampling_rate),
+            )
+        })
         // transform to my thin convenient orderable f32 wrappers
         .map(|(fr, val)| (Frequency::from(fr), FrequencyValue::from(val)))
         // collect all into a sorted vector (from lowest frequency to highest)
@@ -336,6 +455,24 @@ fn fft_result_to_spectrum(
     Ok(spectrum)
 }
 
+/// Verifies that the sampling rate is a positive and finite number. It may be
+/// fractional, e.g. `25599.87 [Hz]` or `0.5 [Hz]`.
+#[inline]
+pub(crate) fn verify_sampling_rate(sampling_rate: f32) -> Result<(), SpectrumAnalyzerError> {
+    if sampling_rate.is_finite() && sampling_rate > 0.0 {
+        Ok(())
+    } else {
+        Err(SpectrumAnalyzerError::InvalidSamplingRate(sampling_rate))
+    }
+}
+
+/// Converts a constant, e.g. a coefficient of a window function, into the
+/// floating point type `T`, i.e. `f32` or `f64`.
+#[inline]
+pub(crate) fn constant<T: num_traits::Float>(val: f64) -> T {
+    T::from(val).expect("constant is representable")
+}
+
 /// Calculate the frequency resolution of the FFT. It is determined by the sampling rate
 /// in Hertz and N, the number of samples given into the FFT. With the frequency resolution,
 /// we can determine the corresponding frequency of each index in the FFT result buffer.
@@ -353,20 +490,6 @@ fn fft_result_to_spectrum(
 /// * <https://www.researchgate.net/post/How-can-I-define-the-frequency-resolution-in-FFT-And-what-is-the-difference-on-interpreting-the-results-between-high-and-low-frequency-resolution>
 /// * <https://stackoverflow.com/questions/4364823/>
 #[inline]
-fn fft_calc_frequency_resolution(sampling_rate: u32, samples_len: u32) -> f32 {
-    sampling_rate as f32 / samples_len as f32
-}
-
-/// Maps a [`Complex32`] to its magnitude as `f32`. This is done by calculating
-/// `sqrt(re*re + im*im)`. This is required to convert the complex FFT results
-/// back to real values.
-///
-/// ## Parameters
-/// * `val` A single value from the FFT output buffer of type [`Complex32`].
-fn complex_to_magnitude(val: &Complex32) -> f32 {
-    // calculates sqrt(re*re + im*im), i.e. magnitude of complex number
-    let sum = val.re * val.re + val.im * val.im;
-    let sqrt = libm::sqrtf(sum);
-    debug_assert!(!sqrt.is_nan(), "sqrt is NaN!");
-    sqrt
+fn fft_calc_frequency_resolution(sampling_rate: f32, samples_len: u32) -> f32 {
+    sampling_rate / samples_len as f32
 }
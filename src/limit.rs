@@ -25,6 +25,7 @@ SOFTWARE.
 
 use core::error::Error;
 use core::fmt::{Display, Formatter};
+use num_traits::Float;
 
 /// Can be used to specify a desired frequency limit.
 ///
@@ -34,29 +35,35 @@ use core::fmt::{Display, Formatter};
 ///
 /// Please note that due to frequency inaccuracies the FFT result may not contain
 /// a value for `1000Hz` but for `998.76Hz`!
-#[derive(Debug, Copy, Clone)]
-pub enum FrequencyLimit {
+///
+/// Two-sided spectra, such as the ones of [`crate::iq`], also support negative
+/// values down to the negative Nyquist frequency.
+///
+/// The values are `f64` for [`crate::double`], e.g. for fractional sampling
+/// rates or for frequencies that `f32` can't represent exactly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrequencyLimit<T = f32> {
     /// Interested in all frequencies. [0, sampling_rate/2] (Nyquist theorem).
     /// Semantically equivalent to "None" limit at all).
     All,
     /// Only interested in frequencies `Frequency <= x`. Limit is inclusive.
     /// Supported values are `0 <= x <= Nyquist-Frequency`.
-    Min(f32),
+    Min(T),
     /// Only interested in frequencies `x <= Frequency`. Limit is inclusive.
     /// Supported values are `0 <= x <= N`.
-    Max(f32),
+    Max(T),
     /// Only interested in frequencies `1000 <= f <= 6777` for example. Both values are inclusive.
     /// The first value of the tuple is equivalent to [`FrequencyLimit::Min`] and the latter
     /// equivalent to [`FrequencyLimit::Max`]. Furthermore, the first value must not be
     /// bigger than the second value.
-    Range(f32, f32),
+    Range(T, T),
 }
 
-impl FrequencyLimit {
+impl<T: Float> FrequencyLimit<T> {
     /// Returns the minimum value, if any.
     #[inline]
     #[must_use]
-    pub const fn maybe_min(&self) -> Option<f32> {
+    pub const fn maybe_min(&self) -> Option<T> {
         match self {
             Self::Min(min) => Some(*min),
             Self::Range(min, _) => Some(*min),
@@ -67,7 +74,7 @@ impl FrequencyLimit {
     /// Returns the maximum value, if any.
     #[inline]
     #[must_use]
-    pub const fn maybe_max(&self) -> Option<f32> {
+    pub const fn maybe_max(&self) -> Option<T> {
         match self {
             Self::Max(max) => Some(*max),
             Self::Range(_, max) => Some(*max),
@@ -79,7 +86,7 @@ impl FrequencyLimit {
     /// Unwrapped version of [`Self::maybe_min`].
     #[inline]
     #[must_use]
-    pub const fn min(&self) -> f32 {
+    pub const fn min(&self) -> T {
         self.maybe_min().expect("Must contain a value!")
     }
 
@@ -87,29 +94,54 @@ impl FrequencyLimit {
     /// Unwrapped version of [`Self::maybe_max`].
     #[inline]
     #[must_use]
-    pub const fn max(&self) -> f32 {
+    pub const fn max(&self) -> T {
         self.maybe_max().expect("Must contain a value!")
     }
 
+    /// Returns whether the frequency is within the limit.
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, frequency: T) -> bool {
+        self.maybe_min().is_none_or(|min| frequency >= min)
+            && self.maybe_max().is_none_or(|max| frequency <= max)
+    }
+
     /// Verifies that the frequency limit has sane values and takes the maximum possible
     /// frequency into account.
-    pub fn verify(&self, max_detectable_frequency: f32) -> Result<(), FrequencyLimitError> {
+    pub fn verify(&self, max_detectable_frequency: T) -> Result<(), FrequencyLimitError> {
+        self.verify_between(T::zero(), max_detectable_frequency)
+    }
+
+    /// Like [`Self::verify`] but for a two-sided spectrum, e.g. of complex
+    /// I/Q samples, where the frequencies range from the negative to the
+    /// positive maximum detectable frequency. Negative limits are valid.
+    pub fn verify_two_sided(&self, max_detectable_frequency: T) -> Result<(), FrequencyLimitError> {
+        self.verify_between(-max_detectable_frequency, max_detectable_frequency)
+    }
+
+    /// Verifies that all values are in the interval `[lowest; highest]`. The
+    /// values of the error are rounded to `f32`.
+    fn verify_between(&self, lowest: T, highest: T) -> Result<(), FrequencyLimitError> {
+        let to_f32 = |x: T| x.to_f32().expect("float is representable as f32");
         match self {
             Self::All => Ok(()),
             Self::Min(x) | Self::Max(x) => {
-                if *x < 0.0 {
-                    Err(FrequencyLimitError::ValueBelowMinimum(*x))
-                } else if *x > max_detectable_frequency {
-                    Err(FrequencyLimitError::ValueAboveNyquist(*x))
+                if *x < lowest {
+                    Err(FrequencyLimitError::ValueBelowMinimum(to_f32(*x)))
+                } else if *x > highest {
+                    Err(FrequencyLimitError::ValueAboveNyquist(to_f32(*x)))
                 } else {
                     Ok(())
                 }
             }
             Self::Range(min, max) => {
-                Self::Min(*min).verify(max_detectable_frequency)?;
-                Self::Max(*max).verify(max_detectable_frequency)?;
+                Self::Min(*min).verify_between(lowest, highest)?;
+                Self::Max(*max).verify_between(lowest, highest)?;
                 if min > max {
-                    Err(FrequencyLimitError::InvalidRange(*min, *max))
+                    Err(FrequencyLimitError::InvalidRange(
+                        to_f32(*min),
+                        to_f32(*max),
+                    ))
                 } else {
                     Ok(())
                 }
@@ -121,7 +153,8 @@ impl FrequencyLimit {
 /// Possible errors when creating a [`FrequencyLimit`]-object.
 #[derive(Debug)]
 pub enum FrequencyLimitError {
-    /// If the minimum value is below 0. Negative frequencies are not supported.
+    /// If the minimum value is below 0. Negative frequencies are only supported
+    /// by two-sided spectra, down to the negative Nyquist frequency.
     ValueBelowMinimum(f32),
     /// If the maximum value is above Nyquist frequency. Nyquist-Frequency is the maximum
     /// detectable frequency.
@@ -182,6 +215,21 @@ mod tests {
         let _ = FrequencyLimit::Range(0.0, -1.0).verify(0.0).unwrap_err();
     }
 
+    #[test]
+    fn test_two_sided() {
+        FrequencyLimit::Range(-50.0, -20.0)
+            .verify_two_sided(100.0)
+            .unwrap();
+        FrequencyLimit::Min(-100.0).verify_two_sided(100.0).unwrap();
+        FrequencyLimit::Max(-50.0).verify(100.0).unwrap_err();
+        FrequencyLimit::Min(-100.1)
+            .verify_two_sided(100.0)
+            .unwrap_err();
+        FrequencyLimit::Range(-20.0, -50.0)
+            .verify_two_sided(100.0)
+            .unwrap_err();
+    }
+
     #[test]
     fn test_ok() {
         FrequencyLimit::Min(50.0).verify(100.0).unwrap();
@@ -190,4 +238,14 @@ mod tests {
         FrequencyLimit::Range(50.0, 50.0).verify(100.0).unwrap();
         FrequencyLimit::Range(50.0, 70.0).verify(100.0).unwrap();
     }
+
+    #[test]
+    fn test_f64() {
+        // not representable as `f32`
+        let limit = FrequencyLimit::Max(16_777_217.0_f64);
+        assert!(limit.contains(16_777_217.0));
+        assert!(!limit.contains(16_777_217.5));
+        limit.verify(16_777_217.0).unwrap();
+        FrequencyLimit::Min(-1.0_f64).verify(100.0).unwrap_err();
+    }
 }
@@ -0,0 +1,343 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the Lomb–Scargle periodogram, the spectrum of unevenly sampled
+//! data, such as sensor values with jitter and gaps or astronomical
+//! observations. See [`lomb_scargle`].
+
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::spectrum::FrequencySpectrum;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::f64::consts::PI;
+use core::fmt::{Display, Formatter};
+
+/// Result of [`lomb_scargle`].
+#[derive(Debug)]
+pub struct LombScarglePeriodogram {
+    /// The normalized power of each frequency.
+    spectrum: FrequencySpectrum,
+    /// Estimated number of independent frequencies of the grid.
+    independent_frequencies: f32,
+}
+
+impl LombScarglePeriodogram {
+    /// Returns the normalized power of each frequency. The power is the
+    /// reduction of the variance by fitting a sine of the frequency, relative
+    /// to the variance of the data. For pure Gaussian noise, it is
+    /// exponentially distributed with a mean of `1`.
+    #[inline]
+    #[must_use]
+    pub const fn spectrum(&self) -> &FrequencySpectrum {
+        &self.spectrum
+    }
+
+    /// Returns the estimated number of independent frequencies of the grid,
+    /// i.e. `(max_frequency - min_frequency) * time_span`, but at least `1`
+    /// and at most the number of frequencies.
+    #[inline]
+    #[must_use]
+    pub const fn independent_frequencies(&self) -> f32 {
+        self.independent_frequencies
+    }
+
+    /// Returns the false-alarm probability of the highest peak, i.e. the
+    /// probability that pure Gaussian noise has a peak of at least the same
+    /// power anywhere in the grid. Small values, such as `0.01`, indicate a
+    /// significant periodic signal.
+    #[must_use]
+    pub fn false_alarm_probability(&self) -> f32 {
+        self.false_alarm_probability_of(self.spectrum.max().1.val())
+    }
+
+    /// Like [`Self::false_alarm_probability`] but for any power, e.g. of the
+    /// second highest peak.
+    ///
+    /// The estimate is `1 - (1 - e^(-power))^M`, where `M` is the number of
+    /// [`Self::independent_frequencies`] (Horne & Baliunas, 1986).
+    #[must_use]
+    pub fn false_alarm_probability_of(&self, power: f32) -> f32 {
+        let probability = libm::exp(-(power as f64));
+        // 1 - (1 - p)^M, without cancellation for small p
+        let fap = -libm::expm1(self.independent_frequencies as f64 * libm::log1p(-probability));
+        fap as f32
+    }
+}
+
+/// Calculates the normalized Lomb–Scargle periodogram of unevenly sampled
+/// data.
+///
+/// Unlike an FFT, it doesn't require a constant sampling rate. For each
+/// frequency of the grid, it fits a sine with a phase offset `τ`, that
+/// makes the sine and cosine terms orthogonal for the given timestamps
+/// (Scargle, 1982).
+///
+/// ## Parameters
+/// * `samples` Pairs of timestamps and values in any order. The timestamps
+///   are `f64`, as `f32` is too imprecise for Unix times or Julian dates.
+/// * `min_frequency` Lowest frequency of the grid in `1 / time unit`, e.g.
+///   Hertz for timestamps in seconds. Must be positive.
+/// * `max_frequency` Highest frequency of the grid. A common choice is the
+///   average Nyquist frequency `N / (2 * time_span)`, but the periodogram
+///   can also detect higher frequencies.
+/// * `frequency_resolution` Distance between two frequencies of the grid.
+///   A common choice is `1 / (5 * time_span)`.
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::lomb_scargle::lomb_scargle;
+///
+/// // a sensor with 0.2 Hz that reports roughly every second
+/// let samples = (0..100)
+///     .map(|i| {
+///         let t = i as f64 + 0.3 * ((i * 7) % 5) as f64;
+///         (t, (2.0 * core::f64::consts::PI * 0.2 * t).sin() as f32)
+///     })
+///     .collect::<Vec<_>>();
+/// let periodogram = lomb_scargle(&samples, 0.01, 0.5, 0.002).unwrap();
+/// let (frequency, _) = periodogram.spectrum().max();
+/// assert!((frequency.val() - 0.2).abs() < 0.005);
+/// assert!(periodogram.false_alarm_probability() < 0.01);
+/// ```
+pub fn lomb_scargle(
+    samples: &[(f64, f32)],
+    min_frequency: f32,
+    max_frequency: f32,
+    frequency_resolution: f32,
+) -> Result<LombScarglePeriodogram, LombScargleError> {
+    if samples.len() < 3 {
+        return Err(LombScargleError::TooFewSamples);
+    }
+    if samples
+        .iter()
+        .any(|(t, val)| !t.is_finite() || !val.is_finite())
+    {
+        return Err(LombScargleError::InvalidSamples);
+    }
+    let is_positive = |x: f32| x.is_finite() && x > 0.0;
+    if !is_positive(min_frequency)
+        || !is_positive(frequency_resolution)
+        || !max_frequency.is_finite()
+        || max_frequency < min_frequency + frequency_resolution
+    {
+        return Err(LombScargleError::InvalidFrequencyGrid);
+    }
+
+    // relative to the first timestamp for precision
+    let t0 = samples
+        .iter()
+        .map(|(t, _)| *t)
+        .fold(f64::INFINITY, f64::min);
+    let t_max = samples
+        .iter()
+        .map(|(t, _)| *t)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let len = samples.len() as f64;
+    let mean = samples.iter().map(|(_, val)| *val as f64).sum::<f64>() / len;
+    let variance = samples
+        .iter()
+        .map(|(_, val)| (*val as f64 - mean) * (*val as f64 - mean))
+        .sum::<f64>()
+        / (len - 1.0);
+    if variance <= 0.0 {
+        return Err(LombScargleError::ZeroVariance);
+    }
+    let samples = samples
+        .iter()
+        .map(|(t, val)| (t - t0, *val as f64 - mean))
+        .collect::<Vec<_>>();
+
+    // The quotient may end up slightly below an integer due to rounding,
+    // which must not drop the last frequency of the grid.
+    let steps = (max_frequency - min_frequency) / frequency_resolution;
+    let tolerance = 4.0 * f32::EPSILON * max_frequency / frequency_resolution;
+    let steps = if (steps - libm::roundf(steps)).abs() <= tolerance {
+        libm::roundf(steps)
+    } else {
+        libm::floorf(steps)
+    };
+    let count = (steps as usize + 1).max(2);
+    let data = (0..count)
+        .map(|i| {
+            let fr = min_frequency + i as f32 * frequency_resolution;
+            let omega = 2.0 * PI * fr as f64;
+
+            // tan(2ωτ) = sum(sin(2ωt)) / sum(cos(2ωt))
+            let (sin_sum, cos_sum) = samples.iter().fold((0.0, 0.0), |(s, c), (t, _)| {
+                (
+                    s + libm::sin(2.0 * omega * t),
+                    c + libm::cos(2.0 * omega * t),
+                )
+            });
+            let tau = libm::atan2(sin_sum, cos_sum) / (2.0 * omega);
+
+            let (mut yc, mut ys, mut cc, mut ss) = (0.0, 0.0, 0.0, 0.0);
+            for (t, val) in &samples {
+                let (sin, cos) = (libm::sin(omega * (t - tau)), libm::cos(omega * (t - tau)));
+                yc += val * cos;
+                ys += val * sin;
+                cc += cos * cos;
+                ss += sin * sin;
+            }
+            let ratio = |num: f64, den: f64| if den > 0.0 { num * num / den } else { 0.0 };
+            let power = (ratio(yc, cc) + ratio(ys, ss)) / (2.0 * variance);
+            (Frequency::from(fr), FrequencyValue::from(power as f32))
+        })
+        .collect::<Vec<_>>();
+
+    let time_span = (t_max - t0) as f32;
+    let independent_frequencies =
+        ((max_frequency - min_frequency) * time_span).clamp(1.0, count as f32);
+    let mut working_buffer = vec![(0.0.into(), 0.0.into()); data.len()];
+    Ok(LombScarglePeriodogram {
+        spectrum: FrequencySpectrum::new(
+            data,
+            frequency_resolution,
+            samples.len() as u32,
+            &mut working_buffer,
+        ),
+        independent_frequencies,
+    })
+}
+
+/// Errors of [`lomb_scargle`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LombScargleError {
+    /// At least three samples are required.
+    TooFewSamples,
+    /// Timestamps or values are NaN or infinite.
+    InvalidSamples,
+    /// The frequency grid is invalid or has fewer than two frequencies.
+    InvalidFrequencyGrid,
+    /// All values are equal, so there is no periodic signal.
+    ZeroVariance,
+}
+
+impl Display for LombScargleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooFewSamples => write!(f, "Too few samples!"),
+            Self::InvalidSamples => write!(f, "NaN or infinite samples are not supported!"),
+            Self::InvalidFrequencyGrid => write!(f, "Invalid frequency grid!"),
+            Self::ZeroVariance => write!(f, "The values have no variance!"),
+        }
+    }
+}
+
+impl Error for LombScargleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::sine::{gaussian_noise, random};
+
+    #[test]
+    fn test_irregular_sine_with_gaps() {
+        // Unix timestamps with jitter and a gap of 30 minutes
+        let t0 = 1_700_000_000.0;
+        let times = random(300, 7)
+            .iter()
+            .enumerate()
+            .map(|(i, jitter)| t0 + i as f64 * 10.0 + jitter * 8.0)
+            .filter(|t| !(t0 + 1000.0..t0 + 2800.0).contains(t))
+            .collect::<Vec<_>>();
+        // period of 5 minutes
+        let samples = times
+            .iter()
+            .zip(gaussian_noise(times.len(), 3))
+            .map(|(t, noise)| {
+                (
+                    *t,
+                    2.0 * libm::sin(2.0 * PI * (t - t0) / 300.0) as f32 + noise,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let periodogram = lomb_scargle(&samples, 0.0001, 0.05, 0.00005).unwrap();
+        let (fr, power) = periodogram.spectrum().max();
+        assert!((fr.val() - 1.0 / 300.0).abs() < 0.0001, "{fr}");
+        assert!(power.val() > 20.0, "{power}");
+        assert!(periodogram.false_alarm_probability() < 0.000_001);
+        assert_eq!(periodogram.spectrum().data().len(), 999);
+        assert!(periodogram.independent_frequencies() > 100.0);
+    }
+
+    /// Pure noise must not result in significant peaks.
+    #[test]
+    fn test_noise() {
+        let samples = random(200, 11)
+            .iter()
+            .enumerate()
+            .map(|(i, jitter)| i as f64 + jitter)
+            .zip(gaussian_noise(200, 5))
+            .collect::<Vec<_>>();
+        let periodogram = lomb_scargle(&samples, 0.01, 0.5, 0.001).unwrap();
+        let mean = periodogram.spectrum().average().val();
+        assert!((mean - 1.0).abs() < 0.2, "{mean}");
+        assert!(periodogram.false_alarm_probability() > 0.05);
+        assert!((periodogram.false_alarm_probability_of(0.0) - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let samples = [(0.0, 1.0), (1.0, 2.0), (2.5, 0.0)];
+        lomb_scargle(&samples, 0.1, 0.5, 0.1).unwrap();
+        assert_eq!(
+            lomb_scargle(&samples[..2], 0.1, 0.5, 0.1).unwrap_err(),
+            LombScargleError::TooFewSamples
+        );
+        assert_eq!(
+            lomb_scargle(&[(0.0, 1.0), (f64::NAN, 2.0), (2.5, 0.0)], 0.1, 0.5, 0.1).unwrap_err(),
+            LombScargleError::InvalidSamples
+        );
+        assert_eq!(
+            lomb_scargle(&samples, 0.0, 0.5, 0.1).unwrap_err(),
+            LombScargleError::InvalidFrequencyGrid
+        );
+        assert_eq!(
+            lomb_scargle(&samples, 0.1, 0.15, 0.1).unwrap_err(),
+            LombScargleError::InvalidFrequencyGrid
+        );
+        assert_eq!(
+            lomb_scargle(&[(0.0, 1.0), (1.0, 1.0), (2.5, 1.0)], 0.1, 0.5, 0.1).unwrap_err(),
+            LombScargleError::ZeroVariance
+        );
+    }
+
+    /// The grid includes the upper bound, even if the quotient of the range
+    /// and the resolution is rounded down.
+    #[test]
+    fn test_frequency_grid() {
+        let samples = [(0.0, 1.0), (0.01, 2.0), (0.025, 0.0)];
+        let (min, res) = (49.544014, 4.4949656);
+        let periodogram = lomb_scargle(&samples, min, min + res, res).unwrap();
+        let data = periodogram.spectrum().data();
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[1].0.val(), min + res);
+
+        let periodogram = lomb_scargle(&samples, 0.1, 0.75, 0.1).unwrap();
+        assert_eq!(periodogram.spectrum().data().len(), 7);
+        let periodogram = lomb_scargle(&samples, 0.1, 0.7, 0.1).unwrap();
+        assert_eq!(periodogram.spectrum().data().len(), 7);
+    }
+}
@@ -0,0 +1,365 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the spectrum analysis of multi-channel audio, such as stereo
+//! recordings. See [`interleaved_samples_fft_to_spectra`] and
+//! [`planar_samples_fft_to_spectra`].
+
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::{Complex32, DefaultFftBackend, Fft, FftBackend};
+use crate::limit::FrequencyLimit;
+use crate::output::SpectrumOutput;
+use crate::scaling::SpectrumScalingFunction;
+use crate::spectrum::FrequencySpectrum;
+use alloc::vec::Vec;
+
+/// Describes which spectra are calculated from the channels.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ChannelMix {
+    /// One spectrum per channel, in the order of the channels. This is the
+    /// default.
+    #[default]
+    Separate,
+    /// Two spectra of a stereo signal: the mid signal `(L + R) / 2` and the
+    /// side signal `(L - R) / 2`. Requires exactly two channels.
+    MidSide,
+    /// A single spectrum of the sum of all channels.
+    Sum,
+}
+
+/// Like [`crate::samples_fft_to_spectrum`] but for interleaved multi-channel
+/// samples, e.g. `[L0, R0, L1, R1, ...]` for stereo.
+///
+/// ## Parameters
+/// * `samples` Interleaved samples. The number of frames, i.e. samples per
+///   channel, must be a power of two.
+/// * `channels` Number of channels, e.g. `2` for stereo.
+/// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+/// * `frequency_limit` The [`FrequencyLimit`].
+/// * `mix` See [`ChannelMix`].
+/// * `scaling_fn` See [`crate::samples_fft_to_spectrum`].
+///
+/// ## Return value
+/// The spectra as described by [`ChannelMix`].
+///
+/// ## Examples
+/// ```rust
+/// use spectrum_analyzer::multichannel::{interleaved_samples_fft_to_spectra, ChannelMix};
+/// use spectrum_analyzer::FrequencyLimit;
+/// // stereo samples from the audio source
+/// let samples = [0.0, 0.0, 1.1, -1.1, 5.5, 0.5, -5.5, 0.3];
+/// let spectra = interleaved_samples_fft_to_spectra(
+///     &samples,
+///     2,
+///     44100.0,
+///     FrequencyLimit::All,
+///     ChannelMix::Separate,
+///     None,
+/// )
+/// .unwrap();
+/// assert_eq!(spectra.len(), 2);
+/// ```
+pub fn interleaved_samples_fft_to_spectra(
+    samples: &[f32],
+    channels: usize,
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    mix: ChannelMix,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<Vec<FrequencySpectrum>, SpectrumAnalyzerError> {
+    interleaved_samples_fft_to_spectra_with_backend::<DefaultFftBackend>(
+        samples,
+        channels,
+        sampling_rate,
+        frequency_limit,
+        mix,
+        scaling_fn,
+    )
+}
+
+/// Like [`interleaved_samples_fft_to_spectra`] but calculates the FFTs with
+/// the given [`FftBackend`] instead of the [`DefaultFftBackend`].
+pub fn interleaved_samples_fft_to_spectra_with_backend<B: FftBackend>(
+    samples: &[f32],
+    channels: usize,
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    mix: ChannelMix,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<Vec<FrequencySpectrum>, SpectrumAnalyzerError> {
+    if channels == 0 || samples.len() % channels != 0 {
+        return Err(SpectrumAnalyzerError::InvalidChannelLayout);
+    }
+    multichannel_fft_to_spectra::<B>(
+        channels,
+        samples.len() / channels,
+        |channel, frame| samples[frame * channels + channel],
+        sampling_rate,
+        frequency_limit,
+        mix,
+        scaling_fn,
+    )
+}
+
+/// Like [`interleaved_samples_fft_to_spectra`] but for planar samples, i.e.
+/// one slice per channel. All channels must have the same length.
+pub fn planar_samples_fft_to_spectra(
+    channels: &[&[f32]],
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    mix: ChannelMix,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<Vec<FrequencySpectrum>, SpectrumAnalyzerError> {
+    planar_samples_fft_to_spectra_with_backend::<DefaultFftBackend>(
+        channels,
+        sampling_rate,
+        frequency_limit,
+        mix,
+        scaling_fn,
+    )
+}
+
+/// Like [`planar_samples_fft_to_spectra`] but calculates the FFTs with the
+/// given [`FftBackend`] instead of the [`DefaultFftBackend`].
+pub fn planar_samples_fft_to_spectra_with_backend<B: FftBackend>(
+    channels: &[&[f32]],
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    mix: ChannelMix,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<Vec<FrequencySpectrum>, SpectrumAnalyzerError> {
+    let frames = channels.first().map_or(0, |channel| channel.len());
+    if channels.is_empty() || channels.iter().any(|channel| channel.len() != frames) {
+        return Err(SpectrumAnalyzerError::InvalidChannelLayout);
+    }
+    multichannel_fft_to_spectra::<B>(
+        channels.len(),
+        frames,
+        |channel, frame| channels[channel][frame],
+        sampling_rate,
+        frequency_limit,
+        mix,
+        scaling_fn,
+    )
+}
+
+/// Common implementation for all channel layouts. The input is verified
+/// once and all spectra share the same FFT buffers.
+///
+/// ## Parameters
+/// * `channels` Number of channels.
+/// * `frames` Number of samples per channel.
+/// * `sample_fn` Returns the sample of a channel at a frame index.
+fn multichannel_fft_to_spectra<B: FftBackend>(
+    channels: usize,
+    frames: usize,
+    sample_fn: impl Fn(usize /* channel */, usize /* frame */) -> f32,
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    mix: ChannelMix,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<Vec<FrequencySpectrum>, SpectrumAnalyzerError> {
+    if frames < 2 {
+        return Err(SpectrumAnalyzerError::TooFewSamples);
+    }
+    if !frames.is_power_of_two() {
+        return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+    }
+    if frames > B::MAX_LEN {
+        return Err(SpectrumAnalyzerError::TooManySamples);
+    }
+    if mix == ChannelMix::MidSide && channels != 2 {
+        return Err(SpectrumAnalyzerError::InvalidChannelLayout);
+    }
+    let samples = || (0..channels).flat_map(|c| (0..frames).map(move |f| (c, f)));
+    if samples().any(|(c, f)| sample_fn(c, f).is_nan()) {
+        return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+    }
+    if samples().any(|(c, f)| sample_fn(c, f).is_infinite()) {
+        return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+    }
+    crate::verify_sampling_rate(sampling_rate)?;
+    frequency_limit
+        .verify(sampling_rate / 2.0)
+        .map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+
+    let spectra_count = match mix {
+        ChannelMix::Separate => channels,
+        ChannelMix::MidSide => 2,
+        ChannelMix::Sum => 1,
+    };
+
+    // shared by all spectra
+    let mut fft = Fft::<B>::default();
+    let mut fft_buffer = vec![0.0; frames];
+    let mut fft_result = Vec::with_capacity(frames / 2 + 1);
+
+    (0..spectra_count)
+        .map(|spectrum_index| {
+            for (frame, sample) in fft_buffer.iter_mut().enumerate() {
+                *sample = match mix {
+                    ChannelMix::Separate => sample_fn(spectrum_index, frame),
+                    ChannelMix::MidSide => {
+                        let (l, r) = (sample_fn(0, frame), sample_fn(1, frame));
+                        if spectrum_index == 0 {
+                            (l + r) / 2.0
+                        } else {
+                            (l - r) / 2.0
+                        }
+                    }
+                    ChannelMix::Sum => (0..channels).map(|c| sample_fn(c, frame)).sum(),
+                };
+            }
+
+            let fft_res = fft.calc_in_place(&mut fft_buffer);
+            // `microfft::real` packs the real value of the Nyquist frequency
+            // inside the imaginary part of the DC component.
+            let nyquist = Complex32::new(fft_res[0].im, 0.0);
+            fft_res[0].im = 0.0;
+            fft_result.clear();
+            fft_result.extend_from_slice(fft_res);
+            fft_result.push(nyquist);
+
+            crate::fft_result_to_spectrum(
+                frames,
+                &fft_result,
+                sampling_rate,
+                frequency_limit,
+                SpectrumOutput::Magnitude,
+                scaling_fn,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::Microfft;
+    use crate::samples_fft_to_spectrum;
+
+    #[test]
+    fn test_layouts_match_single_channel() {
+        let left = (0..64)
+            .map(|i| libm::sinf(i as f32 * 0.4))
+            .collect::<Vec<_>>();
+        let right = (0..64)
+            .map(|i| libm::cosf(i as f32 * 0.1))
+            .collect::<Vec<_>>();
+        let interleaved = left
+            .iter()
+            .zip(&right)
+            .flat_map(|(l, r)| [*l, *r])
+            .collect::<Vec<_>>();
+
+        let planar = planar_samples_fft_to_spectra(
+            &[&left, &right],
+            64.0,
+            FrequencyLimit::All,
+            ChannelMix::Separate,
+            None,
+        )
+        .unwrap();
+        let interleaved = interleaved_samples_fft_to_spectra(
+            &interleaved,
+            2,
+            64.0,
+            FrequencyLimit::All,
+            ChannelMix::Separate,
+            None,
+        )
+        .unwrap();
+
+        for (channel, samples) in [&left, &right].into_iter().enumerate() {
+            let expected = samples_fft_to_spectrum(samples, 64, FrequencyLimit::All, None).unwrap();
+            assert_eq!(planar[channel].data(), expected.data());
+            assert_eq!(interleaved[channel].data(), expected.data());
+        }
+
+        let sum = planar_samples_fft_to_spectra(
+            &[&left, &right],
+            64.0,
+            FrequencyLimit::All,
+            ChannelMix::Sum,
+            None,
+        )
+        .unwrap();
+        let summed = left
+            .iter()
+            .zip(&right)
+            .map(|(l, r)| l + r)
+            .collect::<Vec<_>>();
+        let expected = samples_fft_to_spectrum(&summed, 64, FrequencyLimit::All, None).unwrap();
+        assert_eq!(sum.len(), 1);
+        assert_eq!(sum[0].data(), expected.data());
+    }
+
+    #[test]
+    fn test_invalid_layout() {
+        let samples = [0.0; 12];
+        let limit = FrequencyLimit::All;
+        let mix = ChannelMix::Separate;
+        assert!(matches!(
+            interleaved_samples_fft_to_spectra(&samples, 0, 8.0, limit, mix, None),
+            Err(SpectrumAnalyzerError::InvalidChannelLayout)
+        ));
+        assert!(matches!(
+            interleaved_samples_fft_to_spectra(&samples, 5, 8.0, limit, mix, None),
+            Err(SpectrumAnalyzerError::InvalidChannelLayout)
+        ));
+        assert!(matches!(
+            interleaved_samples_fft_to_spectra(&samples, 2, 8.0, limit, mix, None),
+            Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo)
+        ));
+        assert!(matches!(
+            interleaved_samples_fft_to_spectra(&samples, 3, 8.0, limit, ChannelMix::MidSide, None),
+            Err(SpectrumAnalyzerError::InvalidChannelLayout)
+        ));
+        assert!(matches!(
+            planar_samples_fft_to_spectra(&[&samples[..4], &samples[..8]], 8.0, limit, mix, None),
+            Err(SpectrumAnalyzerError::InvalidChannelLayout)
+        ));
+        assert!(matches!(
+            planar_samples_fft_to_spectra(&[], 8.0, limit, mix, None),
+            Err(SpectrumAnalyzerError::InvalidChannelLayout)
+        ));
+    }
+
+    #[test]
+    fn test_backend() {
+        let samples = (0..256)
+            .map(|i| libm::sinf(i as f32 * 0.3) + libm::cosf(i as f32 * 0.05))
+            .collect::<Vec<_>>();
+        let limit = FrequencyLimit::All;
+        let mix = ChannelMix::MidSide;
+        let expected = interleaved_samples_fft_to_spectra(&samples, 2, 64.0, limit, mix, None);
+        let spectra = interleaved_samples_fft_to_spectra_with_backend::<Microfft>(
+            &samples, 2, 64.0, limit, mix, None,
+        );
+        for (spectrum, expected) in spectra.unwrap().iter().zip(&expected.unwrap()) {
+            for ((_, val), (_, expected_val)) in spectrum.data().iter().zip(expected.data()) {
+                float_cmp::assert_approx_eq!(f32, val.val(), expected_val.val(), epsilon = 0.0001);
+            }
+        }
+    }
+}
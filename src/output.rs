@@ -0,0 +1,186 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the enum [`SpectrumOutput`].
+
+use crate::error::SpectrumAnalyzerError;
+use num_complex::Complex;
+use num_traits::Float;
+
+/// Describes what each frequency value of a [`crate::FrequencySpectrum`]
+/// represents. `X` is the complex FFT result of a frequency bin, `N` the
+/// number of samples, and `fs` the sampling rate.
+///
+/// The one-sided densities double all values except the ones of the DC
+/// component and the Nyquist frequency, as the energy of the mirrored
+/// negative frequencies is folded into them.
+///
+/// The window energy is `f64` for [`crate::double`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SpectrumOutput<T = f32> {
+    /// Linear magnitude `|X|`, i.e. `sqrt(re² + im²)`, without any
+    /// normalization. This is the default.
+    #[default]
+    Magnitude,
+    /// Power `|X|²` without any normalization.
+    Power,
+    /// One-sided power spectral density (PSD) in `unit²/Hz`, i.e.
+    /// `2 * |X|² / (fs * window_energy)`.
+    PowerSpectralDensity {
+        /// Sum of the squared window coefficients, i.e. `N` if no window was
+        /// applied or roughly `3/8 * N` for a Hann window.
+        window_energy: T,
+    },
+    /// One-sided amplitude spectral density (ASD) in `unit/sqrt(Hz)`, i.e.
+    /// the square root of [`Self::PowerSpectralDensity`].
+    AmplitudeSpectralDensity {
+        /// See [`Self::PowerSpectralDensity`].
+        window_energy: T,
+    },
+}
+
+impl<T: Float> SpectrumOutput<T> {
+    /// Verifies that the output has sane parameters.
+    pub(crate) fn verify(&self) -> Result<(), SpectrumAnalyzerError> {
+        match self {
+            Self::PowerSpectralDensity { window_energy }
+            | Self::AmplitudeSpectralDensity { window_energy } => {
+                if window_energy.is_finite() && *window_energy > T::zero() {
+                    Ok(())
+                } else {
+                    Err(SpectrumAnalyzerError::InvalidWindowEnergy(
+                        window_energy
+                            .to_f32()
+                            .expect("float is representable as f32"),
+                    ))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Converts a single value of the FFT result into the value of the
+    /// spectrum.
+    ///
+    /// ## Parameters
+    /// * `val` A single value from the FFT output buffer.
+    /// * `fft_index` Index of `val` in the FFT output buffer.
+    /// * `samples_len` Number of samples put into the FFT.
+    /// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+    #[inline]
+    pub(crate) fn convert(
+        &self,
+        val: &Complex<T>,
+        fft_index: usize,
+        samples_len: usize,
+        sampling_rate: T,
+    ) -> T {
+        self.convert_with_factor(val, one_sided_factor(fft_index, samples_len), sampling_rate)
+    }
+
+    /// Like [`Self::convert`] but for a two-sided spectrum, i.e. the
+    /// densities are not doubled as the negative frequencies are part of the
+    /// spectrum.
+    #[inline]
+    pub(crate) fn convert_two_sided(&self, val: &Complex<T>, sampling_rate: T) -> T {
+        self.convert_with_factor(val, T::one(), sampling_rate)
+    }
+
+    /// Common implementation of [`Self::convert`] and
+    /// [`Self::convert_two_sided`] with the factor of the densities.
+    #[inline]
+    fn convert_with_factor(&self, val: &Complex<T>, density_factor: T, sampling_rate: T) -> T {
+        let power = val.re * val.re + val.im * val.im;
+        let psd = |window_energy: T| density_factor * power / (sampling_rate * window_energy);
+        match self {
+            Self::Magnitude => {
+                let sqrt = power.sqrt();
+                debug_assert!(!sqrt.is_nan(), "sqrt is NaN!");
+                sqrt
+            }
+            Self::Power => power,
+            Self::PowerSpectralDensity { window_energy } => psd(*window_energy),
+            Self::AmplitudeSpectralDensity { window_energy } => psd(*window_energy).sqrt(),
+        }
+    }
+}
+
+/// Returns the factor that folds the energy of the mirrored negative
+/// frequencies into a bin of a one-sided density. The DC component and the
+/// Nyquist frequency have no mirrored counterpart.
+#[inline]
+fn one_sided_factor<T: Float>(fft_index: usize, samples_len: usize) -> T {
+    let is_dc_or_nyquist = fft_index == 0 || fft_index == samples_len / 2;
+    if is_dc_or_nyquist {
+        T::one()
+    } else {
+        crate::constant(2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::Complex32;
+
+    #[test]
+    fn test_convert() {
+        let val = Complex32::new(3.0, 4.0);
+        assert_eq!(SpectrumOutput::Magnitude.convert(&val, 1, 8, 8.0), 5.0);
+        assert_eq!(SpectrumOutput::Power.convert(&val, 1, 8, 8.0), 25.0);
+
+        let psd = SpectrumOutput::PowerSpectralDensity { window_energy: 5.0 };
+        assert_eq!(psd.convert(&val, 0, 8, 10.0), 0.5, "DC must not be doubled");
+        assert_eq!(psd.convert(&val, 1, 8, 10.0), 1.0);
+        assert_eq!(
+            psd.convert(&val, 4, 8, 10.0),
+            0.5,
+            "Nyquist must not be doubled"
+        );
+        assert_eq!(psd.convert_two_sided(&val, 10.0), 0.5);
+
+        let asd = SpectrumOutput::AmplitudeSpectralDensity { window_energy: 5.0 };
+        assert_eq!(asd.convert(&val, 1, 8, 10.0), 1.0);
+
+        let val = num_complex::Complex64::new(3.0, 4.0);
+        let psd = SpectrumOutput::PowerSpectralDensity { window_energy: 5.0 };
+        assert_eq!(psd.convert(&val, 1, 8, 10.0), 1.0_f64);
+        assert_eq!(psd.convert(&val, 4, 8, 10.0), 0.5_f64);
+    }
+
+    #[test]
+    fn test_verify() {
+        SpectrumOutput::<f32>::Power.verify().unwrap();
+        SpectrumOutput::PowerSpectralDensity { window_energy: 1.0 }
+            .verify()
+            .unwrap();
+        SpectrumOutput::PowerSpectralDensity { window_energy: 0.0 }
+            .verify()
+            .unwrap_err();
+        SpectrumOutput::AmplitudeSpectralDensity {
+            window_energy: f32::NAN,
+        }
+        .verify()
+        .unwrap_err();
+    }
+}
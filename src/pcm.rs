@@ -0,0 +1,345 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+//! Module for integer and fixed-point samples, such as 16 bit PCM or Q15.
+//!
+//! All formats are normalized to `[-1.0; 1.0)`, i.e. the most negative value
+//! of a format corresponds to `-1.0`. This way, a full scale sine results in
+//! `0 dBFS` with [`crate::scaling::Decibel::dbfs`] and a `full_scale` of
+//! `1.0`, independent of the format.
+//!
+//! [`samples_fft_to_spectrum`] converts the samples straight into the buffer
+//! of the FFT, without an intermediate `Vec` of `f32` samples.
+
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::{DefaultFftBackend, Fft, FftBackend};
+use crate::scaling::SpectrumScalingFunction;
+use crate::{FrequencyLimit, FrequencySpectrum, SpectrumOutput};
+
+/// A sample of an integer or fixed-point format that can be normalized to
+/// `[-1.0; 1.0)`.
+pub trait PcmSample: Copy {
+    /// Returns the normalized value of the sample.
+    fn to_f32(self) -> f32;
+}
+
+impl PcmSample for i8 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        f32::from(self) / 128.0
+    }
+}
+
+impl PcmSample for i16 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        f32::from(self) / 32768.0
+    }
+}
+
+impl PcmSample for i32 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self as f32 / 2_147_483_648.0
+    }
+}
+
+/// Signed fixed-point number in Q15 format, i.e. with 15 fractional bits.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Q15(pub i16);
+
+impl PcmSample for Q15 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self.0.to_f32()
+    }
+}
+
+/// Signed fixed-point number in Q31 format, i.e. with 31 fractional bits.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct Q31(pub i32);
+
+impl PcmSample for Q31 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self.0.to_f32()
+    }
+}
+
+/// Packed signed 24 bit sample in little-endian byte order, as in WAV files.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct I24(pub [u8; 3]);
+
+impl I24 {
+    /// Creates a sample from the lower 24 bits of `value`.
+    #[must_use]
+    pub const fn from_i32(value: i32) -> Self {
+        let [b0, b1, b2, _] = value.to_le_bytes();
+        Self([b0, b1, b2])
+    }
+
+    /// Returns the sign-extended value of the sample.
+    #[must_use]
+    pub const fn value(self) -> i32 {
+        let [b0, b1, b2] = self.0;
+        i32::from_le_bytes([0, b0, b1, b2]) >> 8
+    }
+
+    /// Interprets a buffer of packed little-endian 24 bit samples without
+    /// copying them. Returns `None` if the length of the buffer is not a
+    /// multiple of three.
+    #[must_use]
+    pub const fn slice_from_le_bytes(bytes: &[u8]) -> Option<&[Self]> {
+        if bytes.len() % 3 != 0 {
+            return None;
+        }
+        // SAFETY: `I24` is `repr(transparent)` over `[u8; 3]`, so it has the
+        // size 3 and the alignment 1 of `u8`. The length of the buffer is a
+        // multiple of three and the lifetime of the result is bound to the
+        // buffer.
+        Some(unsafe { core::slice::from_raw_parts(bytes.as_ptr().cast::<Self>(), bytes.len() / 3) })
+    }
+}
+
+impl PcmSample for I24 {
+    #[inline]
+    fn to_f32(self) -> f32 {
+        self.value() as f32 / 8_388_608.0
+    }
+}
+
+/// Normalizes the samples into an existing buffer, e.g. for
+/// [`crate::streaming::StreamingAnalyzer::push`]. Both must have the same
+/// length.
+///
+/// ## Panics
+/// If `samples` and `buffer` have different lengths.
+pub fn normalize_into<S: PcmSample>(samples: &[S], buffer: &mut [f32]) {
+    assert_eq!(samples.len(), buffer.len(), "lengths must be equal");
+    for (sample, dst) in samples.iter().zip(buffer) {
+        *dst = sample.to_f32();
+    }
+}
+
+/// Like [`crate::samples_fft_to_spectrum_with_output`] but for integer or
+/// fixed-point samples. The samples are normalized and windowed while they
+/// are copied into the buffer of the FFT.
+///
+/// ## Parameters
+/// * `samples` The samples. The length must be a power of two and not
+///   bigger than 32768, unless the `large-fft` or the `rustfft` feature is
+///   enabled.
+/// * `window` Optional coefficients of a window function with the same
+///   length as the samples, e.g. `hann_window(&[1.0; 4096])`. Calculate
+///   them once and reuse them for all blocks.
+/// * `sampling_rate` The used sampling rate, e.g. `44100.0 [Hz]`.
+/// * `frequency_limit` The [`FrequencyLimit`].
+/// * `output` See [`SpectrumOutput`].
+/// * `scaling_fn` See [`SpectrumScalingFunction`].
+///
+/// ## Examples
+/// ```rust
+/// use spectrum_analyzer::pcm::{samples_fft_to_spectrum, I24};
+/// use spectrum_analyzer::scaling::Decibel;
+/// use spectrum_analyzer::windows::hann_window;
+/// use spectrum_analyzer::{FrequencyLimit, SpectrumOutput};
+/// // 24 bit samples from a WAV file
+/// let bytes = [0x00, 0x00, 0x80, 0xff, 0xff, 0x7f, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00];
+/// let samples = I24::slice_from_le_bytes(&bytes).unwrap();
+/// let window = hann_window(&[1.0; 4]);
+/// let dbfs = Decibel::dbfs(1.0, 0.5);
+/// let spectrum = samples_fft_to_spectrum(
+///     samples,
+///     Some(&window),
+///     48000.0,
+///     FrequencyLimit::All,
+///     SpectrumOutput::Magnitude,
+///     Some(&move |val, stats| dbfs.scale(val, stats)),
+/// )
+/// .unwrap();
+/// ```
+pub fn samples_fft_to_spectrum<S: PcmSample>(
+    samples: &[S],
+    window: Option<&[f32]>,
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    output: SpectrumOutput,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    samples_fft_to_spectrum_with_backend::<DefaultFftBackend, S>(
+        samples,
+        window,
+        sampling_rate,
+        frequency_limit,
+        output,
+        scaling_fn,
+    )
+}
+
+/// Like [`samples_fft_to_spectrum`] but calculates the FFT with the given
+/// [`FftBackend`] instead of the [`DefaultFftBackend`].
+pub fn samples_fft_to_spectrum_with_backend<B: FftBackend, S: PcmSample>(
+    samples: &[S],
+    window: Option<&[f32]>,
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    output: SpectrumOutput,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    if samples.len() < 2 {
+        return Err(SpectrumAnalyzerError::TooFewSamples);
+    }
+    if !samples.len().is_power_of_two() {
+        return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+    }
+    if samples.len() > B::MAX_LEN {
+        return Err(SpectrumAnalyzerError::TooManySamples);
+    }
+    if let Some(window) = window {
+        if window.len() != samples.len() {
+            return Err(SpectrumAnalyzerError::InvalidWindowLength(window.len()));
+        }
+        if window.iter().any(|x| x.is_nan()) {
+            return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+        }
+        if window.iter().any(|x| x.is_infinite()) {
+            return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+        }
+    }
+    crate::verify_sampling_rate(sampling_rate)?;
+    frequency_limit
+        .verify(sampling_rate / 2.0)
+        .map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+    output.verify()?;
+
+    let normalized = samples.iter().map(|sample| sample.to_f32());
+    let mut fft = Fft::<B>::default();
+    let fft_res = match window {
+        Some(window) => fft.calc_iter(normalized.zip(window).map(|(x, w)| x * w)),
+        None => fft.calc_iter(normalized),
+    };
+    crate::fft_result_to_spectrum(
+        samples.len(),
+        &fft_res,
+        sampling_rate,
+        frequency_limit,
+        output,
+        scaling_fn,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::windows::hann_window;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_normalization() {
+        assert_eq!(i8::MIN.to_f32(), -1.0);
+        assert_eq!(64_i8.to_f32(), 0.5);
+        assert_eq!(i16::MIN.to_f32(), -1.0);
+        assert_eq!((-16384_i16).to_f32(), -0.5);
+        assert_eq!(i32::MIN.to_f32(), -1.0);
+        assert_eq!((1_i32 << 30).to_f32(), 0.5);
+        assert_eq!(Q15(i16::MIN).to_f32(), -1.0);
+        assert_eq!(Q31(1 << 29).to_f32(), 0.25);
+
+        assert_eq!(I24::from_i32(-8_388_608).to_f32(), -1.0);
+        assert_eq!(I24::from_i32(4_194_304).to_f32(), 0.5);
+        assert_eq!(I24::from_i32(-2).value(), -2);
+        assert_eq!(I24::from_i32(8_388_607).0, [0xff, 0xff, 0x7f]);
+    }
+
+    #[test]
+    fn test_slice_from_le_bytes() {
+        let bytes = [0x01, 0x00, 0x00, 0xfe, 0xff, 0xff, 0x00, 0x00, 0x80];
+        let samples = I24::slice_from_le_bytes(&bytes).unwrap();
+        assert_eq!(
+            samples.iter().map(|x| x.value()).collect::<Vec<_>>(),
+            [1, -2, -8_388_608]
+        );
+        assert_eq!(I24::slice_from_le_bytes(&bytes[..8]), None);
+    }
+
+    #[test]
+    fn test_matches_f32_samples() {
+        let samples = (0..256)
+            .map(|i| (libm::sinf(i as f32 * 0.2) * 20000.0) as i16)
+            .collect::<Vec<_>>();
+        let window = hann_window(&[1.0; 256]);
+        let spectrum = samples_fft_to_spectrum(
+            &samples,
+            Some(&window),
+            44100.0,
+            FrequencyLimit::All,
+            SpectrumOutput::Magnitude,
+            None,
+        )
+        .unwrap();
+
+        let mut normalized = vec![0.0; samples.len()];
+        normalize_into(&samples, &mut normalized);
+        let expected = crate::samples_fft_to_spectrum(
+            &hann_window(&normalized),
+            44100,
+            FrequencyLimit::All,
+            None,
+        )
+        .unwrap();
+        assert_eq!(spectrum.data(), expected.data());
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let samples = [0_i16; 8];
+        let spectrum = |samples: &[i16], window: Option<&[f32]>| {
+            samples_fft_to_spectrum(
+                samples,
+                window,
+                8.0,
+                FrequencyLimit::All,
+                SpectrumOutput::Magnitude,
+                None,
+            )
+        };
+        assert!(matches!(
+            spectrum(&samples[..6], None),
+            Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo)
+        ));
+        assert!(matches!(
+            spectrum(&samples, Some(&[1.0; 4])),
+            Err(SpectrumAnalyzerError::InvalidWindowLength(4))
+        ));
+        #[cfg(not(any(feature = "large-fft", feature = "rustfft")))]
+        assert!(matches!(
+            spectrum(&[0; 65536], None),
+            Err(SpectrumAnalyzerError::TooManySamples)
+        ));
+        spectrum(&samples, None).unwrap();
+    }
+}
@@ -0,0 +1,198 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+//! Iterative radix-2 FFT that is generic over the floating point type. Unlike
+//! [`microfft`], it is not limited to fixed sizes and also works with `f64`.
+//! All twiddle factors are calculated directly instead of recursively, so
+//! rounding errors don't accumulate over the stages.
+
+use alloc::vec::Vec;
+use num_complex::Complex;
+use num_traits::{Float, FloatConst};
+
+/// Calculates the twiddle factors `e^(-j * 2 * PI * k / len)` for
+/// `k` in `0..len / 2`. They can be reused for all FFTs of at most `len`
+/// samples.
+pub(crate) fn twiddles<T: Float + FloatConst>(len: usize) -> Vec<Complex<T>> {
+    let len_t = T::from(len).expect("len is representable");
+    (0..len / 2)
+        .map(|k| {
+            let k = T::from(k).expect("k is representable");
+            let angle = -(T::PI() + T::PI()) * k / len_t;
+            Complex::new(angle.cos(), angle.sin())
+        })
+        .collect()
+}
+
+/// Calculates the complex FFT in place.
+///
+/// ## Parameters
+/// * `buffer` The samples. The length must be a power of two.
+/// * `twiddles` The twiddle factors of an FFT of `2 * twiddles.len()`
+///   samples, which must be a multiple of `buffer.len()`.
+pub(crate) fn fft_in_place<T: Float>(buffer: &mut [Complex<T>], twiddles: &[Complex<T>]) {
+    let len = buffer.len();
+    debug_assert!(len.is_power_of_two());
+    if len < 2 {
+        return;
+    }
+
+    // reorder the samples by their bit-reversed index
+    let bits = len.trailing_zeros();
+    for i in 0..len {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            buffer.swap(i, j);
+        }
+    }
+
+    // butterflies of all stages, from two to `len` samples
+    let mut half = 1;
+    while half < len {
+        let stride = 2 * twiddles.len() / (2 * half);
+        for chunk in buffer.chunks_exact_mut(2 * half) {
+            let (lower, upper) = chunk.split_at_mut(half);
+            for (k, (a, b)) in lower.iter_mut().zip(upper).enumerate() {
+                let product = *b * twiddles[k * stride];
+                *b = *a - product;
+                *a = *a + product;
+            }
+        }
+        half *= 2;
+    }
+}
+
+/// Calculates the FFT of real samples and returns `samples.len() / 2 + 1`
+/// values, i.e. from the DC component to the Nyquist frequency.
+///
+/// ## Parameters
+/// * `samples` The samples. The length must be a power of two and at least
+///   two.
+pub(crate) fn real_fft<T: Float + FloatConst>(samples: &[T]) -> Vec<Complex<T>> {
+    let twiddles = twiddles::<T>(samples.len());
+    let mut buffer = samples
+        .chunks_exact(2)
+        .map(|pair| Complex::new(pair[0], pair[1]))
+        .collect::<Vec<_>>();
+    real_fft_in_place(&mut buffer, &twiddles);
+
+    // unpack the Nyquist frequency
+    let nyquist = buffer[0].im;
+    buffer[0].im = T::zero();
+    buffer.push(Complex::new(nyquist, T::zero()));
+    buffer
+}
+
+/// Calculates the FFT of real samples in place without any allocation.
+///
+/// The samples are packed into a complex FFT of half the length, i.e. each
+/// pair of samples is one complex value. The result is split into the
+/// spectra of the even and the odd samples afterwards. The real value of the
+/// Nyquist frequency is packed into the imaginary part of the DC component,
+/// as [`microfft::real`] does.
+///
+/// ## Parameters
+/// * `buffer` The samples as pairs of real values. The number of samples,
+///   i.e. `2 * buffer.len()`, must be a power of two and at least two.
+/// * `twiddles` The twiddle factors of an FFT of `2 * twiddles.len()`
+///   samples, which must be a multiple of the number of samples.
+pub(crate) fn real_fft_in_place<T: Float>(buffer: &mut [Complex<T>], twiddles: &[Complex<T>]) {
+    let half = buffer.len();
+    debug_assert!(half.is_power_of_two());
+    debug_assert_eq!((2 * twiddles.len()) % (2 * half), 0);
+    fft_in_place(buffer, twiddles);
+
+    let stride = twiddles.len() / half;
+    let one_half = T::one() / (T::one() + T::one());
+    // `X[k] = E[k] + W^k * O[k]`, where `E[k] = (Z[k] + conj(Z[half - k])) / 2`
+    // and `O[k] = (Z[k] - conj(Z[half - k])) / 2j`. Both `E` and `O` are
+    // Hermitian, so `k` and `half - k` are calculated together.
+    let split = |z: Complex<T>, z_mirrored: Complex<T>| {
+        let even = (z + z_mirrored.conj()) * one_half;
+        let odd = (z - z_mirrored.conj()) * Complex::new(T::zero(), -one_half);
+        (even, odd)
+    };
+    for k in 1..=half / 2 {
+        let mirrored = half - k;
+        let (even, odd) = split(buffer[k], buffer[mirrored]);
+        buffer[k] = even + twiddles[k * stride] * odd;
+        if mirrored != k {
+            buffer[mirrored] = even.conj() + twiddles[mirrored * stride] * odd.conj();
+        }
+    }
+
+    // DC component and Nyquist frequency: `E[0] = re(Z[0])`, `O[0] = im(Z[0])`
+    let z = buffer[0];
+    buffer[0] = Complex::new(z.re + z.im, z.re - z.im);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f64::consts::PI;
+
+    /// Textbook DFT as reference.
+    fn dft(samples: &[f64]) -> Vec<Complex<f64>> {
+        let len = samples.len();
+        (0..=len / 2)
+            .map(|k| {
+                samples
+                    .iter()
+                    .enumerate()
+                    .map(|(n, x)| {
+                        let angle = -2.0 * PI * (k * n) as f64 / len as f64;
+                        Complex::new(x * libm::cos(angle), x * libm::sin(angle))
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_dft() {
+        for len in [2, 4, 8, 64, 512] {
+            let samples = (0..len)
+                .map(|i| libm::sin(i as f64 * 0.7) + 0.3 * libm::cos(i as f64 * 2.1) + 0.1)
+                .collect::<Vec<_>>();
+            let fft = real_fft(&samples);
+            let expected = dft(&samples);
+            assert_eq!(fft.len(), len / 2 + 1);
+            for (actual, expected) in fft.iter().zip(expected) {
+                assert!((actual - expected).norm() < 1e-10, "{actual} != {expected}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_microfft() {
+        let samples = (0..1024)
+            .map(|i| libm::sinf(i as f32 * 0.3) - 0.5 * libm::cosf(i as f32 * 1.3))
+            .collect::<Vec<_>>();
+        let fft = real_fft(&samples);
+        let expected = crate::fft::FftImpl::default().calc(&samples);
+        for (actual, expected) in fft.iter().zip(expected) {
+            assert!((actual - expected).norm() < 1e-3, "{actual} != {expected}");
+        }
+    }
+}
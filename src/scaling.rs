@@ -21,6 +21,7 @@ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
+
 //! This module contains convenient public transform functions that you can use
 //! as parameters in [`samples_fft_to_spectrum`] for scaling the frequency value
 //! (the FFT result).
@@ -28,8 +29,21 @@ SOFTWARE.
 //! They act as "idea/inspiration". Feel free to either compose them or create
 //! your own derivation from them.
 //!
+//! To build a chain of scalings at runtime, e.g. from a configuration file,
+//! use [`ScalingPipeline`].
+//!
+//! All types and functions are generic over the floating point type, which
+//! is `f32` by default and `f64` for [`crate::double`].
+//!
 //! [`samples_fft_to_spectrum`]: crate::samples_fft_to_spectrum
+use crate::error::SpectrumAnalyzerError;
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::spectrum::FrequencySpectrum;
 use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+use num_traits::{Float, FloatConst};
 
 /// Helper struct for [`SpectrumScalingFunction`] that is passed into the
 /// scaling function together with the current frequency value.
@@ -38,23 +52,24 @@ use alloc::boxed::Box;
 /// current data of a [`FrequencySpectrum`].
 ///
 /// This uses `f32` in favor of [`FrequencyValue`] because the latter led to
-/// some implementation problems.
+/// some implementation problems. The values are `f64` for the spectra of
+/// [`crate::double`].
 ///
 /// [`FrequencySpectrum`]: crate::FrequencySpectrum
 /// [`FrequencyValue`]: crate::FrequencyValue
 #[derive(Debug)]
-pub struct SpectrumDataStats {
+pub struct SpectrumDataStats<T = f32> {
     /// Minimal frequency value in spectrum.
-    pub min: f32,
+    pub min: T,
     /// Maximum frequency value in spectrum.
-    pub max: f32,
+    pub max: T,
     /// Average frequency value in spectrum.
-    pub average: f32,
+    pub average: T,
     /// Median frequency value in spectrum.
-    pub median: f32,
+    pub median: T,
     /// Number of samples (`samples.len()`). Already casted to f32, to avoid
     /// repeatedly casting in a loop for each value.
-    pub n: f32,
+    pub n: T,
 }
 
 /// Describes the type for a function that scales/normalizes the data inside
@@ -79,7 +94,7 @@ pub struct SpectrumDataStats {
 ///
 /// [`FrequencySpectrum`]: crate::FrequencySpectrum
 /// [`FrequencyValue`]: crate::FrequencyValue
-pub type SpectrumScalingFunction = dyn Fn(f32, &SpectrumDataStats) -> f32;
+pub type SpectrumScalingFunction<T = f32> = dyn Fn(T, &SpectrumDataStats<T>) -> T;
 
 /// Calculates the base 10 logarithm of each frequency magnitude and
 /// multiplies it with 20.
@@ -100,15 +115,18 @@ pub type SpectrumScalingFunction = dyn Fn(f32, &SpectrumDataStats) -> f32;
 /// );
 /// ```
 /// Function is of type [`SpectrumScalingFunction`].
+///
+/// A value of `0.0` is mapped to `0 dB`. Use [`Decibel`] for a configurable
+/// reference value and floor.
 #[must_use]
-pub fn scale_20_times_log10(fr_val: f32, _stats: &SpectrumDataStats) -> f32 {
+pub fn scale_20_times_log10<T: Float>(fr_val: T, _stats: &SpectrumDataStats<T>) -> T {
     debug_assert!(!fr_val.is_infinite());
     debug_assert!(!fr_val.is_nan());
-    debug_assert!(fr_val >= 0.0);
-    if fr_val == 0.0 {
-        0.0
+    debug_assert!(fr_val >= T::zero());
+    if fr_val == T::zero() {
+        T::zero()
     } else {
-        20.0 * libm::log10f(fr_val)
+        crate::constant::<T>(20.0) * fr_val.log10()
     }
 }
 
@@ -116,14 +134,14 @@ pub fn scale_20_times_log10(fr_val: f32, _stats: &SpectrumDataStats) -> f32 {
 /// Function is of type [`SpectrumScalingFunction`]. Expects that [`SpectrumDataStats::min`] is
 /// not negative.
 #[must_use]
-pub fn scale_to_zero_to_one(fr_val: f32, stats: &SpectrumDataStats) -> f32 {
+pub fn scale_to_zero_to_one<T: Float>(fr_val: T, stats: &SpectrumDataStats<T>) -> T {
     debug_assert!(!fr_val.is_infinite());
     debug_assert!(!fr_val.is_nan());
-    debug_assert!(fr_val >= 0.0);
-    if stats.max != 0.0 {
+    debug_assert!(fr_val >= T::zero());
+    if stats.max != T::zero() {
         fr_val / stats.max
     } else {
-        0.0
+        T::zero()
     }
 }
 
@@ -131,11 +149,11 @@ pub fn scale_to_zero_to_one(fr_val: f32, stats: &SpectrumDataStats) -> f32 {
 /// by the length of samples, so that values of different samples lengths are comparable.
 #[allow(non_snake_case)]
 #[must_use]
-pub fn divide_by_N(fr_val: f32, stats: &SpectrumDataStats) -> f32 {
+pub fn divide_by_N<T: Float>(fr_val: T, stats: &SpectrumDataStats<T>) -> T {
     debug_assert!(!fr_val.is_infinite());
     debug_assert!(!fr_val.is_nan());
-    debug_assert!(fr_val >= 0.0);
-    if stats.n == 0.0 {
+    debug_assert!(fr_val >= T::zero());
+    if stats.n == T::zero() {
         fr_val
     } else {
         fr_val / stats.n
@@ -149,22 +167,413 @@ pub fn divide_by_N(fr_val: f32, stats: &SpectrumDataStats) -> f32 {
 /// See <https://docs.rs/rustfft/latest/rustfft/#normalization>
 #[allow(non_snake_case)]
 #[must_use]
-pub fn divide_by_N_sqrt(fr_val: f32, stats: &SpectrumDataStats) -> f32 {
+pub fn divide_by_N_sqrt<T: Float>(fr_val: T, stats: &SpectrumDataStats<T>) -> T {
     debug_assert!(!fr_val.is_infinite());
     debug_assert!(!fr_val.is_nan());
-    debug_assert!(fr_val >= 0.0);
-    if stats.n == 0.0 {
+    debug_assert!(fr_val >= T::zero());
+    if stats.n == T::zero() {
         fr_val
     } else {
         // https://docs.rs/rustfft/latest/rustfft/#normalization
-        fr_val / libm::sqrtf(stats.n)
+        fr_val / stats.n.sqrt()
+    }
+}
+
+/// Reference value of a [`Decibel`] scaling, i.e., the value that corresponds
+/// to `0 dB`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecibelReference<T = f32> {
+    /// A fixed frequency value, e.g. `1.0`.
+    Value(T),
+    /// The unscaled FFT magnitude of a sine with the given peak `amplitude`
+    /// (in the unit of the samples) at the center of a frequency bin. This is
+    /// `amplitude * N / 2 * window_gain`, where `window_gain` is the coherent
+    /// gain of the applied window function, e.g. `1.0` for no window or `0.5`
+    /// for a Hann window. With [`Decibel::power`], the reference is the
+    /// square of it, i.e. the unscaled power of the sine.
+    Sine {
+        /// Peak amplitude of the reference sine.
+        amplitude: T,
+        /// Coherent gain of the window function.
+        window_gain: T,
+    },
+}
+
+/// Whether a [`Decibel`] scaling converts amplitude or power values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecibelKind {
+    /// Amplitude values, e.g. magnitudes: `20 * log10`.
+    Amplitude,
+    /// Power values: `10 * log10`.
+    Power,
+}
+
+impl DecibelKind {
+    /// Returns the factor of the logarithm, i.e. `20.0` or `10.0`.
+    #[must_use]
+    pub const fn factor(self) -> f32 {
+        match self {
+            Self::Amplitude => 20.0,
+            Self::Power => 10.0,
+        }
+    }
+}
+
+/// Converts frequency values to decibels relative to a [`DecibelReference`].
+///
+/// The result is `factor * log10(fr_val / reference)` with the factor of the
+/// [`DecibelKind`]. Values below the floor, such as the value of silence, are
+/// clamped to the floor.
+///
+/// The presets [`Self::dbfs`], [`Self::dbv`], and [`Self::dbu`] expect the
+/// unscaled FFT magnitudes, so the scaling must not be combined with a
+/// preceding [`divide_by_N`] or similar.
+///
+/// ## Usage
+/// ```rust
+/// use spectrum_analyzer::{samples_fft_to_spectrum, FrequencyLimit};
+/// use spectrum_analyzer::scaling::Decibel;
+/// use spectrum_analyzer::windows::hann_window;
+/// let window = hann_window(&[0.0, 0.1, 0.2, 0.3]); // add real data here
+/// // samples are normalized to [-1.0; 1.0]
+/// let dbfs = Decibel::dbfs(1.0, 0.5).with_floor(-100.0);
+/// let spectrum = samples_fft_to_spectrum(
+///     &window,
+///     44100,
+///     FrequencyLimit::All,
+///     Some(&move |val, stats| dbfs.scale(val, stats)),
+/// );
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decibel<T = f32> {
+    /// Whether amplitude or power values are converted.
+    kind: DecibelKind,
+    /// Value that corresponds to `0 dB`.
+    reference: DecibelReference<T>,
+    /// Lowest possible result in dB.
+    floor: T,
+}
+
+impl Decibel {
+    /// Default floor for all constructors, also of the `f64` scalings of
+    /// [`crate::double`].
+    pub const DEFAULT_FLOOR: f32 = -120.0;
+}
+
+impl<T: Float + FloatConst> Decibel<T> {
+    /// Creates a new scaling for amplitude values (`20 * log10`).
+    #[must_use]
+    pub fn amplitude(reference: DecibelReference<T>) -> Self {
+        Self::new(DecibelKind::Amplitude, reference)
+    }
+
+    /// Creates a new scaling for power values (`10 * log10`).
+    #[must_use]
+    pub fn power(reference: DecibelReference<T>) -> Self {
+        Self::new(DecibelKind::Power, reference)
+    }
+
+    /// Creates a new scaling with the [`Decibel::DEFAULT_FLOOR`].
+    fn new(kind: DecibelKind, reference: DecibelReference<T>) -> Self {
+        Self {
+            kind,
+            reference,
+            floor: crate::constant(f64::from(Decibel::DEFAULT_FLOOR)),
+        }
+    }
+
+    /// dB relative to full scale (dBFS): a sine with the peak amplitude
+    /// `full_scale`, e.g. `1.0` for normalized samples or `32767.0` for
+    /// 16 bit samples, results in `0 dBFS`. `window_gain` is the coherent
+    /// gain of the window function, see [`DecibelReference::Sine`].
+    #[must_use]
+    pub fn dbfs(full_scale: T, window_gain: T) -> Self {
+        Self::amplitude(DecibelReference::Sine {
+            amplitude: full_scale,
+            window_gain,
+        })
+    }
+
+    /// dB relative to 1 V RMS (dBV). The samples must be in volts.
+    #[must_use]
+    pub fn dbv(window_gain: T) -> Self {
+        Self::amplitude(DecibelReference::Sine {
+            amplitude: T::SQRT_2(),
+            window_gain,
+        })
+    }
+
+    /// dB relative to 0.7746 V RMS (dBu), i.e. 1 mW into 600 Ω. The samples
+    /// must be in volts.
+    #[must_use]
+    pub fn dbu(window_gain: T) -> Self {
+        Self::amplitude(DecibelReference::Sine {
+            amplitude: crate::constant::<T>(0.774_596_669_241_483_4) * T::SQRT_2(),
+            window_gain,
+        })
+    }
+
+    /// Sets the lowest possible result in dB.
+    #[must_use]
+    pub const fn with_floor(mut self, floor: T) -> Self {
+        self.floor = floor;
+        self
+    }
+
+    /// Returns whether amplitude or power values are converted.
+    #[must_use]
+    pub const fn kind(&self) -> DecibelKind {
+        self.kind
+    }
+
+    /// Returns the reference value.
+    #[must_use]
+    pub const fn reference(&self) -> DecibelReference<T> {
+        self.reference
+    }
+
+    /// Returns the lowest possible result in dB.
+    #[must_use]
+    pub const fn floor(&self) -> T {
+        self.floor
+    }
+
+    /// Scales a single frequency value. Has the signature of a
+    /// [`SpectrumScalingFunction`].
+    #[must_use]
+    pub fn scale(&self, fr_val: T, stats: &SpectrumDataStats<T>) -> T {
+        debug_assert!(!fr_val.is_infinite());
+        debug_assert!(!fr_val.is_nan());
+        debug_assert!(fr_val >= T::zero());
+        let reference = match self.reference {
+            DecibelReference::Value(reference) => reference,
+            DecibelReference::Sine {
+                amplitude,
+                window_gain,
+            } => {
+                let magnitude = amplitude * stats.n / crate::constant(2.0) * window_gain;
+                match self.kind {
+                    DecibelKind::Amplitude => magnitude,
+                    DecibelKind::Power => magnitude * magnitude,
+                }
+            }
+        };
+        if fr_val <= T::zero() || reference <= T::zero() {
+            return self.floor;
+        }
+        let factor = crate::constant::<T>(f64::from(self.kind.factor()));
+        let db = factor * (fr_val / reference).log10();
+        if db < self.floor { self.floor } else { db }
+    }
+}
+
+/// A single step of a [`ScalingPipeline`]. Each built-in scaling of this
+/// module has a corresponding variant.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Scaling<T = f32> {
+    /// See [`scale_20_times_log10`].
+    Scale20TimesLog10,
+    /// See [`scale_to_zero_to_one`].
+    ScaleToZeroToOne,
+    /// See [`divide_by_N`].
+    DivideByN,
+    /// See [`divide_by_N_sqrt`].
+    DivideByNSqrt,
+    /// See [`Decibel`].
+    Decibel(Decibel<T>),
+    /// A custom scaling function. It can't be (de)serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(CustomScaling<T>),
+}
+
+impl<T: Float + FloatConst> Scaling<T> {
+    /// Creates a [`Self::Custom`] step from a named closure or function.
+    pub fn custom(
+        name: impl Into<String>,
+        scaling_fn: impl Fn(T, &SpectrumDataStats<T>) -> T + Send + Sync + 'static,
+    ) -> Self {
+        Self::Custom(CustomScaling {
+            name: name.into(),
+            scaling_fn: Box::new(scaling_fn),
+        })
+    }
+
+    /// Scales a single frequency value. Has the signature of a
+    /// [`SpectrumScalingFunction`].
+    #[must_use]
+    pub fn scale(&self, fr_val: T, stats: &SpectrumDataStats<T>) -> T {
+        match self {
+            Self::Scale20TimesLog10 => scale_20_times_log10(fr_val, stats),
+            Self::ScaleToZeroToOne => scale_to_zero_to_one(fr_val, stats),
+            Self::DivideByN => divide_by_N(fr_val, stats),
+            Self::DivideByNSqrt => divide_by_N_sqrt(fr_val, stats),
+            Self::Decibel(decibel) => decibel.scale(fr_val, stats),
+            Self::Custom(custom) => (custom.scaling_fn)(fr_val, stats),
+        }
+    }
+}
+
+/// Named custom scaling function of a [`Scaling::Custom`] step. The name is
+/// used for the [`Debug`] output.
+pub struct CustomScaling<T = f32> {
+    /// Name of the scaling.
+    name: String,
+    /// The actual scaling function.
+    scaling_fn: Box<SyncScalingFunction<T>>,
+}
+
+/// Like [`SpectrumScalingFunction`] but can be shared across thread boundaries.
+type SyncScalingFunction<T> = dyn Fn(T, &SpectrumDataStats<T>) -> T + Send + Sync;
+
+impl<T> CustomScaling<T> {
+    /// Returns the name of the scaling.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<T> Debug for CustomScaling<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CustomScaling")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A spectrum that a [`ScalingPipeline`] can be applied to, i.e. a
+/// [`FrequencySpectrum`] or a [`crate::double::FrequencySpectrum`].
+pub trait ScalableSpectrum<T> {
+    /// The (frequency, frequency value)-pairs of the spectrum. The working
+    /// buffer holds one of them per frequency.
+    type Pair;
+
+    /// Applies `scaling_fn` to each frequency value and updates the
+    /// statistics afterwards, like [`FrequencySpectrum::apply_scaling_fn`].
+    fn scale_values(
+        &mut self,
+        scaling_fn: &dyn Fn(T, &SpectrumDataStats<T>) -> T,
+        working_buffer: &mut [Self::Pair],
+    ) -> Result<(), SpectrumAnalyzerError>;
+}
+
+impl ScalableSpectrum<f32> for FrequencySpectrum {
+    type Pair = (Frequency, FrequencyValue);
+
+    fn scale_values(
+        &mut self,
+        scaling_fn: &dyn Fn(f32, &SpectrumDataStats) -> f32,
+        working_buffer: &mut [Self::Pair],
+    ) -> Result<(), SpectrumAnalyzerError> {
+        self.apply_scaling(scaling_fn, working_buffer)
+    }
+}
+
+impl ScalableSpectrum<f64> for crate::double::FrequencySpectrum {
+    type Pair = (f64, f64);
+
+    fn scale_values(
+        &mut self,
+        scaling_fn: &dyn Fn(f64, &SpectrumDataStats<f64>) -> f64,
+        working_buffer: &mut [Self::Pair],
+    ) -> Result<(), SpectrumAnalyzerError> {
+        self.apply_scaling_fn(scaling_fn, working_buffer)
+    }
+}
+
+/// Owned chain of [`Scaling`] steps that can be built at runtime, e.g. from
+/// user configuration. With the `serde` feature, pipelines without custom
+/// steps can be (de)serialized.
+///
+/// ## Usage
+/// ```rust
+/// use spectrum_analyzer::{samples_fft_to_spectrum, FrequencyLimit};
+/// use spectrum_analyzer::scaling::{Decibel, Scaling, ScalingPipeline};
+/// let window = [0.0, 0.1, 0.2, 0.3]; // add real data here
+/// let mut spectrum = samples_fft_to_spectrum(&window, 44100, FrequencyLimit::All, None).unwrap();
+///
+/// let offset = 3.0; // from user configuration
+/// let pipeline = ScalingPipeline::new()
+///     .with(Scaling::Decibel(Decibel::dbfs(1.0, 1.0)))
+///     .with(Scaling::custom("offset", move |val, _stats| val + offset));
+///
+/// let mut working_buffer = vec![(0.0.into(), 0.0.into()); spectrum.data().len()];
+/// pipeline.apply(&mut spectrum, &mut working_buffer).unwrap();
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScalingPipeline<T = f32> {
+    /// All steps in the order they are applied.
+    steps: Vec<Scaling<T>>,
+}
+
+impl<T> Default for ScalingPipeline<T> {
+    fn default() -> Self {
+        Self { steps: Vec::new() }
+    }
+}
+
+impl<T: Float + FloatConst> ScalingPipeline<T> {
+    /// Creates a new pipeline without any steps.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    /// Appends a step to the pipeline.
+    #[must_use]
+    pub fn with(mut self, step: Scaling<T>) -> Self {
+        self.push(step);
+        self
+    }
+
+    /// Appends a step to the pipeline.
+    pub fn push(&mut self, step: Scaling<T>) {
+        self.steps.push(step);
+    }
+
+    /// Returns all steps in the order they are applied.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // false positive
+    pub fn steps(&self) -> &[Scaling<T>] {
+        &self.steps
+    }
+
+    /// Applies all steps to a single frequency value with the same `stats`.
+    /// Has the signature of a [`SpectrumScalingFunction`], which is like
+    /// [`combined`].
+    #[must_use]
+    pub fn scale(&self, fr_val: T, stats: &SpectrumDataStats<T>) -> T {
+        self.steps
+            .iter()
+            .fold(fr_val, |val, step| step.scale(val, stats))
+    }
+
+    /// Applies all steps to the spectrum, one after another. Unlike
+    /// [`Self::scale`], each step gets the statistics of the spectrum as
+    /// scaled by the previous steps. See
+    /// [`FrequencySpectrum::apply_scaling_fn`] for details.
+    pub fn apply<S: ScalableSpectrum<T>>(
+        &self,
+        spectrum: &mut S,
+        working_buffer: &mut [S::Pair],
+    ) -> Result<(), SpectrumAnalyzerError> {
+        for step in &self.steps {
+            spectrum.scale_values(&|val, stats| step.scale(val, stats), working_buffer)?;
+        }
+        Ok(())
     }
 }
 
 /// Combines several scaling functions into a new single one.
 ///
 /// Currently there is the limitation that the functions need to have
-/// a `'static` lifetime. This will be fixed if someone needs this.
+/// a `'static` lifetime. Use [`ScalingPipeline`] to combine scalings that
+/// are created at runtime.
 ///
 /// # Example
 /// ```
@@ -208,21 +617,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decibel() {
+        let stats = SpectrumDataStats {
+            min: 0.0,
+            max: 0.0,
+            average: 0.0,
+            median: 0.0,
+            n: 8.0,
+        };
+
+        let db = Decibel::amplitude(DecibelReference::Value(10.0));
+        assert_eq!(db.scale(10.0, &stats), 0.0);
+        assert_eq!(db.scale(100.0, &stats), 20.0);
+        assert_eq!(
+            db.scale(0.0, &stats),
+            Decibel::DEFAULT_FLOOR,
+            "silence must be the floor"
+        );
+
+        let db = Decibel::power(DecibelReference::Value(1.0)).with_floor(-30.0);
+        assert_eq!(db.kind(), DecibelKind::Power);
+        assert_eq!(db.scale(100.0, &stats), 20.0);
+        assert_eq!(db.scale(0.0001, &stats), -30.0);
+
+        // full scale sine without window: magnitude is `N / 2`
+        let db = Decibel::dbfs(1.0, 1.0);
+        float_cmp::assert_approx_eq!(f32, db.scale(4.0, &stats), 0.0, epsilon = 0.0001);
+        float_cmp::assert_approx_eq!(f32, db.scale(0.4, &stats), -20.0, epsilon = 0.0001);
+
+        // power of a full scale sine without window: `(N / 2)²`
+        let db = Decibel::power(DecibelReference::Sine {
+            amplitude: 1.0,
+            window_gain: 1.0,
+        });
+        float_cmp::assert_approx_eq!(f32, db.scale(16.0, &stats), 0.0, epsilon = 0.0001);
+        float_cmp::assert_approx_eq!(f32, db.scale(1.6, &stats), -10.0, epsilon = 0.0001);
+
+        // a sine with 1 V RMS has a peak amplitude of sqrt(2)
+        let db = Decibel::dbv(1.0);
+        let magnitude = core::f32::consts::SQRT_2 * 4.0;
+        float_cmp::assert_approx_eq!(f32, db.scale(magnitude, &stats), 0.0, epsilon = 0.0001);
+        float_cmp::assert_approx_eq!(
+            f32,
+            Decibel::dbu(1.0).scale(magnitude, &stats),
+            2.218_487,
+            epsilon = 0.0001
+        );
+    }
+
+    #[test]
+    fn test_scaling_pipeline() {
+        let stats = SpectrumDataStats {
+            min: 0.0,
+            max: 100.0,
+            average: 0.0,
+            median: 0.0,
+            n: 10.0,
+        };
+        let factor = 3.0;
+        let mut pipeline = ScalingPipeline::new()
+            .with(Scaling::DivideByN)
+            .with(Scaling::custom("factor", move |val, _stats| val * factor));
+        pipeline.push(Scaling::Scale20TimesLog10);
+
+        assert_eq!(pipeline.steps().len(), 3);
+        float_cmp::assert_approx_eq!(f32, pipeline.scale(1000.0, &stats), 49.542_425);
+        assert!(format!("{pipeline:?}").contains("\"factor\""));
+
+        let mut data = vec![(0.0_f32.into(), 1.0_f32.into()), (1.0.into(), 3.0.into())];
+        let mut spectrum = FrequencySpectrum::new(data.clone(), 1.0, 2, &mut data);
+        ScalingPipeline::new()
+            .with(Scaling::custom("minus_one", |val, _stats| val - 1.0))
+            // gets the statistics of the previous step
+            .with(Scaling::ScaleToZeroToOne)
+            .apply(&mut spectrum, &mut data)
+            .unwrap();
+        assert_eq!(spectrum.data()[0].1.val(), 0.0);
+        assert_eq!(spectrum.data()[1].1.val(), 1.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_scaling_pipeline_serde() {
+        let pipeline = ScalingPipeline::new()
+            .with(Scaling::DivideByNSqrt)
+            .with(Scaling::Decibel(Decibel::dbfs(1.0, 0.5).with_floor(-90.0)));
+        let json = serde_json::to_string(&pipeline).unwrap();
+        let deserialized: ScalingPipeline = serde_json::from_str(&json).unwrap();
+        assert!(matches!(deserialized.steps()[0], Scaling::DivideByNSqrt));
+        assert!(matches!(
+            deserialized.steps()[1],
+            Scaling::Decibel(db) if db == Decibel::dbfs(1.0, 0.5).with_floor(-90.0)
+        ));
+
+        let custom: ScalingPipeline =
+            ScalingPipeline::new().with(Scaling::custom("x", |val, _| val));
+        serde_json::to_string(&custom).unwrap_err();
+    }
+
     // make sure this compiles
     #[test]
     fn test_combined_compiles() {
         let _combined_static = combined(&[&scale_20_times_log10, &divide_by_N, &divide_by_N_sqrt]);
 
-        // doesn't compile yet.. fix this once someone requests it
-        /*let closure_scaling_fnc = |fr_val: f32, _stats: &SpectrumDataStats| {
-           0.0
+        // closures, which `combined` doesn't support, are chained with a
+        // pipeline instead
+        let offset = 3.0;
+        let pipeline = ScalingPipeline::new()
+            .with(Scaling::Scale20TimesLog10)
+            .with(Scaling::custom("offset", move |val, _stats| val + offset))
+            .with(Scaling::DivideByN);
+        let stats = SpectrumDataStats {
+            min: 0.0,
+            max: 0.0,
+            average: 0.0,
+            median: 0.0,
+            n: 2.0,
         };
-
-        let _combined_dynamic = combined(&[
-            &scale_20_times_log10,
-            &divide_by_N,
-            &divide_by_N_sqrt,
-            &closure_scaling_fnc,
-        ]);*/
+        float_cmp::assert_approx_eq!(f32, pipeline.scale(10.0, &stats), 11.5, epsilon = 0.0001);
     }
 }
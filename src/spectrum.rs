@@ -26,9 +26,13 @@ SOFTWARE.
 use self::math::*;
 use crate::error::SpectrumAnalyzerError;
 use crate::frequency::{Frequency, FrequencyValue};
+use crate::interpolation::Interpolation;
 use crate::scaling::{SpectrumDataStats, SpectrumScalingFunction};
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{Debug, Display, Formatter};
+use num_traits::{Float, NumCast, Zero};
 
 /// Convenient wrapper around the processed FFT result which describes each
 /// frequency and its value/amplitude from the analyzed samples.
@@ -129,6 +133,17 @@ impl FrequencySpectrum {
         &mut self,
         scaling_fn: &SpectrumScalingFunction,
         working_buffer: &mut [(Frequency, FrequencyValue)],
+    ) -> Result<(), SpectrumAnalyzerError> {
+        self.apply_scaling(scaling_fn, working_buffer)
+    }
+
+    /// Like [`Self::apply_scaling_fn`] but the scaling function may borrow
+    /// data, such as a [`crate::scaling::ScalingPipeline`].
+    #[inline]
+    pub(crate) fn apply_scaling(
+        &mut self,
+        scaling_fn: impl Fn(f32, &SpectrumDataStats) -> f32,
+        working_buffer: &mut [(Frequency, FrequencyValue)],
     ) -> Result<(), SpectrumAnalyzerError> {
         // This represents statistics about the spectrum in its current state
         // which a scaling function may use to scale values.
@@ -144,12 +159,28 @@ impl FrequencySpectrum {
             n: self.samples_len as f32,
         };
 
+        self.map_values(|_fr, fr_val| scaling_fn(fr_val, &stats), working_buffer)
+    }
+
+    /// Like [`Self::apply_scaling_fn`] but the mapping function also gets the
+    /// frequency of each value. Used for frequency-dependent corrections,
+    /// such as [`crate::calibration::CalibrationCurve`].
+    ///
+    /// ## Parameters
+    /// * `map_fn` Function that maps `(frequency, frequency value)` to the
+    ///   new frequency value.
+    #[inline]
+    pub(crate) fn map_values(
+        &mut self,
+        mut map_fn: impl FnMut(f32, f32) -> f32,
+        working_buffer: &mut [(Frequency, FrequencyValue)],
+    ) -> Result<(), SpectrumAnalyzerError> {
         // Iterate over the whole spectrum and scale each frequency value.
         // I use a regular for loop instead of for_each(), so that I can
         // early return a result here
-        for (_fr, fr_val) in &mut self.data {
+        for (fr, fr_val) in &mut self.data {
             // scale value
-            let scaled_val: f32 = scaling_fn(fr_val.val(), &stats);
+            let scaled_val: f32 = map_fn(fr.val(), fr_val.val());
 
             // sanity check
             if scaled_val.is_nan() || scaled_val.is_infinite() {
@@ -266,14 +297,17 @@ impl FrequencySpectrum {
     /// tend to filter out any DC component at the analogue level. In cases where you might
     /// be interested it can be calculated directly as an average in the usual way, without
     /// resorting to a DFT/FFT.* - Paul R.
+    ///
+    /// The DC component is found by its frequency, so it is also found in
+    /// two-sided spectra with negative frequencies, see [`crate::iq`].
     #[inline]
     #[must_use]
     pub fn dc_component(&self) -> Option<FrequencyValue> {
-        let (maybe_dc_component, dc_value) = &self.data[0];
-        if maybe_dc_component.val() == 0.0 {
-            Some(*dc_value)
-        } else {
-            None
+        // index of the first frequency that is not negative
+        let index = self.data.partition_point(|(fr, _)| fr.val() < 0.0);
+        match self.data.get(index) {
+            Some((fr, dc_value)) if fr.val() == 0.0 => Some(*dc_value),
+            _ => None,
         }
     }
 
@@ -288,7 +322,8 @@ impl FrequencySpectrum {
     /// If parameter `search_fr` (frequency) is below the lowest or the maximum
     /// frequency, this function panics! This is because the user provide
     /// the min/max frequency when the spectrum is created and knows about it.
-    /// This is similar to an intended "out of bounds"-access.
+    /// This is similar to an intended "out of bounds"-access. Use
+    /// [`Self::try_freq_val_exact`] for frequencies from untrusted input.
     ///
     /// ## Parameters
     /// - `search_fr` The frequency of that you want the amplitude/value in the spectrum.
@@ -298,6 +333,18 @@ impl FrequencySpectrum {
     #[inline]
     #[must_use]
     pub fn freq_val_exact(&self, search_fr: f32) -> FrequencyValue {
+        self.try_freq_val_exact(search_fr)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::freq_val_exact`] but returns an error instead of
+    /// panicking if `search_fr` is out of bounds or NaN. The lookup is a
+    /// binary search, i.e. `O(log n)`.
+    #[inline]
+    pub fn try_freq_val_exact(
+        &self,
+        search_fr: f32,
+    ) -> Result<FrequencyValue, FrequencyLookupError> {
         // lowest frequency in the spectrum
         let (min_fr, min_fr_val) = self.data[0];
         // highest frequency in the spectrum
@@ -309,41 +356,26 @@ impl FrequencySpectrum {
 
         // Fast return if possible
         if equals_min_fr {
-            return min_fr_val;
+            return Ok(min_fr_val);
         }
         if equals_max_fr {
-            return max_fr_val;
-        }
-        // bounds check
-        if search_fr < min_fr.val() || search_fr > max_fr.val() {
-            panic!(
-                "Frequency {}Hz is out of bounds [{}; {}]!",
-                search_fr,
-                min_fr.val(),
-                max_fr.val()
-            );
+            return Ok(max_fr_val);
         }
 
-        // We search for Point C (x=search_fr, y=???) between Point A and Point B iteratively.
-        // Point B is always the successor of A.
-
-        for two_points in self.data.iter().as_slice().windows(2) {
-            let point_a = two_points[0];
-            let point_b = two_points[1];
-            let point_a_x = point_a.0.val();
-            let point_a_y = point_a.1;
-            let point_b_x = point_b.0.val();
-            let point_b_y = point_b.1.val();
-
-            // check if we are in the correct window; we are in the correct window
-            // iff point_a_x <= search_fr <= point_b_x
-            if search_fr > point_b_x {
-                continue;
-            }
+        // We search for Point C (x=search_fr, y=???) between Point A and Point B.
+        let index = upper_index(&self.data, search_fr)?;
+        let (point_a, point_b) = (self.data[index - 1], self.data[index]);
+        let point_a_x = point_a.0.val();
+        let point_a_y = point_a.1;
+        let point_b_x = point_b.0.val();
+        let point_b_y = point_b.1.val();
 
-            return if float_cmp::approx_eq!(f32, point_a_x, search_fr, ulps = 3) {
+        Ok(
+            if float_cmp::approx_eq!(f32, point_a_x, search_fr, ulps = 3) {
                 // directly return if possible
                 point_a_y
+            } else if float_cmp::approx_eq!(f32, point_b_x, search_fr, ulps = 3) {
+                point_b_y.into()
             } else {
                 calculate_y_coord_between_points(
                     (point_a_x, point_a_y.val()),
@@ -351,10 +383,8 @@ impl FrequencySpectrum {
                     search_fr,
                 )
                 .into()
-            };
-        }
-
-        panic!("Here be dragons");
+            },
+        )
     }
 
     /// Returns the frequency closest to parameter `search_fr` in the spectrum. For example
@@ -368,7 +398,8 @@ impl FrequencySpectrum {
     ///
     /// ## Panics
     /// If parameter `search_fr` (frequency) is below the lowest or the maximum
-    /// frequency, this function panics!
+    /// frequency, this function panics! Use [`Self::try_freq_val_closest`] for
+    /// frequencies from untrusted input.
     ///
     /// ## Parameters
     /// - `search_fr` The frequency of that you want the amplitude/value in the spectrum.
@@ -378,6 +409,18 @@ impl FrequencySpectrum {
     #[inline]
     #[must_use]
     pub fn freq_val_closest(&self, search_fr: f32) -> (Frequency, FrequencyValue) {
+        self.try_freq_val_closest(search_fr)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::freq_val_closest`] but returns an error instead of
+    /// panicking if `search_fr` is out of bounds or NaN. The lookup is a
+    /// binary search, i.e. `O(log n)`.
+    #[inline]
+    pub fn try_freq_val_closest(
+        &self,
+        search_fr: f32,
+    ) -> Result<(Frequency, FrequencyValue), FrequencyLookupError> {
         // lowest frequency in the spectrum
         let (min_fr, min_fr_val) = self.data[0];
         // highest frequency in the spectrum
@@ -389,156 +432,368 @@ impl FrequencySpectrum {
 
         // Fast return if possible
         if equals_min_fr {
-            return (min_fr, min_fr_val);
+            return Ok((min_fr, min_fr_val));
         }
         if equals_max_fr {
-            return (max_fr, max_fr_val);
-        }
-
-        // bounds check
-        if search_fr < min_fr.val() || search_fr > max_fr.val() {
-            panic!(
-                "Frequency {}Hz is out of bounds [{}; {}]!",
-                search_fr,
-                min_fr.val(),
-                max_fr.val()
-            );
+            return Ok((max_fr, max_fr_val));
         }
 
-        for two_points in self.data.iter().as_slice().windows(2) {
-            let point_a = two_points[0];
-            let point_b = two_points[1];
-            let point_a_x = point_a.0;
-            let point_a_y = point_a.1;
-            let point_b_x = point_b.0;
-            let point_b_y = point_b.1;
-
-            // check if we are in the correct window; we are in the correct window
-            // iff point_a_x <= search_fr <= point_b_x
-            if search_fr > point_b_x.val() {
-                continue;
-            }
-
-            return if float_cmp::approx_eq!(f32, point_a_x.val(), search_fr, ulps = 3) {
+        let index = upper_index(&self.data, search_fr)?;
+        let (point_a, point_b) = (self.data[index - 1], self.data[index]);
+        Ok(
+            if float_cmp::approx_eq!(f32, point_a.0.val(), search_fr, ulps = 3) {
                 // directly return if possible
-                (point_a_x, point_a_y)
+                point_a
             } else {
                 // absolute difference
-                let delta_to_a = search_fr - point_a_x.val();
-                // let delta_to_b = point_b_x.val() - search_fr;
+                let delta_to_a = search_fr - point_a.0.val();
                 if delta_to_a / self.frequency_resolution < 0.5 {
-                    (point_a_x, point_a_y)
+                    point_a
                 } else {
-                    (point_b_x, point_b_y)
+                    point_b
                 }
-            };
+            },
+        )
+    }
+
+    /// Like [`Self::freq_val_exact`] but with the given [`Interpolation`]
+    /// between two bins.
+    ///
+    /// ## Panics
+    /// If parameter `search_fr` (frequency) is below the lowest or the maximum
+    /// frequency, this function panics! Use
+    /// [`Self::try_freq_val_interpolated`] for frequencies from untrusted
+    /// input.
+    #[inline]
+    #[must_use]
+    pub fn freq_val_interpolated(
+        &self,
+        search_fr: f32,
+        interpolation: Interpolation,
+    ) -> FrequencyValue {
+        self.try_freq_val_interpolated(search_fr, interpolation)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::freq_val_interpolated`] but returns an error instead of
+    /// panicking if `search_fr` is out of bounds or NaN.
+    #[inline]
+    pub fn try_freq_val_interpolated(
+        &self,
+        search_fr: f32,
+        interpolation: Interpolation,
+    ) -> Result<FrequencyValue, FrequencyLookupError> {
+        match interpolation {
+            Interpolation::Linear => return self.try_freq_val_exact(search_fr),
+            Interpolation::Nearest => {
+                return self.try_freq_val_closest(search_fr).map(|(_, val)| val);
+            }
+            _ => {}
         }
 
-        panic!("Here be dragons");
+        let index = upper_index(&self.data, search_fr)?;
+        // All interpolations return the exact value of a bin.
+        for (fr, fr_val) in &self.data[index - 1..=index] {
+            if float_cmp::approx_eq!(f32, fr.val(), search_fr, ulps = 3) {
+                return Ok(*fr_val);
+            }
+        }
+        let val = interpolation.interpolate(&self.data, index, search_fr, self.samples_len);
+        Ok(val.into())
+    }
+
+    /// Resamples the spectrum onto a new frequency grid, e.g. with
+    /// logarithmically spaced frequencies for a plot, with the given
+    /// [`Interpolation`] between two bins.
+    ///
+    /// ## Parameters
+    /// - `frequencies` The frequencies of the new grid. All of them must be
+    ///   within [`Self::min_fr`] and [`Self::max_fr`].
+    ///
+    /// ## Return
+    /// The `(frequency, frequency value)` pairs of the new grid, in the
+    /// order of `frequencies`, or the error of the first frequency that is
+    /// out of bounds.
+    pub fn resample(
+        &self,
+        frequencies: impl IntoIterator<Item = f32>,
+        interpolation: Interpolation,
+    ) -> Result<Vec<(Frequency, FrequencyValue)>, FrequencyLookupError> {
+        frequencies
+            .into_iter()
+            .map(|fr| {
+                self.try_freq_val_interpolated(fr, interpolation)
+                    .map(|val| (fr.into(), val))
+            })
+            .collect()
     }
 
     /// Wrapper around [`Self::freq_val_exact`] that consumes [mel].
     ///
+    /// ## Panics
+    /// If the frequency of `mel_val` is out of bounds, or if `mel_val` is
+    /// negative or NaN. See [`Self::try_mel_val`].
+    ///
     /// [mel]: https://en.wikipedia.org/wiki/Mel_scale
     #[inline]
     #[must_use]
     pub fn mel_val(&self, mel_val: f32) -> FrequencyValue {
+        self.try_mel_val(mel_val).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`Self::mel_val`] but returns an error instead of panicking, see
+    /// [`Self::try_freq_val_exact`]. Negative mel values are below `0Hz`
+    /// and therefore always out of bounds.
+    #[inline]
+    pub fn try_mel_val(&self, mel_val: f32) -> Result<FrequencyValue, FrequencyLookupError> {
+        if mel_val.is_nan() {
+            return Err(FrequencyLookupError::NaN);
+        }
         let hz = mel_to_hertz(mel_val);
-        self.freq_val_exact(hz)
+        if mel_val < 0.0 {
+            let min_fr = self.min_fr().val().max(0.0);
+            return Err(FrequencyLookupError::OutOfBounds(
+                hz,
+                min_fr,
+                self.max_fr().val(),
+            ));
+        }
+        self.try_freq_val_exact(hz)
     }
 
     /// Returns a [`BTreeMap`] with all value pairs. The key is of type [`u32`]
     /// because [`f32`] is not [`Ord`].
+    ///
+    /// Negative frequencies of two-sided spectra, see [`crate::iq`], are
+    /// skipped. Use [`Self::to_signed_map`] to keep them.
     #[inline]
     #[must_use]
     pub fn to_map(&self) -> BTreeMap<u32, f32> {
-        self.data
+        self.non_negative_data()
             .iter()
             .map(|(fr, fr_val)| (fr.val() as u32, fr_val.val()))
             .collect()
     }
 
+    /// Like [`Self::to_map`] but with a signed key, so that the negative
+    /// frequencies of two-sided spectra, see [`crate::iq`], are kept.
+    #[inline]
+    #[must_use]
+    pub fn to_signed_map(&self) -> BTreeMap<i32, f32> {
+        self.data
+            .iter()
+            .map(|(fr, fr_val)| (fr.val() as i32, fr_val.val()))
+            .collect()
+    }
+
     /// Like [`Self::to_map`] but converts the frequency (x-axis) to [mels]. The
     /// resulting map contains more results in a higher density the higher the
     /// mel value gets. This comes from the logarithmic transformation from
     /// hertz to mels.
     ///
+    /// The mel scale starts at `0Hz`, so negative frequencies of two-sided
+    /// spectra, see [`crate::iq`], are skipped.
+    ///
     /// [mels]: https://en.wikipedia.org/wiki/Mel_scale
     #[inline]
     #[must_use]
     pub fn to_mel_map(&self) -> BTreeMap<u32, f32> {
-        self.data
+        self.non_negative_data()
             .iter()
             .map(|(fr, fr_val)| (hertz_to_mel(fr.val()) as u32, fr_val.val()))
             .collect()
     }
 
+    /// Returns the value pairs from `0Hz` on, i.e. without the negative
+    /// frequencies of two-sided spectra.
+    fn non_negative_data(&self) -> &[(Frequency, FrequencyValue)] {
+        let index = self.data.partition_point(|(fr, _)| fr.val() < 0.0);
+        &self.data[index..]
+    }
+
     /// Calculates the `min`, `max`, `median`, and `average` of the frequency values/magnitudes/
-    /// amplitudes.
-    ///
-    /// To do so, it needs to create a sorted copy of the data.
+    /// amplitudes, see [`calc_statistics`].
     #[inline]
     fn calc_statistics(&mut self, working_buffer: &mut [(Frequency, FrequencyValue)]) {
-        // We create a copy with all data from `self.data` but we sort it by the
-        // frequency value and not the frequency. This way, we can easily find the
-        // median.
+        let statistics = calc_statistics(&self.data, working_buffer);
+        self.min = statistics.min;
+        self.max = statistics.max;
+        self.average = statistics.average.into();
+        self.median = statistics.median.into();
+    }
+}
 
-        let data_sorted_by_val = {
-            assert_eq!(
-                self.data.len(),
-                working_buffer.len(),
-                "The working buffer must have the same length as `self.data`!"
-            );
+/// A (frequency, frequency value)-pair of a spectrum, i.e. of a
+/// [`FrequencySpectrum`] or of a [`crate::double::FrequencySpectrum`]. It
+/// lets both share the statistics and the search.
+pub(crate) trait SpectrumPoint: Copy {
+    /// Type of the frequencies and values, i.e. `f32` or `f64`.
+    type Float: Float;
 
-            for (i, pair) in self.data.iter().enumerate() {
-                working_buffer[i] = *pair;
-            }
-            working_buffer.sort_by(|(_l_fr, l_fr_val), (_r_fr, r_fr_val)| {
-                // compare by frequency value, from min to max
-                l_fr_val.cmp(r_fr_val)
-            });
+    /// Returns the frequency.
+    fn fr(&self) -> Self::Float;
 
-            working_buffer
-        };
+    /// Returns the frequency value.
+    fn val(&self) -> Self::Float;
+}
 
-        // sum of all frequency values
-        let sum: f32 = data_sorted_by_val
-            .iter()
-            .map(|fr_val| fr_val.1.val())
-            .fold(0.0, |a, b| a + b);
-
-        // average of all frequency values
-        let avg = sum / data_sorted_by_val.len() as f32;
-        let average: FrequencyValue = avg.into();
-
-        // median of all frequency values
-        let median = {
-            let mid = data_sorted_by_val.len() / 2;
-            if data_sorted_by_val.len() % 2 == 0 {
-                let a = data_sorted_by_val[mid - 1].1;
-                let b = data_sorted_by_val[mid].1;
-                (a + b) / 2.0.into()
-            } else {
-                data_sorted_by_val[mid].1
-            }
-        };
+impl SpectrumPoint for (Frequency, FrequencyValue) {
+    type Float = f32;
+
+    #[inline]
+    fn fr(&self) -> f32 {
+        self.0.val()
+    }
+
+    #[inline]
+    fn val(&self) -> f32 {
+        self.1.val()
+    }
+}
+
+impl SpectrumPoint for (f64, f64) {
+    type Float = f64;
+
+    #[inline]
+    fn fr(&self) -> f64 {
+        self.0
+    }
+
+    #[inline]
+    fn val(&self) -> f64 {
+        self.1
+    }
+}
 
-        // Because we sorted the vector from lowest to highest value, the
-        // following lines are correct, i.e., we get min/max value with
-        // the corresponding frequency.
-        let min = data_sorted_by_val[0];
-        let max = data_sorted_by_val[data_sorted_by_val.len() - 1];
+/// Statistics of the frequency values of a spectrum, see [`calc_statistics`].
+pub(crate) struct Statistics<P: SpectrumPoint> {
+    /// Pair with the minimal frequency value.
+    pub min: P,
+    /// Pair with the maximum frequency value.
+    pub max: P,
+    /// Average frequency value.
+    pub average: P::Float,
+    /// Median frequency value.
+    pub median: P::Float,
+}
 
-        // check that I get the comparison right (and not from max to min)
-        debug_assert!(min.1 <= max.1, "min must be <= max");
+/// Calculates the `min`, `max`, `median`, and `average` of the frequency
+/// values of `data`.
+///
+/// The `min`, `max`, and `average` are calculated in a single pass. For the
+/// median, the data is copied into the working buffer, where the middle
+/// values are selected in `O(n)` instead of sorting everything.
+pub(crate) fn calc_statistics<P: SpectrumPoint>(
+    data: &[P],
+    working_buffer: &mut [P],
+) -> Statistics<P> {
+    assert_eq!(
+        data.len(),
+        working_buffer.len(),
+        "The working buffer must have the same length as `self.data`!"
+    );
+
+    // The data is sorted by frequency. On equal values, the minimum is the
+    // one with the lowest frequency and the maximum the one with the
+    // highest frequency.
+    let mut min = data[0];
+    let mut max = data[0];
+    let mut sum = P::Float::zero();
+    for pair in data {
+        if pair.val() < min.val() {
+            min = *pair;
+        }
+        if pair.val() >= max.val() {
+            max = *pair;
+        }
+        sum = sum + pair.val();
+    }
 
-        self.min = min;
-        self.max = max;
-        self.average = average;
-        self.median = median;
+    // average of all frequency values
+    let len = <P::Float as NumCast>::from(data.len()).expect("length is representable");
+    let average = sum / len;
+
+    // median of all frequency values
+    let median = {
+        working_buffer.copy_from_slice(data);
+        let mid = working_buffer.len() / 2;
+        // Afterward, all values before `mid` are smaller or equal.
+        let (lower, middle, _) = working_buffer.select_nth_unstable_by(mid, |l, r| {
+            l.val()
+                .partial_cmp(&r.val())
+                .expect("frequency values are not NaN")
+        });
+        if data.len() % 2 == 0 {
+            let a = lower
+                .iter()
+                .map(SpectrumPoint::val)
+                .fold(P::Float::neg_infinity(), P::Float::max);
+            (a + middle.val()) / crate::constant(2.0)
+        } else {
+            middle.val()
+        }
+    };
+
+    // check that I get the comparison right (and not from max to min)
+    debug_assert!(min.val() <= max.val(), "min must be <= max");
+
+    Statistics {
+        min,
+        max,
+        average,
+        median,
     }
 }
 
+/// Returns the index of the point `B` of `data` whose predecessor is the
+/// point `A`, with `A.frequency < search_fr <= B.frequency`. It is found by
+/// binary search.
+///
+/// The caller must handle `search_fr` equal to the lowest frequency.
+pub(crate) fn upper_index<P: SpectrumPoint>(
+    data: &[P],
+    search_fr: P::Float,
+) -> Result<usize, FrequencyLookupError<P::Float>> {
+    let min_fr = data[0].fr();
+    let max_fr = data[data.len() - 1].fr();
+    if search_fr.is_nan() {
+        return Err(FrequencyLookupError::NaN);
+    }
+    if search_fr < min_fr || search_fr > max_fr {
+        return Err(FrequencyLookupError::OutOfBounds(search_fr, min_fr, max_fr));
+    }
+
+    // index of the first frequency that is not below `search_fr`
+    Ok(data.partition_point(|pair| pair.fr() < search_fr).max(1))
+}
+
+/// Possible errors of the non-panicking lookups of a [`FrequencySpectrum`],
+/// such as [`FrequencySpectrum::try_freq_val_exact`].
+///
+/// The type parameter is the type of the frequencies, i.e. `f64` for the
+/// lookups of [`crate::double::FrequencySpectrum`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrequencyLookupError<T = f32> {
+    /// The frequency is outside the spectrum: `(frequency, lowest frequency,
+    /// highest frequency)`.
+    OutOfBounds(T, T, T),
+    /// The frequency is NaN.
+    NaN,
+}
+
+impl<T: Display> Display for FrequencyLookupError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::OutOfBounds(x, min, max) => {
+                write!(f, "Frequency {x}Hz is out of bounds [{min}; {max}]!")
+            }
+            Self::NaN => write!(f, "Frequency is NaN!"),
+        }
+    }
+}
+
+impl<T: Debug + Display> Error for FrequencyLookupError<T> {}
+
 /*impl FromIterator<(Frequency, FrequencyValue)> for FrequencySpectrum {
 
     #[inline]
@@ -595,8 +850,8 @@ mod math {
     }
 
     /// Converts [mel](https://en.wikipedia.org/wiki/Mel_scale) to hertz.
+    /// Negative mel values give frequencies in `]-700; 0[`.
     pub fn mel_to_hertz(mel: f32) -> f32 {
-        assert!(mel >= 0.0);
         700.0 * (libm::powf(10.0, mel / 2595.0) - 1.0)
     }
 
@@ -866,6 +1121,71 @@ mod tests {
         let _ = spectrum.freq_val_closest(451.0);
     }
 
+    /// The non-panicking lookups must return errors and match the panicking
+    /// ones within the bounds, including gaps in the frequencies.
+    #[test]
+    fn test_try_lookups() {
+        let mut spectrum_vector = vec![
+            (0.0_f32.into(), 5.0_f32.into()),
+            (50.0.into(), 50.0.into()),
+            (100.0.into(), 100.0.into()),
+            (300.0.into(), 0.0.into()),
+            (450.0.into(), 200.0.into()),
+        ];
+        let spectrum = FrequencySpectrum::new(
+            spectrum_vector.clone(),
+            50.0,
+            spectrum_vector.len() as _,
+            &mut spectrum_vector,
+        );
+
+        assert_eq!(
+            spectrum.try_freq_val_exact(-1.0),
+            Err(FrequencyLookupError::OutOfBounds(-1.0, 0.0, 450.0))
+        );
+        assert_eq!(
+            spectrum.try_freq_val_closest(450.5),
+            Err(FrequencyLookupError::OutOfBounds(450.5, 0.0, 450.0))
+        );
+        assert_eq!(
+            spectrum.try_freq_val_exact(f32::NAN),
+            Err(FrequencyLookupError::NaN)
+        );
+        assert_eq!(
+            spectrum.try_freq_val_closest(f32::NAN),
+            Err(FrequencyLookupError::NaN)
+        );
+        assert!(spectrum.try_mel_val(10000.0).is_err());
+        assert_eq!(
+            spectrum.try_mel_val(f32::NAN),
+            Err(FrequencyLookupError::NaN)
+        );
+        assert!(matches!(
+            spectrum.try_mel_val(-1.0),
+            Err(FrequencyLookupError::OutOfBounds(hz, 0.0, 450.0)) if hz < 0.0
+        ));
+        assert_eq!(spectrum.try_mel_val(0.0).unwrap().val(), 5.0);
+
+        assert_eq!(spectrum.try_freq_val_exact(0.0).unwrap().val(), 5.0);
+        assert_eq!(spectrum.try_freq_val_exact(25.0).unwrap().val(), 27.5);
+        assert_eq!(spectrum.try_freq_val_exact(100.0).unwrap().val(), 100.0);
+        assert_eq!(spectrum.try_freq_val_exact(200.0).unwrap().val(), 50.0);
+        assert_eq!(spectrum.try_freq_val_exact(450.0).unwrap().val(), 200.0);
+        assert_eq!(spectrum.try_freq_val_closest(24.0).unwrap().0.val(), 0.0);
+        assert_eq!(spectrum.try_freq_val_closest(26.0).unwrap().0.val(), 50.0);
+        assert_eq!(spectrum.try_freq_val_closest(100.0).unwrap().0.val(), 100.0);
+        for fr in [0.0, 12.5, 50.0, 60.0, 299.0, 300.0, 449.0] {
+            assert_eq!(
+                spectrum.try_freq_val_exact(fr).unwrap(),
+                spectrum.freq_val_exact(fr)
+            );
+            assert_eq!(
+                spectrum.try_freq_val_closest(fr).unwrap(),
+                spectrum.freq_val_closest(fr)
+            );
+        }
+    }
+
     #[test]
     fn test_nan_safety() {
         let mut spectrum_vector: Vec<(Frequency, FrequencyValue)> =
@@ -980,6 +1300,39 @@ mod tests {
         )
     }
 
+    /// The statistics must match those of a sorted copy, including the
+    /// frequencies of equal minimum and maximum values.
+    #[test]
+    fn test_statistics_match_sorting() {
+        for len in [2, 3, 8, 33] {
+            let mut spectrum_vector = (0..len)
+                .map(|i| {
+                    let val = ((i * 7919) % 13) as f32;
+                    ((i as f32).into(), val.into())
+                })
+                .collect::<Vec<(Frequency, FrequencyValue)>>();
+            let spectrum = FrequencySpectrum::new(
+                spectrum_vector.clone(),
+                1.0,
+                len as _,
+                &mut spectrum_vector,
+            );
+
+            let mut sorted = spectrum.data().to_vec();
+            sorted.sort_by(|(l_fr, l_fr_val), (r_fr, r_fr_val)| {
+                l_fr_val.cmp(r_fr_val).then(l_fr.cmp(r_fr))
+            });
+            let median = if len % 2 == 0 {
+                (sorted[len / 2 - 1].1 + sorted[len / 2].1) / 2.0.into()
+            } else {
+                sorted[len / 2].1
+            };
+            assert_eq!(spectrum.min(), sorted[0], "{len}");
+            assert_eq!(spectrum.max(), sorted[len - 1], "{len}");
+            assert_eq!(spectrum.median(), median, "{len}");
+        }
+    }
+
     #[test]
     fn test_mel_getter() {
         let mut spectrum_vector = vec![
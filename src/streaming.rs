@@ -0,0 +1,401 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the struct [`StreamingAnalyzer`], which calculates spectra of a
+//! continuous stream of samples, e.g. inside a real-time audio callback.
+
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::{Complex32, DefaultFftBackend, Fft, FftBackend, FftImpl, MAX_FFT_LEN};
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::limit::FrequencyLimit;
+use crate::output::SpectrumOutput;
+use crate::scaling::ScalingPipeline;
+use crate::spectrum::FrequencySpectrum;
+use alloc::vec::Vec;
+
+/// Analyzes a continuous stream of samples.
+///
+/// Samples can be pushed in blocks of any size. The analyzer keeps the latest
+/// `fft_size` samples in a ring buffer and emits a windowed
+/// [`FrequencySpectrum`] each time `hop_size` new samples were collected.
+///
+/// All buffers are allocated and the FFT is prepared, e.g. planned, when the
+/// analyzer is created. Afterward, [`Self::push`] doesn't allocate, so it can
+/// run inside a real-time audio thread, as long as the optional
+/// [`ScalingPipeline`] doesn't allocate.
+///
+/// The FFTs are calculated with the [`DefaultFftBackend`], unless another
+/// [`FftBackend`] is set with [`Self::with_backend`].
+///
+/// ## Example
+/// ```rust
+/// use spectrum_analyzer::streaming::StreamingAnalyzer;
+/// use spectrum_analyzer::windows::hann_window;
+/// use spectrum_analyzer::FrequencyLimit;
+///
+/// // 2048 samples per FFT, a new spectrum every 512 samples
+/// let mut analyzer =
+///     StreamingAnalyzer::new(2048, 512, 48000.0, FrequencyLimit::All, hann_window).unwrap();
+///
+/// // inside the audio callback
+/// let block = [0.0; 480];
+/// analyzer
+///     .push(&block, |spectrum| {
+///         let _ = spectrum.max();
+///     })
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct StreamingAnalyzer<B: FftBackend = DefaultFftBackend> {
+    /// The FFT, prepared for `fft_size` samples.
+    fft: Fft<B>,
+    /// Number of samples of each FFT.
+    fft_size: usize,
+    /// Number of new samples between two spectra.
+    hop_size: usize,
+    /// The used sampling rate, e.g. `44100 [Hz]`.
+    sampling_rate: f32,
+    /// What the values of the spectrum represent.
+    output: SpectrumOutput,
+    /// Optional scaling of each spectrum.
+    scaling: Option<ScalingPipeline>,
+    /// Coefficients of the window function.
+    window: Vec<f32>,
+    /// The latest `fft_size` samples. The oldest sample is at `write_pos`.
+    ring_buffer: Vec<f32>,
+    /// Position of the next sample in `ring_buffer`.
+    write_pos: usize,
+    /// Number of samples until the next spectrum is emitted.
+    until_next: usize,
+    /// Buffer for the windowed samples and the in-place FFT.
+    fft_buffer: Vec<f32>,
+    /// Index of the first FFT result that is part of the spectrum.
+    first_fft_index: usize,
+    /// The spectrum that is updated on each hop.
+    spectrum: FrequencySpectrum,
+    /// Working buffer for the statistics of the spectrum.
+    working_buffer: Vec<(Frequency, FrequencyValue)>,
+}
+
+impl StreamingAnalyzer {
+    /// Creates a new analyzer and allocates all buffers.
+    ///
+    /// ## Parameters
+    /// * `fft_size` Number of samples of each FFT. Must be a power of two.
+    /// * `hop_size` Number of new samples between two spectra. Must not be
+    ///   zero.
+    /// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+    /// * `frequency_limit` The [`FrequencyLimit`].
+    /// * `window_fn` Window function that is applied to each FFT, e.g.
+    ///   [`crate::windows::hann_window`]. Its coefficients are calculated
+    ///   once.
+    pub fn new(
+        fft_size: usize,
+        hop_size: usize,
+        sampling_rate: f32,
+        frequency_limit: FrequencyLimit,
+        window_fn: fn(&[f32]) -> Vec<f32>,
+    ) -> Result<Self, SpectrumAnalyzerError> {
+        if fft_size < 2 || hop_size == 0 {
+            return Err(SpectrumAnalyzerError::TooFewSamples);
+        }
+        if !fft_size.is_power_of_two() {
+            return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+        }
+        if fft_size > MAX_FFT_LEN {
+            return Err(SpectrumAnalyzerError::TooManySamples);
+        }
+        crate::verify_sampling_rate(sampling_rate)?;
+        frequency_limit
+            .verify(sampling_rate / 2.0)
+            .map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+
+        // Creates the spectrum with all frequencies of the limit. Only the
+        // values are updated later.
+        let spectrum = crate::fft_result_to_spectrum(
+            fft_size,
+            &vec![Complex32::new(0.0, 0.0); fft_size / 2 + 1],
+            sampling_rate,
+            frequency_limit,
+            SpectrumOutput::Magnitude,
+            None,
+        )?;
+        let first_fft_index =
+            libm::roundf(spectrum.min_fr().val() / spectrum.frequency_resolution()) as usize;
+        let working_buffer = vec![(0.0.into(), 0.0.into()); spectrum.data().len()];
+        let mut fft = FftImpl::default();
+        fft.prepare(fft_size);
+
+        Ok(Self {
+            fft,
+            fft_size,
+            hop_size,
+            sampling_rate,
+            output: SpectrumOutput::Magnitude,
+            scaling: None,
+            window: window_fn(&vec![1.0; fft_size]),
+            ring_buffer: vec![0.0; fft_size],
+            write_pos: 0,
+            until_next: fft_size,
+            fft_buffer: vec![0.0; fft_size],
+            first_fft_index,
+            spectrum,
+            working_buffer,
+        })
+    }
+}
+
+impl<B: FftBackend> StreamingAnalyzer<B> {
+    /// Calculates the FFTs with the given backend instead of the
+    /// [`DefaultFftBackend`], e.g. to keep [`crate::fft::Microfft`] even if
+    /// the `rustfft` feature is enabled. The backend is prepared for
+    /// [`Self::fft_size`], so [`Self::push`] still doesn't allocate.
+    ///
+    /// Returns [`SpectrumAnalyzerError::TooManySamples`] if the backend
+    /// doesn't support FFTs of [`Self::fft_size`] samples.
+    pub fn with_backend<C: FftBackend>(
+        self,
+        backend: C,
+    ) -> Result<StreamingAnalyzer<C>, SpectrumAnalyzerError> {
+        if self.fft_size > C::MAX_LEN {
+            return Err(SpectrumAnalyzerError::TooManySamples);
+        }
+        let mut fft = Fft::new(backend);
+        fft.prepare(self.fft_size);
+        Ok(StreamingAnalyzer {
+            fft,
+            fft_size: self.fft_size,
+            hop_size: self.hop_size,
+            sampling_rate: self.sampling_rate,
+            output: self.output,
+            scaling: self.scaling,
+            window: self.window,
+            ring_buffer: self.ring_buffer,
+            write_pos: self.write_pos,
+            until_next: self.until_next,
+            fft_buffer: self.fft_buffer,
+            first_fft_index: self.first_fft_index,
+            spectrum: self.spectrum,
+            working_buffer: self.working_buffer,
+        })
+    }
+
+    /// Sets what the values of the emitted spectra represent.
+    pub fn with_output(mut self, output: SpectrumOutput) -> Result<Self, SpectrumAnalyzerError> {
+        output.verify()?;
+        self.output = output;
+        Ok(self)
+    }
+
+    /// Sets a scaling that is applied to each emitted spectrum.
+    #[must_use]
+    pub fn with_scaling(mut self, scaling: ScalingPipeline) -> Self {
+        self.scaling = Some(scaling);
+        self
+    }
+
+    /// Returns the number of samples of each FFT.
+    #[inline]
+    #[must_use]
+    pub const fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// Returns the number of new samples between two spectra.
+    #[inline]
+    #[must_use]
+    pub const fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// Returns the used sampling rate.
+    #[inline]
+    #[must_use]
+    pub const fn sampling_rate(&self) -> f32 {
+        self.sampling_rate
+    }
+
+    /// Forgets all pushed samples.
+    pub fn reset(&mut self) {
+        self.ring_buffer.iter_mut().for_each(|x| *x = 0.0);
+        self.write_pos = 0;
+        self.until_next = self.fft_size;
+    }
+
+    /// Pushes a block of samples of any size. `on_spectrum` is called for
+    /// each spectrum that is completed by the block, which might be none or
+    /// several. The first spectrum is emitted once `fft_size` samples were
+    /// pushed.
+    ///
+    /// ## Return value
+    /// Number of emitted spectra.
+    pub fn push(
+        &mut self,
+        samples: &[f32],
+        mut on_spectrum: impl FnMut(&FrequencySpectrum),
+    ) -> Result<usize, SpectrumAnalyzerError> {
+        if samples.iter().any(|x| x.is_nan()) {
+            return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+        }
+        if samples.iter().any(|x| x.is_infinite()) {
+            return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+        }
+
+        let mut emitted = 0;
+        let mut samples = samples;
+        while !samples.is_empty() {
+            let count = samples.len().min(self.until_next);
+            for sample in &samples[..count] {
+                self.ring_buffer[self.write_pos] = *sample;
+                self.write_pos = (self.write_pos + 1) % self.fft_size;
+            }
+            samples = &samples[count..];
+            self.until_next -= count;
+
+            if self.until_next == 0 {
+                self.until_next = self.hop_size;
+                self.update_spectrum()?;
+                on_spectrum(&self.spectrum);
+                emitted += 1;
+            }
+        }
+        Ok(emitted)
+    }
+
+    /// Calculates the spectrum of the samples in the ring buffer.
+    fn update_spectrum(&mut self) -> Result<(), SpectrumAnalyzerError> {
+        // oldest samples first
+        let (newer, older) = self.ring_buffer.split_at(self.write_pos);
+        for ((windowed, sample), coefficient) in self
+            .fft_buffer
+            .iter_mut()
+            .zip(older.iter().chain(newer))
+            .zip(&self.window)
+        {
+            *windowed = sample * coefficient;
+        }
+
+        let fft_res = self.fft.calc_in_place(&mut self.fft_buffer);
+        // `microfft::real` packs the real value of the Nyquist frequency
+        // inside the imaginary part of the DC component.
+        let nyquist = Complex32::new(fft_res[0].im, 0.0);
+        fft_res[0].im = 0.0;
+
+        let mut fft_index = self.first_fft_index;
+        let (fft_size, sampling_rate, output) = (self.fft_size, self.sampling_rate, self.output);
+        self.spectrum.map_values(
+            |_fr, _fr_val| {
+                let val = fft_res.get(fft_index).unwrap_or(&nyquist);
+                let converted = output.convert(val, fft_index, fft_size, sampling_rate);
+                fft_index += 1;
+                converted
+            },
+            &mut self.working_buffer,
+        )?;
+
+        if let Some(scaling) = &self.scaling {
+            scaling.apply(&mut self.spectrum, &mut self.working_buffer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::Microfft;
+    use crate::windows::hann_window;
+
+    #[test]
+    fn test_hops() {
+        let mut analyzer =
+            StreamingAnalyzer::new(8, 2, 8.0, FrequencyLimit::All, |s| s.to_vec()).unwrap();
+
+        // the first spectrum needs a full buffer
+        assert_eq!(analyzer.push(&[1.0; 7], |_| ()).unwrap(), 0);
+        assert_eq!(analyzer.push(&[1.0; 1], |_| ()).unwrap(), 1);
+        // afterward, a spectrum per hop
+        assert_eq!(analyzer.push(&[1.0; 1], |_| ()).unwrap(), 0);
+        assert_eq!(analyzer.push(&[1.0; 5], |_| ()).unwrap(), 3);
+
+        analyzer.reset();
+        assert_eq!(analyzer.push(&[1.0; 7], |_| ()).unwrap(), 0);
+
+        analyzer.push(&[f32::NAN], |_| ()).unwrap_err();
+        StreamingAnalyzer::new(6, 2, 8.0, FrequencyLimit::All, |s| s.to_vec()).unwrap_err();
+        StreamingAnalyzer::new(8, 0, 8.0, FrequencyLimit::All, |s| s.to_vec()).unwrap_err();
+    }
+
+    /// Each spectrum must be the same as the one of the corresponding samples.
+    #[test]
+    fn test_matches_samples_fft_to_spectrum() {
+        let samples = (0..256)
+            .map(|i| libm::sinf(i as f32 * 0.3) + libm::cosf(i as f32 * 0.05))
+            .collect::<Vec<_>>();
+        let limit = FrequencyLimit::Range(100.0, 900.0);
+        let mut analyzer = StreamingAnalyzer::new(64, 16, 2048.0, limit, hann_window).unwrap();
+
+        let mut spectra = Vec::new();
+        for block in samples.chunks(30) {
+            analyzer
+                .push(block, |spectrum| spectra.push(spectrum.data().to_vec()))
+                .unwrap();
+        }
+        assert_eq!(spectra.len(), (256 - 64) / 16 + 1);
+
+        for (i, spectrum) in spectra.iter().enumerate() {
+            let window = hann_window(&samples[i * 16..i * 16 + 64]);
+            let expected = crate::samples_fft_to_spectrum(&window, 2048, limit, None).unwrap();
+            assert_eq!(spectrum.len(), expected.data().len());
+            for ((fr, val), (expected_fr, expected_val)) in spectrum.iter().zip(expected.data()) {
+                assert_eq!(fr, expected_fr);
+                float_cmp::assert_approx_eq!(f32, val.val(), expected_val.val(), epsilon = 0.0001);
+            }
+        }
+    }
+
+    /// Another backend must result in the same spectra.
+    #[test]
+    fn test_backend() {
+        let samples = (0..256)
+            .map(|i| libm::sinf(i as f32 * 0.3) + libm::cosf(i as f32 * 0.05))
+            .collect::<Vec<_>>();
+        let new = || StreamingAnalyzer::new(64, 16, 2048.0, FrequencyLimit::All, hann_window);
+        let mut analyzer = new().unwrap();
+        let mut microfft = new().unwrap().with_backend(Microfft::default()).unwrap();
+
+        let (mut expected, mut spectra) = (Vec::new(), Vec::new());
+        analyzer
+            .push(&samples, |spectrum| expected.push(spectrum.data().to_vec()))
+            .unwrap();
+        microfft
+            .push(&samples, |spectrum| spectra.push(spectrum.data().to_vec()))
+            .unwrap();
+        assert_eq!(spectra.len(), expected.len());
+        for (spectrum, expected) in spectra.iter().zip(&expected) {
+            for ((_, val), (_, expected_val)) in spectrum.iter().zip(expected) {
+                float_cmp::assert_approx_eq!(f32, val.val(), expected_val.val(), epsilon = 0.0001);
+            }
+        }
+    }
+}
@@ -23,11 +23,17 @@ SOFTWARE.
 */
 //! Test module for "integration"-like tests. No small unit tests of simple functions.
 
+use crate::dtmf::{DtmfConfig, decode_dtmf};
 use crate::error::SpectrumAnalyzerError;
-use crate::scaling::{divide_by_N, scale_to_zero_to_one};
-use crate::tests::sine::sine_wave_audio_data_multiple;
+use crate::multichannel::{ChannelMix, interleaved_samples_fft_to_spectra};
+use crate::pcm::I24;
+use crate::scaling::{Decibel, DecibelReference, divide_by_N, scale_to_zero_to_one};
+use crate::tests::sine::{dual_tone_audio_data, sine_wave_audio_data_multiple};
 use crate::windows::{blackman_harris_4term, blackman_harris_7term, hamming_window, hann_window};
-use crate::{FrequencyLimit, samples_fft_to_spectrum};
+use crate::{
+    FrequencyLimit, FrequencyLookupError, Interpolation, SpectrumOutput, samples_fft_to_spectrum,
+    samples_fft_to_spectrum_with_output,
+};
 use alloc::vec::Vec;
 use audio_visualizer::Channels;
 use audio_visualizer::spectrum::plotters_png_file::spectrum_static_plotters_png_visualize;
@@ -52,7 +58,7 @@ fn test_out_dir() -> PathBuf {
     path
 }
 
-mod sine;
+pub(crate) mod sine;
 
 #[test]
 #[cfg_attr(miri, ignore)] // runs forever + no real value add
@@ -488,3 +494,440 @@ fn test_divide_by_n_has_effect() {
         );
     }
 }
+
+/// A sine at the center of a frequency bin must have the expected level in
+/// dBFS, independent of the number of samples.
+#[test]
+#[cfg_attr(miri, ignore)] // runs forever + no real value add
+fn test_dbfs_scaling() {
+    // 10 percent of full scale => -20 dBFS
+    let audio_data = sine_wave_audio_data_multiple(&[128.0], 1024, 2000);
+    let audio_data = audio_data.into_iter().map(|x| x as f32).collect::<Vec<_>>();
+    let dbfs = Decibel::dbfs(i16::MAX as f32, 0.5);
+
+    for samples_len in [512, 1024, 2048] {
+        let window = hann_window(&audio_data[0..samples_len]);
+        let spectrum = samples_fft_to_spectrum(
+            &window,
+            1024,
+            FrequencyLimit::All,
+            Some(&move |val, stats| dbfs.scale(val, stats)),
+        )
+        .unwrap();
+
+        let peak = spectrum.freq_val_exact(128.0).val();
+        assert!(
+            (peak - -20.0).abs() < 0.1,
+            "sine must be at -20 dBFS, but is at {peak} dBFS"
+        );
+        assert!(
+            spectrum.min().1.val() >= Decibel::DEFAULT_FLOOR,
+            "values must not be below the floor"
+        );
+    }
+}
+
+/// The power spectral density must be independent of the number of samples
+/// and its integral must equal the mean power of the signal (Parseval).
+#[test]
+#[cfg_attr(miri, ignore)] // runs forever + no real value add
+fn test_power_spectral_density() {
+    let audio_data = sine_wave_audio_data_multiple(&[100.0], 1000, 5000);
+    // normalize to [-1.0; 1.0]: amplitude is 0.1, mean power is 0.005
+    let audio_data = audio_data
+        .into_iter()
+        .map(|x| x as f32 / i16::MAX as f32)
+        .collect::<Vec<_>>();
+
+    for samples_len in [1024, 4096] {
+        let window = hann_window(&audio_data[0..samples_len]);
+        let window_energy = hann_window(&vec![1.0; samples_len])
+            .iter()
+            .map(|w| w * w)
+            .sum();
+        let psd = samples_fft_to_spectrum_with_output(
+            &window,
+            1000.0,
+            FrequencyLimit::All,
+            SpectrumOutput::PowerSpectralDensity { window_energy },
+            None,
+        )
+        .unwrap();
+
+        let mean_power =
+            psd.data().iter().map(|(_, val)| val.val()).sum::<f32>() * psd.frequency_resolution();
+        assert!(
+            (mean_power - 0.005).abs() < 0.0001,
+            "integral of PSD must be the mean power, but is {mean_power}"
+        );
+
+        let asd = samples_fft_to_spectrum_with_output(
+            &window,
+            1000.0,
+            FrequencyLimit::All,
+            SpectrumOutput::AmplitudeSpectralDensity { window_energy },
+            None,
+        )
+        .unwrap();
+        let (psd_peak, asd_peak) = (psd.max().1.val(), asd.max().1.val());
+        assert!((asd_peak * asd_peak - psd_peak).abs() / psd_peak < 0.001);
+    }
+
+    let err = samples_fft_to_spectrum_with_output(
+        &audio_data[0..1024],
+        1000.0,
+        FrequencyLimit::All,
+        SpectrumOutput::PowerSpectralDensity { window_energy: 0.0 },
+        None,
+    )
+    .unwrap_err();
+    assert!(matches!(err, SpectrumAnalyzerError::InvalidWindowEnergy(_)));
+}
+
+/// Each channel of a stereo recording must only contain its own tone, and
+/// mid/side must separate the correlated from the uncorrelated part.
+#[test]
+#[cfg_attr(miri, ignore)] // runs forever + no real value add
+fn test_stereo_channel_separation() {
+    let to_f32 = |data: Vec<i16>| data.into_iter().map(|x| x as f32).collect::<Vec<_>>();
+    let left = to_f32(sine_wave_audio_data_multiple(&[1000.0], 44100, 100));
+    let right = to_f32(sine_wave_audio_data_multiple(&[3000.0], 44100, 100));
+    let interleaved = left[0..4096]
+        .iter()
+        .zip(&right[0..4096])
+        .flat_map(|(l, r)| [*l, *r])
+        .collect::<Vec<_>>();
+
+    let spectra = interleaved_samples_fft_to_spectra(
+        &hann_window_interleaved(&interleaved),
+        2,
+        44100.0,
+        FrequencyLimit::All,
+        ChannelMix::Separate,
+        None,
+    )
+    .unwrap();
+    let (left_spectrum, right_spectrum) = (&spectra[0], &spectra[1]);
+    assert!((left_spectrum.max().0.val() - 1000.0).abs() < 11.0);
+    assert!((right_spectrum.max().0.val() - 3000.0).abs() < 11.0);
+    // crosstalk is at least 60 dB below the tone
+    assert!(left_spectrum.freq_val_closest(3000.0).1.val() < left_spectrum.max().1.val() * 0.001);
+    assert!(right_spectrum.freq_val_closest(1000.0).1.val() < right_spectrum.max().1.val() * 0.001);
+
+    // the left tone is in both channels, the right tone only in the right one
+    let mixed = left[0..4096]
+        .iter()
+        .zip(&right[0..4096])
+        .flat_map(|(l, r)| [*l, *l + *r])
+        .collect::<Vec<_>>();
+    let mid_side = interleaved_samples_fft_to_spectra(
+        &hann_window_interleaved(&mixed),
+        2,
+        44100.0,
+        FrequencyLimit::All,
+        ChannelMix::MidSide,
+        None,
+    )
+    .unwrap();
+    let side = &mid_side[1];
+    assert!((side.max().0.val() - 3000.0).abs() < 11.0);
+    assert!(side.freq_val_closest(1000.0).1.val() < side.max().1.val() * 0.001);
+}
+
+/// Applies the Hann window to each channel of interleaved stereo samples.
+fn hann_window_interleaved(samples: &[f32]) -> Vec<f32> {
+    let window = hann_window(&vec![1.0; samples.len() / 2]);
+    samples
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| sample * window[i / 2])
+        .collect()
+}
+
+/// Decodes a sequence of all DTMF digits with the tone and pause durations
+/// of a fast dialer.
+#[test]
+#[cfg_attr(miri, ignore)] // runs forever + no real value add
+fn test_dtmf_sequence() {
+    let keypad = ["123A", "456B", "789C", "*0#D"];
+    for sampling_rate in [8000, 44100] {
+        let mut samples = Vec::new();
+        let mut expected = Vec::new();
+        for (row, keys) in keypad.iter().enumerate() {
+            for (column, digit) in keys.chars().enumerate() {
+                let tones = [
+                    (crate::dtmf::DTMF_ROW_FREQUENCIES[row], 0.3),
+                    (crate::dtmf::DTMF_COLUMN_FREQUENCIES[column], 0.4),
+                ];
+                let start = samples.len() as f32 / sampling_rate as f32;
+                samples.extend(dual_tone_audio_data(tones, sampling_rate, 50));
+                let end = samples.len() as f32 / sampling_rate as f32;
+                expected.push((digit, start, end));
+                samples.extend(vec![0.0; sampling_rate as usize / 20]);
+            }
+        }
+
+        let digits = decode_dtmf(&samples, sampling_rate as f32, DtmfConfig::default()).unwrap();
+        assert_eq!(
+            digits
+                .iter()
+                .map(|d| d.digit())
+                .collect::<alloc::string::String>(),
+            "123A456B789C*0#D"
+        );
+        for (digit, (_, start, end)) in digits.iter().zip(expected) {
+            assert!(
+                (digit.start() - start).abs() < 0.005,
+                "{digit:?} != {start}"
+            );
+            assert!((digit.end() - end).abs() < 0.005, "{digit:?} != {end}");
+        }
+    }
+}
+
+/// Checks the ITU-T Q.24 rules for twist, frequency deviation, and minimum
+/// duration with the default config.
+#[test]
+#[cfg_attr(miri, ignore)] // runs forever + no real value add
+fn test_dtmf_rules() {
+    // digit "5"
+    let detects = |low: (f32, f32), high: (f32, f32), duration_ms| {
+        let mut samples = vec![0.0; 400];
+        samples.extend(dual_tone_audio_data([low, high], 8000, duration_ms));
+        samples.extend(vec![0.0; 400]);
+        let digits = decode_dtmf(&samples, 8000.0, DtmfConfig::default()).unwrap();
+        match digits.as_slice() {
+            [] => false,
+            [digit] if digit.digit() == '5' => true,
+            _ => panic!("unexpected digits: {digits:?}"),
+        }
+    };
+    let db = |x: f32| libm::powf(10.0, x / 20.0);
+
+    assert!(detects((770.0, 0.3), (1336.0, 0.3), 60));
+
+    // twist
+    assert!(detects((770.0, 0.1), (1336.0, 0.1 * db(6.0)), 60));
+    assert!(!detects((770.0, 0.1), (1336.0, 0.1 * db(10.0)), 60));
+    assert!(detects((770.0, 0.1 * db(3.0)), (1336.0, 0.1), 60));
+    assert!(!detects((770.0, 0.1 * db(6.0)), (1336.0, 0.1), 60));
+
+    // frequency deviation of each tone
+    for factor in [0.985, 1.015] {
+        assert!(detects((770.0 * factor, 0.3), (1336.0, 0.3), 60));
+        assert!(detects((770.0, 0.3), (1336.0 * factor, 0.3), 60));
+    }
+    for factor in [0.965, 1.035] {
+        assert!(!detects((770.0 * factor, 0.3), (1336.0, 0.3), 60));
+        assert!(!detects((770.0, 0.3), (1336.0 * factor, 0.3), 60));
+    }
+
+    // minimum duration
+    assert!(detects((770.0, 0.3), (1336.0, 0.3), 40));
+    assert!(!detects((770.0, 0.3), (1336.0, 0.3), 20));
+
+    // minimum level
+    assert!(!detects((770.0, 0.005), (1336.0, 0.005), 60));
+}
+
+/// Fractional sampling rates, e.g. of a calibrated DAQ clock or of slow
+/// sensors below 1 Hz, must not be truncated.
+#[test]
+fn test_fractional_sampling_rate() {
+    let sine = |frequency: f32, sampling_rate: f32, len: usize| {
+        let samples = (0..len)
+            .map(|i| libm::sinf(2.0 * core::f32::consts::PI * frequency * i as f32 / sampling_rate))
+            .collect::<Vec<_>>();
+        hann_window(&samples)
+    };
+    let spectrum = |samples: &[f32], sampling_rate: f32, limit: FrequencyLimit| {
+        samples_fft_to_spectrum_with_output(
+            samples,
+            sampling_rate,
+            limit,
+            SpectrumOutput::Magnitude,
+            None,
+        )
+    };
+
+    // vibration DAQ with a calibrated clock
+    let sampling_rate = 25_599.87;
+    let samples = sine(1000.3, sampling_rate, 4096);
+    let daq = spectrum(&samples, sampling_rate, FrequencyLimit::All).unwrap();
+    assert_eq!(daq.frequency_resolution(), sampling_rate / 4096.0);
+    assert!((daq.max_fr().val() - sampling_rate / 2.0).abs() < 0.001);
+    assert!((daq.max().0.val() - 1000.3).abs() < daq.frequency_resolution());
+    assert_eq!(
+        daq.freq_val_closest(1000.3).0,
+        daq.max().0,
+        "lookups must use the fractional frequency resolution"
+    );
+    // the Nyquist frequency is 12799.935 Hz and not 12799.5 Hz
+    spectrum(&samples, sampling_rate, FrequencyLimit::Min(12_799.9)).unwrap_err();
+    spectrum(&samples, sampling_rate, FrequencyLimit::Max(12_799.9)).unwrap();
+    assert!(matches!(
+        spectrum(&samples, sampling_rate, FrequencyLimit::Max(12_800.0)),
+        Err(SpectrumAnalyzerError::InvalidFrequencyLimit(_))
+    ));
+
+    // slow sensor with one sample every two seconds
+    let sampling_rate = 0.5;
+    let samples = sine(0.1, sampling_rate, 256);
+    let slow = spectrum(&samples, sampling_rate, FrequencyLimit::All).unwrap();
+    assert_eq!(slow.max_fr().val(), 0.25);
+    assert!((slow.max().0.val() - 0.1).abs() < slow.frequency_resolution());
+    let limited = spectrum(&samples, sampling_rate, FrequencyLimit::Range(0.05, 0.15)).unwrap();
+    assert_eq!(limited.max().0, slow.max().0);
+
+    // the u32 entry point is a wrapper
+    let samples = sine(1000.0, 44100.0, 1024);
+    assert_eq!(
+        samples_fft_to_spectrum(&samples, 44100, FrequencyLimit::All, None)
+            .unwrap()
+            .data(),
+        spectrum(&samples, 44100.0, FrequencyLimit::All)
+            .unwrap()
+            .data()
+    );
+
+    for sampling_rate in [0.0, -44100.0, f32::NAN, f32::INFINITY] {
+        assert!(matches!(
+            spectrum(&samples, sampling_rate, FrequencyLimit::All),
+            Err(SpectrumAnalyzerError::InvalidSamplingRate(_))
+        ));
+    }
+}
+
+/// 16 bit and 24 bit samples of the same sine must result in the same level
+/// in dBFS.
+#[test]
+fn test_pcm_samples_dbfs() {
+    // 1000 Hz is in the centre of a bin; the sine has 10% of full scale
+    let samples = sine_wave_audio_data_multiple(&[1000.0], 32768, 200);
+    let samples = &samples[..4096];
+    let samples_24bit = samples
+        .iter()
+        .map(|x| I24::from_i32(i32::from(*x) << 8))
+        .collect::<Vec<_>>();
+    let window = hann_window(&[1.0; 4096]);
+    let dbfs = Decibel::dbfs(1.0, 0.5);
+    let peak = |spectrum: crate::FrequencySpectrum| {
+        assert_eq!(spectrum.max().0.val(), 1000.0);
+        spectrum.max().1.val()
+    };
+
+    let level_16bit = peak(
+        crate::pcm::samples_fft_to_spectrum(
+            samples,
+            Some(&window),
+            32768.0,
+            FrequencyLimit::All,
+            SpectrumOutput::Magnitude,
+            Some(&move |val, stats| dbfs.scale(val, stats)),
+        )
+        .unwrap(),
+    );
+    let level_24bit = peak(
+        crate::pcm::samples_fft_to_spectrum(
+            &samples_24bit,
+            Some(&window),
+            32768.0,
+            FrequencyLimit::All,
+            SpectrumOutput::Magnitude,
+            Some(&move |val, stats| dbfs.scale(val, stats)),
+        )
+        .unwrap(),
+    );
+    assert!((level_16bit + 20.0).abs() < 0.01, "{level_16bit} dBFS");
+    assert_eq!(level_16bit, level_24bit);
+}
+
+/// More than 32768 samples require the `large-fft` or the `rustfft` feature.
+#[test]
+#[cfg_attr(miri, ignore)] // runs forever + no real value add
+fn test_large_fft() {
+    // 1 Hz is in the centre of a bin
+    let samples = (0..65536)
+        .map(|i| libm::sinf(2.0 * core::f32::consts::PI * i as f32 / 1024.0))
+        .collect::<Vec<_>>();
+    let res = samples_fft_to_spectrum(&samples, 1024, FrequencyLimit::All, None);
+
+    #[cfg(any(feature = "large-fft", feature = "rustfft"))]
+    {
+        let spectrum = res.unwrap();
+        assert_eq!(spectrum.data().len(), 32769);
+        assert_eq!(spectrum.frequency_resolution(), 1.0 / 64.0);
+        assert_eq!(spectrum.max().0.val(), 1.0);
+        let magnitude = spectrum.max().1.val();
+        assert!((magnitude - 32768.0).abs() < 1.0, "{magnitude}");
+    }
+    #[cfg(not(any(feature = "large-fft", feature = "rustfft")))]
+    assert!(matches!(res, Err(SpectrumAnalyzerError::TooManySamples)));
+}
+
+/// Between two bins, the interpolations must approximate the peak of a tone
+/// without a window better than the linear interpolation.
+#[test]
+fn test_interpolation_of_peak() {
+    // the tone is exactly between the bins of 100 Hz and 101 Hz
+    let samples = (0..1024)
+        .map(|i| libm::sinf(2.0 * core::f32::consts::PI * 100.5 * i as f32 / 1024.0))
+        .collect::<Vec<_>>();
+    let spectrum = samples_fft_to_spectrum(&samples, 1024, FrequencyLimit::All, None).unwrap();
+    let peak = |interpolation| spectrum.freq_val_interpolated(100.5, interpolation).val() / 512.0;
+
+    let linear = peak(Interpolation::Linear);
+    assert!((linear - 0.64).abs() < 0.01, "{linear}");
+    assert_eq!(
+        peak(Interpolation::Nearest),
+        spectrum.freq_val_closest(100.5).1.val() / 512.0
+    );
+    let cubic = peak(Interpolation::Cubic);
+    assert!(cubic > linear, "{cubic}");
+    let sinc = peak(Interpolation::Sinc);
+    assert!(sinc > linear && sinc < 1.0, "{sinc}");
+
+    // all interpolations keep the values of the bins
+    let grid = [0.0, 100.0, 101.0, 512.0];
+    for interpolation in [
+        Interpolation::Nearest,
+        Interpolation::Linear,
+        Interpolation::LinearDb,
+        Interpolation::Cubic,
+        Interpolation::Sinc,
+    ] {
+        let resampled = spectrum.resample(grid, interpolation).unwrap();
+        for (fr, val) in resampled {
+            assert_eq!(val, spectrum.freq_val_closest(fr.val()).1);
+        }
+    }
+    assert!(matches!(
+        spectrum.resample([100.0, 600.0], Interpolation::Sinc),
+        Err(FrequencyLookupError::OutOfBounds(600.0, 0.0, 512.0))
+    ));
+}
+
+/// The power of a full scale sine must be `0 dB` relative to the power of
+/// the sine reference.
+#[test]
+fn test_power_decibel_of_sine() {
+    // 1000 Hz is in the centre of a bin
+    let samples = (0..4096)
+        .map(|i| 0.5 * libm::sinf(2.0 * core::f32::consts::PI * 1000.0 * i as f32 / 32768.0))
+        .collect::<Vec<_>>();
+    let db = Decibel::power(DecibelReference::Sine {
+        amplitude: 0.5,
+        window_gain: 1.0,
+    });
+    let spectrum = samples_fft_to_spectrum_with_output(
+        &samples,
+        32768.0,
+        FrequencyLimit::All,
+        SpectrumOutput::Power,
+        Some(&move |val, stats| db.scale(val, stats)),
+    )
+    .unwrap();
+    assert_eq!(spectrum.max().0.val(), 1000.0);
+    let level = spectrum.max().1.val();
+    assert!(level.abs() < 0.01, "{level} dB");
+}
@@ -21,8 +21,9 @@ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
-//! Module for generating synthetic sine waves.
+//! Module for generating synthetic sine waves and noise.
 
+use crate::fft::Complex32;
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::f32::consts::PI;
@@ -102,3 +103,95 @@ pub fn sine_wave_audio_data_multiple(
 
     sine_wave
 }
+
+/// Creates a dual-tone signal, such as a DTMF digit, with a separate
+/// amplitude for each tone. The values are in interval `[-1; 1]` if the sum
+/// of both amplitudes is not bigger than `1`.
+///
+/// * `tones` frequency in Hz and amplitude of each tone
+/// * `sampling_rate` sampling rate, i.e. 8000Hz
+/// * `duration_ms` duration of the audio data in milliseconds
+pub fn dual_tone_audio_data(
+    tones: [(f32, f32); 2],
+    sampling_rate: u32,
+    duration_ms: u32,
+) -> Vec<f32> {
+    let [(low_fr, low_amplitude), (high_fr, high_amplitude)] = tones;
+    let (low, high) = (sine_wave(low_fr), sine_wave(high_fr));
+
+    let sample_count = (sampling_rate as f32 * (duration_ms as f32 / 1000.0)) as usize;
+    (0..sample_count)
+        .map(|i_sample| {
+            // t: time
+            let t = (1.0 / sampling_rate as f32) * i_sample as f32;
+            low(t) * low_amplitude + high(t) * high_amplitude
+        })
+        .collect()
+}
+
+/// Creates the sum of sine waves with an amplitude of `1` each.
+///
+/// * `frequencies` frequency in Hz of each sine wave
+/// * `sampling_rate` sampling rate, i.e. 8000Hz
+/// * `len` number of samples
+pub fn sines(frequencies: &[f32], sampling_rate: f32, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| {
+            frequencies
+                .iter()
+                .map(|fr| libm::sinf(2.0 * PI * fr * i as f32 / sampling_rate))
+                .sum()
+        })
+        .collect()
+}
+
+/// Creates a complex (IQ) tone, i.e. a phasor that rotates counterclockwise
+/// for positive and clockwise for negative frequencies.
+///
+/// * `frequency` frequency in Hz, can be negative
+/// * `amplitude` magnitude of each sample
+/// * `sampling_rate` sampling rate, i.e. 1024Hz
+/// * `len` number of samples
+pub fn complex_tone(
+    frequency: f32,
+    amplitude: f32,
+    sampling_rate: f32,
+    len: usize,
+) -> Vec<Complex32> {
+    (0..len)
+        .map(|i| {
+            let phase = 2.0 * PI * frequency * i as f32 / sampling_rate;
+            Complex32::new(amplitude * libm::cosf(phase), amplitude * libm::sinf(phase))
+        })
+        .collect()
+}
+
+/// Creates reproducible pseudo-random values in interval `[0; 1]`.
+pub fn random(len: usize, mut seed: u32) -> Vec<f64> {
+    (0..len)
+        .map(|_| {
+            // xorshift
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            f64::from(seed) / f64::from(u32::MAX)
+        })
+        .collect()
+}
+
+/// Creates reproducible white noise in interval `[-1; 1]`.
+pub fn uniform_noise(len: usize, seed: u32) -> Vec<f32> {
+    random(len, seed)
+        .iter()
+        .map(|x| (x * 2.0 - 1.0) as f32)
+        .collect()
+}
+
+/// Creates reproducible Gaussian-like noise with a standard deviation of
+/// about `1`.
+pub fn gaussian_noise(len: usize, seed: u32) -> Vec<f32> {
+    random(len * 12, seed)
+        .chunks(12)
+        .map(|chunk| (chunk.iter().sum::<f64>() - 6.0) as f32)
+        .collect()
+}
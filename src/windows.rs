@@ -26,10 +26,13 @@ SOFTWARE.
 //! - <https://en.wikipedia.org/wiki/Window_function>
 //! - <https://www.youtube.com/watch?v=dCeHOf4cJE0> (FFT and windowing by Texas Instruments)
 
+//!
+//! All window functions are generic over the floating point type, i.e. they
+//! work with the `f32` samples of the default API as well as with the `f64`
+//! samples of [`crate::double`].
+
 use alloc::vec::Vec;
-use core::f32::consts::PI;
-// replacement for std functions like sin and cos in no_std-environments
-use libm::cosf;
+use num_traits::{Float, FloatConst};
 
 /// Applies a Hann window (<https://en.wikipedia.org/wiki/Window_function#Hann_and_Hamming_windows>)
 /// to an array of samples.
@@ -37,14 +40,15 @@ use libm::cosf;
 /// ## Return value
 /// New vector with Hann window applied to the values.
 #[must_use]
-pub fn hann_window(samples: &[f32]) -> Vec<f32> {
+pub fn hann_window<T: Float + FloatConst>(samples: &[T]) -> Vec<T> {
     let mut windowed_samples = Vec::with_capacity(samples.len());
-    let samples_len_f32 = samples.len() as f32;
+    let samples_len = len_as_float::<T>(samples.len());
+    let half = crate::constant::<T>(0.5);
     for (i, sample) in samples.iter().enumerate() {
-        let two_pi_i = 2.0 * PI * i as f32;
-        let idontknowthename = cosf(two_pi_i / samples_len_f32);
-        let multiplier = 0.5 * (1.0 - idontknowthename);
-        windowed_samples.push(multiplier * sample)
+        let two_pi_i = T::TAU() * len_as_float(i);
+        let idontknowthename = (two_pi_i / samples_len).cos();
+        let multiplier = half * (T::one() - idontknowthename);
+        windowed_samples.push(multiplier * *sample)
     }
     windowed_samples
 }
@@ -55,17 +59,19 @@ pub fn hann_window(samples: &[f32]) -> Vec<f32> {
 /// ## Return value
 /// New vector with Hann window applied to the values.
 #[must_use]
-pub fn hamming_window(samples: &[f32]) -> Vec<f32> {
+pub fn hamming_window<T: Float + FloatConst>(samples: &[T]) -> Vec<T> {
     let mut windowed_samples = Vec::with_capacity(samples.len());
     if samples.len() <= 1 {
         windowed_samples.extend_from_slice(samples);
         return windowed_samples;
     }
 
-    let samples_len_f32 = samples.len() as f32;
+    let samples_len = len_as_float::<T>(samples.len());
+    let (alpha, beta) = (crate::constant::<T>(0.54), crate::constant::<T>(0.46));
     for (i, sample) in samples.iter().enumerate() {
-        let multiplier = 0.54 - (0.46 * cosf(2.0 * PI * i as f32 / (samples_len_f32 - 1.0)));
-        windowed_samples.push(multiplier * sample)
+        let multiplier =
+            alpha - (beta * (T::TAU() * len_as_float(i) / (samples_len - T::one())).cos());
+        windowed_samples.push(multiplier * *sample)
     }
     windowed_samples
 }
@@ -76,12 +82,12 @@ pub fn hamming_window(samples: &[f32]) -> Vec<f32> {
 /// ## Return value
 /// New vector with Blackman-Harris 4-term window applied to the values.
 #[must_use]
-pub fn blackman_harris_4term(samples: &[f32]) -> Vec<f32> {
+pub fn blackman_harris_4term<T: Float + FloatConst>(samples: &[T]) -> Vec<T> {
     // constants come from here:
     // https://en.wikipedia.org/wiki/Window_function#Blackman%E2%80%93Harris_window
-    const ALPHA: [f32; 4] = [0.35875, -0.48829, 0.14128, -0.01168];
+    const ALPHA: [f64; 4] = [0.35875, -0.48829, 0.14128, -0.01168];
 
-    blackman_harris_xterm(samples, &ALPHA)
+    blackman_harris_xterm(samples, ALPHA)
 }
 
 /// Applies a Blackman-Harris 7-term window to an array of samples.
@@ -94,20 +100,20 @@ pub fn blackman_harris_4term(samples: &[f32]) -> Vec<f32> {
 /// ## Return value
 /// New vector with Blackman-Harris 7-term window applied to the values.
 #[must_use]
-pub fn blackman_harris_7term(samples: &[f32]) -> Vec<f32> {
+pub fn blackman_harris_7term<T: Float + FloatConst>(samples: &[T]) -> Vec<T> {
     // constants come from here:
     // https://dsp.stackexchange.com/questions/51095/seven-term-blackman-harris-window
-    const ALPHA: [f32; 7] = [
-        0.271_051_4,
-        -0.433_297_93,
-        0.218_123,
-        -0.065_925_45,
-        0.010_811_742,
-        -0.000_776_584_84,
-        0.000_013_887_217,
+    const ALPHA: [f64; 7] = [
+        0.271_051_400_693_42,
+        -0.433_297_939_234_48,
+        0.218_122_999_543_11,
+        -0.065_925_446_388_03,
+        0.010_811_742_098_37,
+        -0.000_776_584_825_22,
+        0.000_013_887_217_35,
     ];
 
-    blackman_harris_xterm(samples, &ALPHA)
+    blackman_harris_xterm(samples, ALPHA)
 }
 
 /// Applies a Blackman-Harris x-term window
@@ -117,7 +123,10 @@ pub fn blackman_harris_7term(samples: &[f32]) -> Vec<f32> {
 /// ## Return value
 /// New vector with Blackman-Harris x-term window applied to the values.
 #[must_use]
-fn blackman_harris_xterm(samples: &[f32], alphas: &[f32]) -> Vec<f32> {
+fn blackman_harris_xterm<T: Float + FloatConst, const N: usize>(
+    samples: &[T],
+    alphas: [f64; N],
+) -> Vec<T> {
     let mut windowed_samples = Vec::with_capacity(samples.len());
 
     if samples.len() <= 1 {
@@ -125,30 +134,37 @@ fn blackman_harris_xterm(samples: &[f32], alphas: &[f32]) -> Vec<f32> {
         return windowed_samples;
     }
 
-    let samples_len_f32 = samples.len() as f32;
+    let samples_len = len_as_float::<T>(samples.len());
+    let alphas = alphas.map(crate::constant::<T>);
 
     for (i, sample) in samples.iter().enumerate() {
         // Will result in something like that:
         /* ALPHA0
-            + ALPHA1 * ((2.0 * PI * i)/(samples_len_f32 - 1.0)).cos()
-            + ALPHA2 * ((4.0 * PI * i)/(samples_len_f32 - 1.0)).cos()
-            + ALPHA3 * ((6.0 * PI * i)/(samples_len_f32 - 1.0)).cos()
+            + ALPHA1 * ((2.0 * PI * i)/(samples_len - 1.0)).cos()
+            + ALPHA2 * ((4.0 * PI * i)/(samples_len - 1.0)).cos()
+            + ALPHA3 * ((6.0 * PI * i)/(samples_len - 1.0)).cos()
         */
 
-        let mut acc = 0.0;
+        let mut acc = T::zero();
         for (alpha_i, alpha) in alphas.iter().enumerate() {
             // in 1. iter. 0PI, then 2PI, then 4 PI, then 6 PI
-            let two_pi_iteration = 2.0 * alpha_i as f32 * PI;
-            let cos = cosf((two_pi_iteration * i as f32) / (samples_len_f32 - 1.0));
-            acc += alpha * cos;
+            let two_pi_iteration = T::TAU() * len_as_float(alpha_i);
+            let cos = ((two_pi_iteration * len_as_float(i)) / (samples_len - T::one())).cos();
+            acc = acc + *alpha * cos;
         }
 
-        windowed_samples.push(acc * sample)
+        windowed_samples.push(acc * *sample)
     }
 
     windowed_samples
 }
 
+/// Converts a length or an index into the floating point type `T`.
+#[inline]
+fn len_as_float<T: Float>(len: usize) -> T {
+    T::from(len).expect("length is representable")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,4 +188,20 @@ mod tests {
             float_cmp::assert_approx_eq!(f32, *actual, expected, epsilon = 0.00001);
         }
     }
+
+    fn assert_f64_matches_f32(window: fn(&[f64]) -> Vec<f64>, window_f32: fn(&[f32]) -> Vec<f32>) {
+        let samples = [0.5, -1.0, 2.0, 0.25, 1.0, -0.75, 0.1, 3.0];
+        let expected = window_f32(&samples.map(|x| x as f32));
+        for (actual, expected) in window(&samples).iter().zip(expected) {
+            float_cmp::assert_approx_eq!(f64, *actual, f64::from(expected), epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_f64_matches_f32() {
+        assert_f64_matches_f32(hann_window, hann_window);
+        assert_f64_matches_f32(hamming_window, hamming_window);
+        assert_f64_matches_f32(blackman_harris_4term, blackman_harris_4term);
+        assert_f64_matches_f32(blackman_harris_7term, blackman_harris_7term);
+    }
 }
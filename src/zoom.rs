@@ -0,0 +1,293 @@
+/*
+MIT License
+
+Copyright (c) 2023 Philipp Schuster
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+//! Module for the zoom FFT, which calculates a narrow frequency range of a
+//! long signal with a high frequency resolution. See
+//! [`samples_zoom_fft_to_spectrum`].
+
+use crate::error::SpectrumAnalyzerError;
+use crate::fft::{Complex32, DefaultFftBackend, Fft, FftBackend};
+use crate::frequency::{Frequency, FrequencyValue};
+use crate::limit::FrequencyLimit;
+use crate::scaling::SpectrumScalingFunction;
+use crate::spectrum::FrequencySpectrum;
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+
+/// Like [`crate::samples_fft_to_spectrum`] but only calculates the
+/// frequencies of the [`FrequencyLimit`], with the frequency resolution of
+/// an FFT of all samples, i.e. `sampling_rate / samples.len()`.
+///
+/// A frequency resolution of `0.1 Hz` thus still requires ten seconds of
+/// samples, but the number of samples is not limited by the maximum FFT
+/// length of the crate, and the work is spent on the chosen range. For
+/// example, 2^19 samples at 48 kHz result in a resolution of `0.09 Hz`.
+///
+/// The samples are split into `D` interleaved sub-sequences, whose FFTs are
+/// combined for the requested frequencies only (decimation in time). The
+/// result equals the corresponding frequencies of a full FFT, without the
+/// aliasing of a zoom FFT with mixing and decimation filters.
+///
+/// ## Parameters
+/// * `samples` Array with samples. Each value must be a regular floating
+///   point number (no NaN or infinite) and the length must be a power of
+///   two. Apply a window function beforehand, if necessary.
+/// * `sampling_rate` The used sampling_rate, e.g. `44100 [Hz]`.
+/// * `frequency_limit` The frequency range of the spectrum, usually a
+///   [`FrequencyLimit::Range`].
+/// * `scaling_fn` See [`crate::samples_fft_to_spectrum`].
+///
+/// ## Examples
+/// ```rust
+/// use spectrum_analyzer::zoom::samples_zoom_fft_to_spectrum;
+/// use spectrum_analyzer::windows::hann_window;
+/// use spectrum_analyzer::FrequencyLimit;
+/// // 10.9 seconds of samples from the power grid
+/// let samples = hann_window(&vec![0.0; 524288]);
+/// let spectrum = samples_zoom_fft_to_spectrum(
+///     &samples,
+///     48000.0,
+///     FrequencyLimit::Range(45.0, 55.0),
+///     None,
+/// )
+/// .unwrap();
+/// assert!(spectrum.frequency_resolution() < 0.1);
+/// ```
+pub fn samples_zoom_fft_to_spectrum(
+    samples: &[f32],
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    samples_zoom_fft_to_spectrum_with_backend::<DefaultFftBackend>(
+        samples,
+        sampling_rate,
+        frequency_limit,
+        scaling_fn,
+    )
+}
+
+/// Like [`samples_zoom_fft_to_spectrum`] but calculates the FFTs of the
+/// sub-sequences with the given [`FftBackend`] instead of the
+/// [`DefaultFftBackend`].
+pub fn samples_zoom_fft_to_spectrum_with_backend<B: FftBackend>(
+    samples: &[f32],
+    sampling_rate: f32,
+    frequency_limit: FrequencyLimit,
+    scaling_fn: Option<&SpectrumScalingFunction>,
+) -> Result<FrequencySpectrum, SpectrumAnalyzerError> {
+    if samples.len() < 2 {
+        return Err(SpectrumAnalyzerError::TooFewSamples);
+    }
+    if samples.iter().any(|x| x.is_nan()) {
+        return Err(SpectrumAnalyzerError::NaNValuesNotSupported);
+    }
+    if samples.iter().any(|x| x.is_infinite()) {
+        return Err(SpectrumAnalyzerError::InfinityValuesNotSupported);
+    }
+    if !samples.len().is_power_of_two() {
+        return Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo);
+    }
+    crate::verify_sampling_rate(sampling_rate)?;
+    frequency_limit
+        .verify(sampling_rate / 2.0)
+        .map_err(SpectrumAnalyzerError::InvalidFrequencyLimit)?;
+
+    let samples_len = samples.len();
+    let frequency_resolution = sampling_rate / samples_len as f32;
+    // indices of the requested frequencies in a full FFT, inclusive
+    let first_index = frequency_limit
+        .maybe_min()
+        .map_or(0, |min| libm::ceilf(min / frequency_resolution) as usize);
+    let last_index = frequency_limit
+        .maybe_max()
+        .map_or(samples_len / 2, |max| {
+            libm::floorf(max / frequency_resolution) as usize
+        })
+        .min(samples_len / 2);
+    if last_index < first_index + 1 {
+        return Err(SpectrumAnalyzerError::FrequencyLimitTooNarrow);
+    }
+    let bins = last_index - first_index + 1;
+
+    // With `N = D * K`, the FFT of all samples is
+    // `X[k] = sum(r = 0..D) e^(-j2πkr/N) * Y_r[k mod K]`, where `Y_r` is
+    // the FFT of the sub-sequence `x[r], x[r + D], x[r + 2D], ...`.
+    let fft_len = bins
+        .next_power_of_two()
+        .clamp(2, B::MAX_LEN)
+        .min(samples_len);
+    let decimation = samples_len / fft_len;
+
+    let mut fft = Fft::<B>::default();
+    let mut sub_sequence = vec![0.0; fft_len];
+    let mut result = vec![Complex32::new(0.0, 0.0); bins];
+    for r in 0..decimation {
+        for (m, sample) in sub_sequence.iter_mut().enumerate() {
+            *sample = samples[m * decimation + r];
+        }
+        // `K / 2 + 1` values, the others are mirrored
+        let sub_fft = fft.calc(&sub_sequence);
+
+        for (i, val) in result.iter_mut().enumerate() {
+            let k = first_index + i;
+            let j = k % fft_len;
+            let y = if j <= fft_len / 2 {
+                sub_fft[j]
+            } else {
+                sub_fft[fft_len - j].conj()
+            };
+            // exact phase in integers, to not lose precision for big N
+            let twiddle_index = (k * r) % samples_len;
+            let angle = -2.0 * PI * twiddle_index as f32 / samples_len as f32;
+            *val += y * Complex32::new(libm::cosf(angle), libm::sinf(angle));
+        }
+    }
+
+    let data = result
+        .iter()
+        .enumerate()
+        .map(|(i, val)| {
+            let fr = (first_index + i) as f32 * frequency_resolution;
+            let magnitude = libm::sqrtf(val.norm_sqr());
+            (Frequency::from(fr), FrequencyValue::from(magnitude))
+        })
+        .collect::<Vec<_>>();
+
+    let mut working_buffer = vec![(0.0.into(), 0.0.into()); data.len()];
+    let mut spectrum = FrequencySpectrum::new(
+        data,
+        frequency_resolution,
+        samples_len as u32,
+        &mut working_buffer,
+    );
+    if let Some(scaling_fn) = scaling_fn {
+        spectrum.apply_scaling_fn(scaling_fn, &mut working_buffer)?
+    }
+    Ok(spectrum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fft::Microfft;
+    use crate::samples_fft_to_spectrum;
+    use crate::windows::hann_window;
+
+    /// The zoomed frequencies must equal the ones of a full FFT.
+    #[test]
+    fn test_matches_full_fft() {
+        let samples = hann_window(
+            &(0..16384)
+                .map(|i| libm::sinf(i as f32 * 0.07) + 0.3 * libm::sinf(i as f32 * 0.9))
+                .collect::<Vec<_>>(),
+        );
+        // rounding errors of f32 relative to the highest peak
+        let tolerance = samples_fft_to_spectrum(&samples, 44100, FrequencyLimit::All, None)
+            .unwrap()
+            .max()
+            .1
+            .val()
+            * 0.00001;
+        for limit in [
+            FrequencyLimit::Range(400.0, 480.0),
+            FrequencyLimit::Range(2000.0, 6000.0),
+            FrequencyLimit::Max(100.0),
+            FrequencyLimit::All,
+        ] {
+            let zoomed = samples_zoom_fft_to_spectrum(&samples, 44100.0, limit, None).unwrap();
+            let full = samples_fft_to_spectrum(&samples, 44100, limit, None).unwrap();
+            assert_eq!(zoomed.frequency_resolution(), full.frequency_resolution());
+            assert_eq!(zoomed.data().len(), full.data().len());
+            for ((fr, val), (expected_fr, expected_val)) in zoomed.data().iter().zip(full.data()) {
+                assert_eq!(fr, expected_fr);
+                assert!((val.val() - expected_val.val()).abs() < tolerance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_backend() {
+        let samples = hann_window(
+            &(0..4096)
+                .map(|i| libm::sinf(i as f32 * 0.07) + 0.3 * libm::sinf(i as f32 * 0.9))
+                .collect::<Vec<_>>(),
+        );
+        let limit = FrequencyLimit::Range(400.0, 1200.0);
+        let expected = samples_zoom_fft_to_spectrum(&samples, 44100.0, limit, None).unwrap();
+        let spectrum =
+            samples_zoom_fft_to_spectrum_with_backend::<Microfft>(&samples, 44100.0, limit, None)
+                .unwrap();
+        assert_eq!(spectrum.data().len(), expected.data().len());
+        for ((fr, val), (expected_fr, expected_val)) in spectrum.data().iter().zip(expected.data())
+        {
+            assert_eq!(fr, expected_fr);
+            float_cmp::assert_approx_eq!(f32, val.val(), expected_val.val(), epsilon = 0.001);
+        }
+    }
+
+    /// Resolves two mains harmonics that are 0.2 Hz apart.
+    #[test]
+    #[cfg_attr(miri, ignore)] // runs forever + no real value add
+    fn test_high_resolution() {
+        let sampling_rate = 4000.0;
+        let samples = hann_window(
+            &(0..65536)
+                .map(|i| {
+                    let t = i as f32 / sampling_rate;
+                    libm::sinf(2.0 * PI * 50.0 * t) + libm::sinf(2.0 * PI * 50.2 * t)
+                })
+                .collect::<Vec<_>>(),
+        );
+        let spectrum = samples_zoom_fft_to_spectrum(
+            &samples,
+            sampling_rate,
+            FrequencyLimit::Range(49.0, 51.0),
+            None,
+        )
+        .unwrap();
+        assert!(spectrum.frequency_resolution() < 0.1);
+        assert!(spectrum.data().len() < 64);
+
+        let at = |fr: f32| spectrum.freq_val_closest(fr).1.val();
+        assert!(at(50.1) < at(50.0) * 0.7, "peaks must be separated");
+        assert!(at(50.1) < at(50.2) * 0.7, "peaks must be separated");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let samples = [0.0; 64];
+        assert!(matches!(
+            samples_zoom_fft_to_spectrum(&samples[..48], 64.0, FrequencyLimit::All, None),
+            Err(SpectrumAnalyzerError::SamplesLengthNotAPowerOfTwo)
+        ));
+        assert!(matches!(
+            samples_zoom_fft_to_spectrum(&samples, 64.0, FrequencyLimit::Range(10.2, 10.8), None),
+            Err(SpectrumAnalyzerError::FrequencyLimitTooNarrow)
+        ));
+        assert!(matches!(
+            samples_zoom_fft_to_spectrum(&samples, 64.0, FrequencyLimit::Max(40.0), None),
+            Err(SpectrumAnalyzerError::InvalidFrequencyLimit(_))
+        ));
+    }
+}